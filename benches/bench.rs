@@ -37,11 +37,7 @@ fn compose_lidar_points_msg() -> LidarPointsMsg {
     };
     let points = (0..10_000)
         .map(|_| LidarPoint {
-            position: [
-                std::f32::EPSILON,
-                std::f32::EPSILON * 1.,
-                std::f32::EPSILON * 2.,
-            ],
+            position: [f32::EPSILON, f32::EPSILON * 1., f32::EPSILON * 2.],
             intensity: 7,
         })
         .collect::<Vec<_>>();
@@ -71,6 +67,19 @@ fn lidar_point_msg_without_encapsulation(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn lidar_point_msg_into_vec(b: &mut Bencher) {
+    use cdr::{self, CdrBe, Infinite};
+
+    let msg = compose_lidar_points_msg();
+    let mut encoded = Vec::new();
+    b.iter(|| {
+        encoded.clear();
+        cdr::serialize_into_vec::<_, _, CdrBe>(&mut encoded, &msg, Infinite).unwrap();
+        let _decoded = cdr::deserialize::<LidarPointsMsg>(&encoded[..]).unwrap();
+    });
+}
+
 fn compose_string_msg() -> String {
     r#"What's he that wishes so?
 My cousin Westmoreland? No, my fair cousin:
@@ -147,3 +156,40 @@ fn string_msg_without_encapsulation(b: &mut Bencher) {
         let _decoded = cdr::de::deserialize_data::<String, BigEndian>(&encoded[..]).unwrap();
     });
 }
+
+#[derive(Serialize, Deserialize)]
+struct PointCloudMsg {
+    points: Vec<f32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BulkPointCloudMsg {
+    #[serde(with = "cdr::bulk")]
+    points: Vec<f32>,
+}
+
+#[bench]
+fn point_cloud_msg(b: &mut Bencher) {
+    use cdr::{self, CdrLe, Infinite};
+
+    let msg = PointCloudMsg {
+        points: vec![f32::EPSILON; 30_000],
+    };
+    b.iter(|| {
+        let encoded = cdr::serialize::<_, _, CdrLe>(&msg, Infinite).unwrap();
+        let _decoded = cdr::deserialize::<PointCloudMsg>(&encoded[..]).unwrap();
+    });
+}
+
+#[bench]
+fn point_cloud_msg_bulk(b: &mut Bencher) {
+    use cdr::{self, CdrLe, Infinite};
+
+    let msg = BulkPointCloudMsg {
+        points: vec![f32::EPSILON; 30_000],
+    };
+    b.iter(|| {
+        let encoded = cdr::serialize::<_, _, CdrLe>(&msg, Infinite).unwrap();
+        let _decoded = cdr::deserialize::<BulkPointCloudMsg>(&encoded[..]).unwrap();
+    });
+}
@@ -0,0 +1,267 @@
+//! Derive macros for the `cdr` crate.
+//!
+//! These are re-exported by `cdr` when its `derive` feature is enabled.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{ext::IdentExt, parse_macro_input, parse_quote, Data, DeriveInput, Fields};
+
+/// Derives `cdr::CdrFixedSize` for a struct or an enum.
+///
+/// The fields are assumed to be serialized in declaration order, as they are
+/// by `#[derive(Serialize)]`.
+#[proc_macro_derive(CdrFixedSize)]
+pub fn derive_cdr_fixed_size(input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input as DeriveInput);
+
+    if let Data::Union(_) = input.data {
+        return syn::Error::new_spanned(&input.ident, "unions are not supported")
+            .to_compile_error()
+            .into();
+    }
+    let layout = data_layout(&input.data, &quote!(LAYOUT));
+    let layout_xcdr2 = data_layout(&input.data, &quote!(LAYOUT_XCDR2));
+
+    let type_params = input
+        .generics
+        .type_params()
+        .map(|p| p.ident.clone())
+        .collect::<Vec<_>>();
+    let where_clause = input.generics.make_where_clause();
+    for param in type_params {
+        where_clause
+            .predicates
+            .push(parse_quote!(#param: ::cdr::CdrFixedSize));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let expanded = quote! {
+        impl #impl_generics ::cdr::CdrFixedSize for #name #ty_generics #where_clause {
+            const LAYOUT: ::cdr::size::Layout = #layout;
+            const LAYOUT_XCDR2: ::cdr::size::Layout = #layout_xcdr2;
+        }
+    };
+    expanded.into()
+}
+
+/// Returns the layout of a struct or an enum, built from the `constant`
+/// layouts of its fields.
+fn data_layout(data: &Data, constant: &TokenStream2) -> TokenStream2 {
+    match *data {
+        Data::Enum(ref data) => {
+            let mut variants = data
+                .variants
+                .iter()
+                .map(|v| fields_layout(&v.fields, constant));
+            match variants.next() {
+                Some(first) => quote! {
+                    ::cdr::size::Layout::primitive(4).then(#first #(.or(#variants))*)
+                },
+                None => quote!(::cdr::size::Layout::EMPTY),
+            }
+        }
+        Data::Struct(ref data) => fields_layout(&data.fields, constant),
+        Data::Union(_) => unreachable!(),
+    }
+}
+
+fn fields_layout(fields: &Fields, constant: &TokenStream2) -> TokenStream2 {
+    let types = fields.iter().map(|f| &f.ty);
+    quote! {
+        ::cdr::size::Layout::EMPTY
+            #(.then(<#types as ::cdr::CdrFixedSize>::#constant))*
+    }
+}
+
+/// Derives `cdr::Described` for a struct or an enum.
+///
+/// A struct is described by its fields in declaration order, and an enum by a
+/// union of its variants selected by their index, as they are serialized by
+/// `#[derive(Serialize)]`.
+#[proc_macro_derive(Described)]
+pub fn derive_described(input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input as DeriveInput);
+
+    let type_name = input.ident.unraw().to_string();
+    let descriptor = match input.data {
+        Data::Struct(ref data) => fields_descriptor(&type_name, &data.fields),
+        Data::Enum(ref data) => {
+            let variants = data.variants.iter().map(|v| {
+                let name = v.ident.unraw().to_string();
+                let ty = match v.fields {
+                    Fields::Unit => quote!(::cdr::TypeDescriptor::Unit),
+                    Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+                        let ty = &fields.unnamed[0].ty;
+                        quote!(<#ty as ::cdr::Described>::descriptor())
+                    }
+                    ref fields => fields_descriptor(&name, fields),
+                };
+                quote!(::cdr::value::Member::new(#name, #ty))
+            });
+            quote! {
+                ::cdr::TypeDescriptor::Union(::cdr::value::UnionDescriptor::from_variants(
+                    #type_name,
+                    vec![#(#variants),*],
+                ))
+            }
+        }
+        Data::Union(_) => {
+            return syn::Error::new_spanned(&input.ident, "unions are not supported")
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    let type_params = input
+        .generics
+        .type_params()
+        .map(|p| p.ident.clone())
+        .collect::<Vec<_>>();
+    let where_clause = input.generics.make_where_clause();
+    for param in type_params {
+        where_clause
+            .predicates
+            .push(parse_quote!(#param: ::cdr::Described));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let expanded = quote! {
+        impl #impl_generics ::cdr::Described for #name #ty_generics #where_clause {
+            fn descriptor() -> ::cdr::TypeDescriptor {
+                #descriptor
+            }
+        }
+    };
+    expanded.into()
+}
+
+/// Returns the descriptor of a struct with the given fields.
+fn fields_descriptor(name: &str, fields: &Fields) -> TokenStream2 {
+    let names = fields.iter().enumerate().map(|(i, f)| match f.ident {
+        Some(ref ident) => ident.unraw().to_string(),
+        None => i.to_string(),
+    });
+    let types = fields.iter().map(|f| &f.ty);
+    quote! {
+        ::cdr::TypeDescriptor::Struct(::cdr::value::StructDescriptor {
+            name: #name.to_string(),
+            members: vec![
+                #(::cdr::value::Member::new(#names, <#types as ::cdr::Described>::descriptor())),*
+            ],
+        })
+    }
+}
+
+/// Derives `cdr::Keyed` for a struct or an enum without fields.
+///
+/// The key members of a struct are the fields marked with `#[key]`, or all of
+/// its fields if none is marked. An enum is a key as a whole.
+#[proc_macro_derive(Keyed, attributes(key))]
+pub fn derive_keyed(input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input as DeriveInput);
+
+    let (layout, layout_xcdr2, body) = match input.data {
+        Data::Struct(ref data) => {
+            let marked = data
+                .fields
+                .iter()
+                .any(|f| f.attrs.iter().any(|a| a.path().is_ident("key")));
+            let keys = data
+                .fields
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| !marked || f.attrs.iter().any(|a| a.path().is_ident("key")))
+                .map(|(i, f)| {
+                    let member = match f.ident {
+                        Some(ref ident) => quote!(#ident),
+                        None => {
+                            let index = syn::Index::from(i);
+                            quote!(#index)
+                        }
+                    };
+                    (&f.ty, member)
+                })
+                .collect::<Vec<_>>();
+            let types = keys.iter().map(|(ty, _)| ty).collect::<Vec<_>>();
+            let members = keys.iter().map(|(_, member)| member);
+            let len = keys.len();
+            (
+                quote! {
+                    ::cdr::size::Layout::EMPTY
+                        #(.then(<#types as ::cdr::Keyed>::KEY_LAYOUT))*
+                },
+                quote! {
+                    ::cdr::size::Layout::EMPTY
+                        #(.then(<#types as ::cdr::Keyed>::KEY_LAYOUT_XCDR2))*
+                },
+                quote! {
+                    let mut tuple = ::cdr::key::__private::Serializer::serialize_tuple(serializer, #len)?;
+                    #(
+                        ::cdr::key::__private::SerializeTuple::serialize_element(
+                            &mut tuple,
+                            &::cdr::key::KeyHolder(&self.#members),
+                        )?;
+                    )*
+                    ::cdr::key::__private::SerializeTuple::end(tuple)
+                },
+            )
+        }
+        Data::Enum(ref data) => {
+            if let Some(v) = data.variants.iter().find(|v| !v.fields.is_empty()) {
+                return syn::Error::new_spanned(
+                    &v.ident,
+                    "only enums without fields are supported",
+                )
+                .to_compile_error()
+                .into();
+            }
+            (
+                quote!(::cdr::size::Layout::primitive(4)),
+                quote!(::cdr::size::Layout::primitive(4)),
+                quote!(::cdr::key::__private::Serialize::serialize(
+                    self, serializer
+                )),
+            )
+        }
+        Data::Union(_) => {
+            return syn::Error::new_spanned(&input.ident, "unions are not supported")
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    let type_params = input
+        .generics
+        .type_params()
+        .map(|p| p.ident.clone())
+        .collect::<Vec<_>>();
+    let where_clause = input.generics.make_where_clause();
+    for param in type_params {
+        where_clause
+            .predicates
+            .push(parse_quote!(#param: ::cdr::Keyed));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let expanded = quote! {
+        impl #impl_generics ::cdr::Keyed for #name #ty_generics #where_clause {
+            const KEY_LAYOUT: ::cdr::size::Layout = #layout;
+            const KEY_LAYOUT_XCDR2: ::cdr::size::Layout = #layout_xcdr2;
+
+            fn serialize_key<S>(
+                &self,
+                serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: ::cdr::key::__private::Serializer,
+            {
+                #body
+            }
+        }
+    };
+    expanded.into()
+}
@@ -0,0 +1,255 @@
+#![allow(clippy::assertions_on_constants)]
+
+use cdr::{CdrFixedSize, Described, Keyed};
+use serde_derive::{Deserialize, Serialize};
+
+fn check<T>(value: &T)
+where
+    T: CdrFixedSize + serde::Serialize,
+{
+    assert!(T::IS_BOUNDED);
+    assert_eq!(
+        T::MAX_SIZE as u64,
+        cdr::size::calc_serialized_data_size(value)
+    );
+}
+
+#[derive(CdrFixedSize, Serialize)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[derive(CdrFixedSize, Serialize)]
+struct Header {
+    kind: u8,
+    stamp: u64,
+    flag: bool,
+}
+
+#[derive(CdrFixedSize, Serialize)]
+struct Pose {
+    header: Header,
+    position: [Point; 2],
+    id: u16,
+}
+
+#[derive(CdrFixedSize, Serialize)]
+struct Wrapper<T>(u8, T);
+
+#[derive(CdrFixedSize, Serialize)]
+struct Unit;
+
+#[derive(CdrFixedSize, Serialize)]
+enum Shape {
+    Dot,
+    Circle(u8, f64),
+    Box { a: u16, b: u32 },
+}
+
+#[derive(CdrFixedSize, Serialize)]
+struct Named {
+    id: u32,
+    name: String,
+}
+
+#[test]
+fn test_struct() {
+    check(&Point { x: 1.0, y: 2.0 });
+    check(&Header {
+        kind: 1,
+        stamp: 2,
+        flag: true,
+    });
+    check(&Pose {
+        header: Header {
+            kind: 1,
+            stamp: 2,
+            flag: true,
+        },
+        position: [Point { x: 1.0, y: 2.0 }, Point { x: 3.0, y: 4.0 }],
+        id: 5,
+    });
+    assert_eq!(Pose::MAX_SIZE, 24 + 32 + 2);
+    check(&Wrapper(1, 2u64));
+    check(&Wrapper(1, (2u8, 3u32)));
+    check(&Unit);
+}
+
+#[test]
+fn test_enum() {
+    assert!(Shape::IS_BOUNDED);
+    assert_eq!(Shape::MAX_SIZE, 4 + 1 + 3 + 8);
+    check(&Shape::Circle(1, 2.0));
+    assert!(cdr::size::calc_serialized_data_size(&Shape::Dot) < Shape::MAX_SIZE as u64);
+    assert!(
+        cdr::size::calc_serialized_data_size(&Shape::Box { a: 1, b: 2 }) < Shape::MAX_SIZE as u64
+    );
+    assert_eq!(<(u64, Shape)>::MAX_SIZE, 8 + 16);
+}
+
+#[test]
+fn test_unbounded() {
+    assert!(!Named::IS_BOUNDED);
+    assert_eq!(Named::MAX_SIZE, usize::MAX);
+    assert!(!<Wrapper<Vec<u8>>>::IS_BOUNDED);
+}
+
+#[derive(Keyed, Serialize)]
+struct Sensor {
+    #[key]
+    id: u16,
+    name: String,
+    #[key]
+    unit: Quantity,
+}
+
+#[derive(Keyed, Serialize)]
+enum Quantity {
+    Meter,
+    Second,
+}
+
+#[derive(Keyed, Serialize)]
+struct Reading {
+    value: f64,
+    #[key]
+    sensor: Sensor,
+    #[key]
+    channel: u8,
+}
+
+#[derive(Keyed, Serialize)]
+struct Position(u32, u64);
+
+#[test]
+fn test_keyed() {
+    use cdr::key::{self, KeyHolder};
+    use cdr::EncapsulationKind;
+
+    let reading = Reading {
+        value: 0.5,
+        sensor: Sensor {
+            id: 3,
+            name: "range".to_string(),
+            unit: Quantity::Second,
+        },
+        channel: 2,
+    };
+    assert_eq!(
+        key::serialize_key(&reading, EncapsulationKind::CdrBe).unwrap(),
+        [0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 1, 2]
+    );
+    assert_eq!(
+        key::serialize_key(&reading, EncapsulationKind::CdrLe).unwrap(),
+        cdr::serialize::<_, _, cdr::CdrLe>(&(3u16, 1u32, 2u8), cdr::Infinite).unwrap()
+    );
+    assert_eq!(
+        key::serialize_key(&Quantity::Meter, EncapsulationKind::CdrBe).unwrap(),
+        [0, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(
+        key::max_serialized_key_size::<Reading>(EncapsulationKind::CdrBe),
+        Some(9)
+    );
+    assert_eq!(
+        cdr::serialize::<_, _, cdr::CdrBe>(&KeyHolder(&reading.sensor), cdr::Infinite).unwrap(),
+        [0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 1]
+    );
+
+    // Without key members, every member is part of the key.
+    let position = Position(1, 2);
+    assert_eq!(
+        key::serialize_key(&position, EncapsulationKind::CdrBe).unwrap(),
+        cdr::serialize::<_, _, cdr::CdrBe>(&position, cdr::Infinite).unwrap()
+    );
+    assert_eq!(
+        key::serialize_key(&position, EncapsulationKind::Cdr2Be).unwrap(),
+        [0, 6, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2]
+    );
+    assert_eq!(
+        key::max_serialized_key_size::<Position>(EncapsulationKind::CdrBe),
+        Some(16)
+    );
+    assert_eq!(
+        key::max_serialized_key_size::<Position>(EncapsulationKind::Cdr2Be),
+        Some(12)
+    );
+    assert_eq!(
+        key::max_serialized_key_size::<Sensor>(EncapsulationKind::CdrBe),
+        Some(8)
+    );
+    assert_eq!(
+        key::max_serialized_key_size::<(u8, String)>(EncapsulationKind::CdrBe),
+        None
+    );
+}
+
+#[derive(Described, Serialize, Deserialize, PartialEq, Debug)]
+struct Scan<T> {
+    stamp: u64,
+    ranges: Vec<T>,
+    kind: Kind,
+    labels: [String; 2],
+}
+
+#[derive(Described, Serialize, Deserialize, PartialEq, Debug)]
+enum Kind {
+    Idle,
+    Sweep(f32),
+    Step { from: i16, to: i16 },
+}
+
+#[test]
+fn test_described() {
+    use cdr::value::{Member, StructDescriptor, UnionDescriptor};
+    use cdr::{EncapsulationKind, TypeDescriptor as T};
+
+    assert_eq!(
+        Scan::<f32>::descriptor(),
+        T::Struct(StructDescriptor {
+            name: "Scan".to_string(),
+            members: vec![
+                Member::new("stamp", T::U64),
+                Member::new("ranges", T::Sequence(Box::new(T::F32))),
+                Member::new(
+                    "kind",
+                    T::Union(UnionDescriptor::from_variants(
+                        "Kind",
+                        vec![
+                            Member::new("Idle", T::Unit),
+                            Member::new("Sweep", T::F32),
+                            Member::new(
+                                "Step",
+                                T::Struct(StructDescriptor {
+                                    name: "Step".to_string(),
+                                    members: vec![
+                                        Member::new("from", T::I16),
+                                        Member::new("to", T::I16),
+                                    ],
+                                })
+                            ),
+                        ],
+                    ))
+                ),
+                Member::new("labels", T::Array(Box::new(T::String), 2)),
+            ],
+        })
+    );
+
+    let scan = Scan {
+        stamp: 7,
+        ranges: vec![0.5f32, 1.5],
+        kind: Kind::Step { from: -1, to: 1 },
+        labels: ["a".to_string(), "b".to_string()],
+    };
+    let be = cdr::serialize::<_, _, cdr::CdrBe>(&scan, cdr::Infinite).unwrap();
+    let le = cdr::reencode::<Scan<f32>>(&be, EncapsulationKind::CdrLe).unwrap();
+    assert_eq!(
+        le,
+        cdr::serialize::<_, _, cdr::CdrLe>(&scan, cdr::Infinite).unwrap()
+    );
+    assert_eq!(cdr::deserialize::<Scan<f32>>(&le).unwrap(), scan);
+    // The labels are an array of strings, which XCDR2 delimits.
+    assert!(cdr::reencode::<Scan<f32>>(&be, EncapsulationKind::Cdr2Le).is_err());
+}
@@ -0,0 +1,408 @@
+//! Prints the contents of an encapsulated CDR payload.
+//!
+//! ```text
+//! cdr-dump [--idl FILE --type NAME] [PAYLOAD]
+//! ```
+//!
+//! The payload is read from `PAYLOAD`, or from the standard input if it is
+//! omitted or `-`. Without a type, the data following the encapsulation
+//! header is hex-dumped. With a type from an IDL file, every member is
+//! printed along with its offset, its bytes and any padding before it.
+//! Offsets are relative to the end of the header, which is the alignment
+//! origin.
+//!
+//! The data of the PL_CDR encapsulations is printed as a parameter list, one
+//! id, length and value per parameter, and cannot be given a type.
+
+use std::{
+    env, fmt, fs,
+    io::{self, Read, Write},
+    process,
+};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use serde::de::{DeserializeOwned, DeserializeSeed};
+
+use cdr::parameter_list::ParameterId;
+use cdr::{Deserializer, EncapsulationKind, Infinite, TypeDescriptor};
+
+const USAGE: &str = "usage: cdr-dump [--idl FILE --type NAME] [PAYLOAD]";
+
+/// The number of bytes shown on a line.
+const BYTES_PER_LINE: usize = 8;
+
+struct Options {
+    idl: Option<String>,
+    type_name: Option<String>,
+    payload: Option<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        idl: None,
+        type_name: None,
+        payload: None,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--idl" => options.idl = Some(args.next().ok_or(USAGE)?),
+            "--type" => options.type_name = Some(args.next().ok_or(USAGE)?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option {}\n{}", arg, USAGE))
+            }
+            _ if options.payload.is_none() => options.payload = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    if options.idl.is_some() != options.type_name.is_some() {
+        return Err("--idl and --type must be given together".to_string());
+    }
+    Ok(options)
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("cdr-dump: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let options = parse_args()?;
+
+    let ty = match (options.idl, options.type_name) {
+        (Some(path), Some(name)) => {
+            let source = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+            let definitions = cdr::idl::parse(&source).map_err(|e| format!("{}:{}", path, e))?;
+            let ty = definitions
+                .get(&name)
+                .ok_or_else(|| format!("{}: no type named {}", path, name))?;
+            Some(ty.clone())
+        }
+        _ => None,
+    };
+
+    let mut payload = Vec::new();
+    match options.payload.as_deref() {
+        None | Some("-") => io::stdin().read_to_end(&mut payload),
+        Some(path) => fs::File::open(path).and_then(|mut f| f.read_to_end(&mut payload)),
+    }
+    .map_err(|e| e.to_string())?;
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    dump(&mut out, &payload, ty.as_ref()).map_err(|e| e.to_string())
+}
+
+/// An error while printing a payload.
+enum DumpError {
+    Io(io::Error),
+    Cdr(cdr::Error),
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DumpError::Io(ref e) => fmt::Display::fmt(e, f),
+            DumpError::Cdr(ref e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+impl From<io::Error> for DumpError {
+    fn from(e: io::Error) -> Self {
+        DumpError::Io(e)
+    }
+}
+
+impl From<cdr::Error> for DumpError {
+    fn from(e: cdr::Error) -> Self {
+        DumpError::Cdr(e)
+    }
+}
+
+fn dump<W>(out: &mut W, payload: &[u8], ty: Option<&TypeDescriptor>) -> Result<(), DumpError>
+where
+    W: Write,
+{
+    let kind = EncapsulationKind::from_header(payload)?;
+    let parameter_list = match kind {
+        EncapsulationKind::PlCdrBe => Some(false),
+        EncapsulationKind::PlCdrLe => Some(true),
+        _ => None,
+    };
+    if parameter_list.is_some() && ty.is_some() {
+        return Err(DumpError::Cdr(cdr::Error::Custom(format!(
+            "a {:?} payload is a parameter list and cannot be printed as a type",
+            kind
+        ))));
+    }
+    writeln!(
+        out,
+        "encapsulation: {:?} (id 0x{:02x}{:02x}, options 0x{:02x}{:02x})",
+        kind, payload[0], payload[1], payload[2], payload[3]
+    )?;
+    let data = &payload[4..];
+    writeln!(out, "data: {} bytes", data.len())?;
+
+    if let Some(little_endian) = parameter_list {
+        return dump_parameter_list(out, data, little_endian);
+    }
+
+    let ty = match ty {
+        Some(ty) => ty,
+        None => {
+            for (i, chunk) in data.chunks(16).enumerate() {
+                writeln!(out, "{:#06x}  {}", i * 16, hex(chunk, 16))?;
+            }
+            return Ok(());
+        }
+    };
+
+    let end = if kind.is_little_endian() {
+        Walker::<_, LittleEndian>::new(out, data, kind.max_alignment()).walk(ty, "")?
+    } else {
+        Walker::<_, BigEndian>::new(out, data, kind.max_alignment()).walk(ty, "")?
+    };
+    if end < data.len() {
+        writeln!(
+            out,
+            "{:#06x}  {:<w$}  (trailing)",
+            end,
+            hex(&data[end..], BYTES_PER_LINE),
+            w = 3 * BYTES_PER_LINE
+        )?;
+    }
+    Ok(())
+}
+
+/// Prints the id, length and value of every parameter of a list.
+fn dump_parameter_list<W>(out: &mut W, data: &[u8], little_endian: bool) -> Result<(), DumpError>
+where
+    W: Write,
+{
+    let mut pos = 0;
+    loop {
+        let header = data.get(pos..pos + 4).ok_or(cdr::Error::Eof)?;
+        let (id, len) = if little_endian {
+            (
+                LittleEndian::read_u16(header),
+                LittleEndian::read_u16(&header[2..]),
+            )
+        } else {
+            (
+                BigEndian::read_u16(header),
+                BigEndian::read_u16(&header[2..]),
+            )
+        };
+        let id = ParameterId(id);
+        let w = 3 * BYTES_PER_LINE;
+        if id == ParameterId::SENTINEL {
+            writeln!(out, "{:#06x}  {:<w$}  sentinel", pos, hex(header, 4), w = w)?;
+            pos += 4;
+            break;
+        }
+        writeln!(
+            out,
+            "{:#06x}  {:<w$}  parameter {}, length {}",
+            pos,
+            hex(header, 4),
+            id,
+            len,
+            w = w
+        )?;
+        pos += 4;
+        let end = pos + len as usize;
+        let value = data.get(pos..end).ok_or(cdr::Error::Eof)?;
+        // The value is shown in full, continuing on lines without a label.
+        for (i, chunk) in value.chunks(BYTES_PER_LINE).enumerate() {
+            let offset = pos + i * BYTES_PER_LINE;
+            if i == 0 {
+                writeln!(out, "{:#06x}  {:<w$}  value", offset, hex(chunk, w), w = w)?;
+            } else {
+                writeln!(out, "{:#06x}  {}", offset, hex(chunk, w))?;
+            }
+        }
+        pos = end;
+    }
+    if pos < data.len() {
+        writeln!(
+            out,
+            "{:#06x}  {:<w$}  (trailing)",
+            pos,
+            hex(&data[pos..], BYTES_PER_LINE),
+            w = 3 * BYTES_PER_LINE
+        )?;
+    }
+    Ok(())
+}
+
+/// Formats at most `limit` bytes in hexadecimal.
+fn hex(bytes: &[u8], limit: usize) -> String {
+    let mut s = bytes
+        .iter()
+        .take(limit)
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if bytes.len() > limit {
+        s.push_str(" ..");
+    }
+    s
+}
+
+/// Prints the members of a value as they are read.
+struct Walker<'a, W, E> {
+    out: &'a mut W,
+    data: &'a [u8],
+    de: Deserializer<&'a [u8], Infinite, E>,
+    max_alignment: usize,
+}
+
+impl<'a, W, E> Walker<'a, W, E>
+where
+    W: Write,
+    E: ByteOrder,
+{
+    fn new(out: &'a mut W, data: &'a [u8], max_alignment: usize) -> Self {
+        Walker {
+            out,
+            data,
+            de: Deserializer::with_alignment(data, Infinite, 0, max_alignment),
+            max_alignment,
+        }
+    }
+
+    fn pos(&self) -> usize {
+        self.de.position() as usize
+    }
+
+    /// Prints the padding that precedes a primitive of `size` bytes and
+    /// returns the position of the primitive.
+    fn align(&mut self, size: usize) -> io::Result<usize> {
+        let pos = self.pos();
+        let alignment = std::cmp::min(size, self.max_alignment);
+        let start = pos + (alignment - pos % alignment) % alignment;
+        let start = std::cmp::min(start, self.data.len());
+        if start > pos {
+            writeln!(
+                self.out,
+                "{:#06x}  {:<w$}  (padding)",
+                pos,
+                hex(&self.data[pos..start], BYTES_PER_LINE),
+                w = 3 * BYTES_PER_LINE
+            )?;
+        }
+        Ok(start)
+    }
+
+    /// Reads a primitive of `size` bytes and prints it.
+    fn read<T>(&mut self, size: usize, path: &str, type_name: &str) -> Result<T, DumpError>
+    where
+        T: DeserializeOwned + fmt::Debug,
+    {
+        let start = self.align(size)?;
+        let value = T::deserialize(&mut self.de)?;
+        self.line(start, path, &format!("{} = {:?}", type_name, value))?;
+        Ok(value)
+    }
+
+    fn line(&mut self, start: usize, path: &str, text: &str) -> io::Result<()> {
+        let end = self.pos();
+        writeln!(
+            self.out,
+            "{:#06x}  {:<w$}  {}: {}",
+            start,
+            hex(&self.data[start..end], BYTES_PER_LINE),
+            if path.is_empty() { "." } else { path },
+            text,
+            w = 3 * BYTES_PER_LINE
+        )
+    }
+
+    /// Prints a value of type `ty` and returns the position following it.
+    fn walk(mut self, ty: &TypeDescriptor, path: &str) -> Result<usize, DumpError> {
+        self.value(ty, path)?;
+        Ok(self.pos())
+    }
+
+    fn value(&mut self, ty: &TypeDescriptor, path: &str) -> Result<(), DumpError> {
+        use cdr::TypeDescriptor as T;
+
+        match *ty {
+            T::Unit => {}
+            T::Bool => drop(self.read::<bool>(1, path, "boolean")?),
+            T::Char => drop(self.read::<char>(1, path, "char")?),
+            T::I8 => drop(self.read::<i8>(1, path, "int8")?),
+            T::I16 => drop(self.read::<i16>(2, path, "short")?),
+            T::I32 => drop(self.read::<i32>(4, path, "long")?),
+            T::I64 => drop(self.read::<i64>(8, path, "long long")?),
+            T::U8 => drop(self.read::<u8>(1, path, "octet")?),
+            T::U16 => drop(self.read::<u16>(2, path, "unsigned short")?),
+            T::U32 => drop(self.read::<u32>(4, path, "unsigned long")?),
+            T::U64 => drop(self.read::<u64>(8, path, "unsigned long long")?),
+            T::F32 => drop(self.read::<f32>(4, path, "float")?),
+            T::F64 => drop(self.read::<f64>(8, path, "double")?),
+            T::String | T::BoundedString(_) => {
+                drop(self.read::<String>(4, path, "string")?);
+            }
+            T::Sequence(ref element) | T::BoundedSequence(ref element, _) => {
+                let len = self.read::<u32>(4, path, "sequence length")?;
+                for i in 0..len {
+                    self.value(element, &format!("{}[{}]", path, i))?;
+                }
+            }
+            T::Array(ref element, len) => {
+                for i in 0..len {
+                    self.value(element, &format!("{}[{}]", path, i))?;
+                }
+            }
+            T::Struct(ref desc) => {
+                for member in &desc.members {
+                    let path = if path.is_empty() {
+                        member.name.clone()
+                    } else {
+                        format!("{}.{}", path, member.name)
+                    };
+                    self.value(&member.ty, &path)?;
+                }
+            }
+            T::Union(ref desc) => {
+                let size = match *desc.discriminator {
+                    T::Bool | T::Char | T::I8 | T::U8 => 1,
+                    T::I16 | T::U16 => 2,
+                    T::I64 | T::U64 => 8,
+                    _ => 4,
+                };
+                let start = self.align(size)?;
+                let discriminator = (&*desc.discriminator).deserialize(&mut self.de)?;
+                let (discriminator, member) = discriminator
+                    .label()
+                    .and_then(|label| Some((label, desc.member(label)?)))
+                    .ok_or_else(|| {
+                        cdr::Error::Custom(format!(
+                            "invalid discriminator {:?} of {}",
+                            discriminator, desc.name
+                        ))
+                    })?;
+                self.line(
+                    start,
+                    path,
+                    &format!("{} = {} ({})", desc.name, member.name, discriminator),
+                )?;
+                let path = if path.is_empty() {
+                    member.name.clone()
+                } else {
+                    format!("{}.{}", path, member.name)
+                };
+                self.value(&member.ty, &path)?;
+            }
+        }
+        Ok(())
+    }
+}
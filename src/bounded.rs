@@ -0,0 +1,202 @@
+//! Strings and sequences with an upper bound on their length.
+//!
+//! These correspond to the IDL types `string<N>` and `sequence<T, N>`. They
+//! are encoded like `String` and `Vec<T>`, but their largest serialized size
+//! is known from their type, see `size::max_serialized_data_size`.
+
+use std::{convert::TryFrom, fmt, ops::Deref};
+
+use serde::{de, ser};
+
+use crate::error::Error;
+use crate::size::{CdrFixedSize, Layout};
+use crate::value::{Described, TypeDescriptor};
+
+/// A string of at most `N` bytes, excluding the terminating null character.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct BoundedString<const N: usize>(String);
+
+impl<const N: usize> BoundedString<N> {
+    /// Unwraps the string.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl<const N: usize> TryFrom<String> for BoundedString<N> {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Error> {
+        if value.len() > N {
+            Err(Error::NumberOutOfRange)
+        } else {
+            Ok(Self(value))
+        }
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for BoundedString<N> {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Error> {
+        Self::try_from(value.to_string())
+    }
+}
+
+impl<const N: usize> Deref for BoundedString<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<const N: usize> AsRef<str> for BoundedString<N> {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<const N: usize> fmt::Display for BoundedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<const N: usize> ser::Serialize for BoundedString<N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de, const N: usize> de::Deserialize<'de> for BoundedString<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        if value.len() > N {
+            Err(de::Error::invalid_length(
+                value.len(),
+                &Expected(N, "bytes"),
+            ))
+        } else {
+            Ok(Self(value))
+        }
+    }
+}
+
+impl<const N: usize> CdrFixedSize for BoundedString<N> {
+    const LAYOUT: Layout = Layout::primitive(4)
+        .then(Layout::bytes(1, N + 1))
+        .with_elements(Layout::primitive(1));
+    const LAYOUT_XCDR2: Layout = Self::LAYOUT;
+}
+
+impl<const N: usize> Described for BoundedString<N> {
+    fn descriptor() -> TypeDescriptor {
+        TypeDescriptor::BoundedString(N)
+    }
+}
+
+/// A sequence of at most `N` elements.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct BoundedSeq<T, const N: usize>(Vec<T>);
+
+impl<T, const N: usize> BoundedSeq<T, N> {
+    /// Unwraps the sequence.
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T, const N: usize> TryFrom<Vec<T>> for BoundedSeq<T, N> {
+    type Error = Error;
+
+    fn try_from(value: Vec<T>) -> Result<Self, Error> {
+        if value.len() > N {
+            Err(Error::NumberOutOfRange)
+        } else {
+            Ok(Self(value))
+        }
+    }
+}
+
+impl<T, const N: usize> Deref for BoundedSeq<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T, const N: usize> AsRef<[T]> for BoundedSeq<T, N> {
+    fn as_ref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T, const N: usize> ser::Serialize for BoundedSeq<T, N>
+where
+    T: ser::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T, const N: usize> de::Deserialize<'de> for BoundedSeq<T, N>
+where
+    T: de::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let value = Vec::<T>::deserialize(deserializer)?;
+        if value.len() > N {
+            Err(de::Error::invalid_length(
+                value.len(),
+                &Expected(N, "elements"),
+            ))
+        } else {
+            Ok(Self(value))
+        }
+    }
+}
+
+impl<T, const N: usize> CdrFixedSize for BoundedSeq<T, N>
+where
+    T: CdrFixedSize,
+{
+    const LAYOUT: Layout = Layout::primitive(4)
+        .then(T::LAYOUT.repeat_up_to(N))
+        .with_elements(T::LAYOUT);
+    const LAYOUT_XCDR2: Layout = Layout::primitive(4)
+        .then(T::LAYOUT_XCDR2.repeat_up_to(N))
+        .with_elements(T::LAYOUT_XCDR2);
+}
+
+impl<T, const N: usize> Described for BoundedSeq<T, N>
+where
+    T: Described,
+{
+    fn descriptor() -> TypeDescriptor {
+        TypeDescriptor::BoundedSequence(Box::new(T::descriptor()), N)
+    }
+}
+
+/// A bound along with the unit it counts, bytes or elements.
+struct Expected(usize, &'static str);
+
+impl de::Expected for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at most {} {}", self.0, self.1)
+    }
+}
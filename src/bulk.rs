@@ -0,0 +1,170 @@
+//! Copying sequences of primitive values as a whole.
+//!
+//! By default every element of a `Vec<f32>` goes through `serialize_f32`. A
+//! field annotated with `#[serde(with = "cdr::bulk")]` is instead copied in a
+//! single operation when the byte order of the data matches that of the host,
+//! and byte-swapped in one pass otherwise. The encoding is the same as that of
+//! an ordinary sequence.
+//!
+//! ```rust
+//! use cdr::{CdrLe, Infinite};
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize, Serialize, PartialEq, Debug)]
+//! struct Scan {
+//!     id: u8,
+//!     #[serde(with = "cdr::bulk")]
+//!     ranges: Vec<f32>,
+//! }
+//!
+//! let scan = Scan { id: 1, ranges: vec![0.5, 1.5, 2.5] };
+//! let encoded = cdr::serialize::<_, _, CdrLe>(&scan, Infinite).unwrap();
+//! assert_eq!(cdr::deserialize::<Scan>(&encoded).unwrap(), scan);
+//! ```
+//!
+//! Human-readable formats see a plain sequence. Other formats see a byte
+//! string holding the elements in the byte order of the host.
+
+use std::{fmt, marker::PhantomData};
+
+use byteorder::ByteOrder;
+use serde::{de, ser};
+
+const NAMES: [&str; 4] = [
+    "$cdr::bulk::1",
+    "$cdr::bulk::2",
+    "$cdr::bulk::4",
+    "$cdr::bulk::8",
+];
+
+/// Returns the element size of a bulk sequence encoded as a newtype struct
+/// named `name`.
+pub(crate) fn element_size(name: &str) -> Option<usize> {
+    NAMES.iter().position(|n| *n == name).map(|i| 1 << i)
+}
+
+/// Returns whether `E` is the byte order of the host.
+pub(crate) fn is_native<E: ByteOrder>() -> bool {
+    E::read_u16(&1u16.to_ne_bytes()) == 1
+}
+
+/// Reverses the bytes of every `size`-byte element of `bytes`.
+pub(crate) fn swap_bytes(bytes: &mut [u8], size: usize) {
+    if size > 1 {
+        bytes.chunks_exact_mut(size).for_each(<[u8]>::reverse);
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A primitive type whose sequences can be copied in bulk.
+///
+/// This trait is sealed and implemented for the integer and floating point
+/// types.
+pub trait Primitive: Copy + sealed::Sealed {
+    #[doc(hidden)]
+    const NAME: &'static str;
+}
+
+macro_rules! impl_primitive {
+    ($($ty:ty),*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl Primitive for $ty {
+                const NAME: &'static str = NAMES[std::mem::size_of::<$ty>().trailing_zeros() as usize];
+            }
+        )*
+    };
+}
+
+impl_primitive!(u8, i8, u16, i16, u32, i32, u64, i64, f32, f64);
+
+fn as_ne_bytes<P: Primitive>(value: &[P]) -> &[u8] {
+    // Safe because `Primitive` is only implemented for types without padding
+    // or invalid bit patterns.
+    unsafe { std::slice::from_raw_parts(value.as_ptr() as *const u8, std::mem::size_of_val(value)) }
+}
+
+/// Copies bytes in the byte order of the host into a vector of primitive
+/// values with a single `memcpy`.
+fn from_ne_bytes<P: Primitive>(bytes: &[u8]) -> Option<Vec<P>> {
+    let len = bytes.len() / std::mem::size_of::<P>();
+    if len * std::mem::size_of::<P>() != bytes.len() {
+        return None;
+    }
+    let mut values = Vec::<P>::with_capacity(len);
+    // Safe for the same reason as `as_ne_bytes`, and because the capacity of
+    // `values` is `bytes.len()` bytes.
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), values.as_mut_ptr() as *mut u8, bytes.len());
+        values.set_len(len);
+    }
+    Some(values)
+}
+
+struct Bytes<'a>(&'a [u8]);
+
+impl ser::Serialize for Bytes<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+/// Serializes a sequence of primitive values in bulk.
+pub fn serialize<P, S>(value: &[P], serializer: S) -> Result<S::Ok, S::Error>
+where
+    P: Primitive + ser::Serialize,
+    S: ser::Serializer,
+{
+    if serializer.is_human_readable() {
+        serializer.collect_seq(value)
+    } else {
+        serializer.serialize_newtype_struct(P::NAME, &Bytes(as_ne_bytes(value)))
+    }
+}
+
+/// Deserializes a sequence of primitive values in bulk.
+pub fn deserialize<'de, P, D>(deserializer: D) -> Result<Vec<P>, D::Error>
+where
+    P: Primitive + de::Deserialize<'de>,
+    D: de::Deserializer<'de>,
+{
+    struct Visitor<P>(PhantomData<P>);
+
+    impl<'de, P> de::Visitor<'de> for Visitor<P>
+    where
+        P: Primitive,
+    {
+        type Value = Vec<P>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a sequence of primitive values")
+        }
+
+        fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: de::Deserializer<'de>,
+        {
+            deserializer.deserialize_byte_buf(self)
+        }
+
+        fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            from_ne_bytes(v).ok_or_else(|| de::Error::invalid_length(v.len(), &self))
+        }
+    }
+
+    if deserializer.is_human_readable() {
+        de::Deserialize::deserialize(deserializer)
+    } else {
+        deserializer.deserialize_newtype_struct(P::NAME, Visitor(PhantomData))
+    }
+}
@@ -5,14 +5,21 @@ use std::{self, io::Read, marker::PhantomData};
 use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt};
 use serde::de::{self, IntoDeserializer};
 
-use crate::error::{Error, Result};
+use crate::bulk;
+use crate::error::{Error, PathSegment, Result};
 use crate::size::{Infinite, SizeLimit};
 
+/// The name of the newtype struct through which an XCDR2 delimited value,
+/// a `u32` DHEADER followed by that many bytes, is read.
+pub(crate) const DELIMITED: &str = "$cdr::delimited";
+
 /// A deserializer that reads bytes from a buffer.
 pub struct Deserializer<R, S, E> {
     reader: R,
     size_limit: S,
     pos: u64,
+    value_pos: u64,
+    max_alignment: usize,
     phantom: PhantomData<E>,
 }
 
@@ -23,18 +30,60 @@ where
     E: ByteOrder,
 {
     pub fn new(reader: R, size_limit: S) -> Self {
+        Self::with_alignment(reader, size_limit, 0, 8)
+    }
+
+    /// Creates a deserializer that reads "packed" CDR, i.e. without any
+    /// alignment padding.
+    pub fn new_packed(reader: R, size_limit: S) -> Self {
+        Self::with_alignment(reader, size_limit, 0, 1)
+    }
+
+    /// Creates a deserializer whose first read is `offset` bytes past the
+    /// alignment origin and whose primitives are aligned to at most
+    /// `max_alignment` bytes.
+    ///
+    /// Classic CDR aligns every primitive to its own size, which corresponds
+    /// to a `max_alignment` of 8. XCDR2 caps the alignment at 4.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_alignment` is not one of 1, 2, 4 or 8.
+    pub fn with_alignment(reader: R, size_limit: S, offset: u64, max_alignment: usize) -> Self {
+        assert!(
+            max_alignment.is_power_of_two() && max_alignment <= 8,
+            "alignment must be 1, 2, 4 or 8"
+        );
         Self {
             reader,
             size_limit,
-            pos: 0,
+            pos: offset,
+            value_pos: offset,
+            max_alignment,
             phantom: PhantomData,
         }
     }
 
+    /// Deserializes a value from an encapsulation, a sequence of octets
+    /// whose first octet gives the byte order of the value that follows.
+    ///
+    /// The value is aligned relative to the start of the encapsulation.
+    pub fn deserialize_encapsulated<'de, T>(&mut self) -> Result<T>
+    where
+        T: de::Deserialize<'de>,
+    {
+        let bytes = self.read_vec()?;
+        read_encapsulated(&bytes, self.max_alignment)
+    }
+
     fn read_padding_of<T>(&mut self) -> Result<()> {
+        self.read_padding(std::mem::size_of::<T>())
+    }
+
+    fn read_padding(&mut self, size: usize) -> Result<()> {
         // Calculate the required padding to align with 1-byte, 2-byte, 4-byte, 8-byte boundaries
         // Instead of using the slow modulo operation '%', the faster bit-masking is used
-        let alignment = std::mem::size_of::<T>();
+        let alignment = std::cmp::min(size, self.max_alignment);
         let rem_mask = alignment - 1; // mask like 0x0, 0x1, 0x3, 0x7
         let mut padding: [u8; 8] = [0; 8];
         match (self.pos as usize) & rem_mask {
@@ -50,12 +99,47 @@ where
         }
     }
 
+    fn deserialize_fields<'de, V>(
+        &mut self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_seq(Access {
+            deserializer: self,
+            fields: Some(fields),
+            index: 0,
+            len: fields.len(),
+        })
+    }
+
+    /// Runs `f`, attaching the offset of the value being read to any error
+    /// that does not carry one yet.
+    fn located<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        let start = self.pos;
+        f(self).map_err(|error| match error {
+            Error::At { .. } => error,
+            error => Error::At {
+                // Points at the primitive being read rather than at any padding before it
+                offset: std::cmp::max(start, self.value_pos),
+                path: String::new(),
+                error: Box::new(error),
+            },
+        })
+    }
+
     fn read_size(&mut self, size: u64) -> Result<()> {
         self.pos += size;
         self.size_limit.add(size)
     }
 
     fn read_size_of<T>(&mut self) -> Result<()> {
+        self.value_pos = self.pos;
         self.read_size(std::mem::size_of::<T>() as u64)
     }
 
@@ -67,21 +151,100 @@ where
         .map_err(|e| Error::InvalidUtf8Encoding(e.utf8_error()))
     }
 
-    fn read_vec(&mut self) -> Result<Vec<u8>> {
+    pub(crate) fn read_bulk(&mut self, size: usize) -> Result<Vec<u8>> {
+        let len: u32 = de::Deserialize::deserialize(&mut *self)?;
+        if len > 0 {
+            self.read_padding(size)?;
+        }
+        let byte_len = u64::from(len) * size as u64;
+        self.value_pos = self.pos;
+        self.read_size(byte_len)?;
+        let mut buf = self.read_bytes(byte_len)?;
+        if !bulk::is_native::<E>() {
+            bulk::swap_bytes(&mut buf, size);
+        }
+        Ok(buf)
+    }
+
+    /// Reads a DHEADER and the bytes it delimits, and hands the visitor a
+    /// deserializer over both, so that bytes it leaves unread, such as
+    /// members appended by a newer version of the type, are skipped.
+    fn read_delimited<'de, V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let size: u32 = de::Deserialize::deserialize(&mut *self)?;
+        let start = self.pos - 4;
+        self.read_size(u64::from(size))?;
+        let mut buf = vec![0; 4];
+        E::write_u32(&mut buf, size);
+        (&mut self.reader)
+            .take(u64::from(size))
+            .read_to_end(&mut buf)?;
+        if buf.len() - 4 < size as usize {
+            return Err(Error::Eof);
+        }
+        visitor.visit_newtype_struct(&mut Deserializer::<_, _, E>::with_alignment(
+            &buf[..],
+            Infinite,
+            start,
+            self.max_alignment,
+        ))
+    }
+
+    pub(crate) fn read_vec(&mut self) -> Result<Vec<u8>> {
         let len: u32 = de::Deserialize::deserialize(&mut *self)?;
-        let mut buf = Vec::with_capacity(len as usize);
-        unsafe { buf.set_len(len as usize) }
         self.read_size(u64::from(len))?;
-        self.reader.read_exact(&mut buf[..])?;
+        self.read_bytes(u64::from(len))
+    }
+
+    /// Reads `len` bytes without trusting `len` to allocate them up front,
+    /// since it comes from the data and may exceed what is left to read.
+    fn read_bytes(&mut self, len: u64) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut buf)?;
+        if (buf.len() as u64) < len {
+            return Err(Error::Eof);
+        }
         Ok(buf)
     }
 
-    pub(crate) fn reset_pos(&mut self) {
+    /// Returns the number of bytes read since the alignment origin.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Returns the number of bytes that may still be read, or `None` if the
+    /// size limit is infinite.
+    pub fn remaining_limit(&self) -> Option<u64> {
+        self.size_limit.limit()
+    }
+
+    /// Makes the current position the alignment origin of subsequent reads.
+    pub fn reset_pos(&mut self) {
         self.pos = 0;
     }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    ///
+    /// Reading from it directly does not advance the position of the
+    /// deserializer.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this `Deserializer`, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
 }
 
-impl<'de, 'a, R, S, E> de::Deserializer<'de> for &'a mut Deserializer<R, S, E>
+impl<'de, R, S, E> de::Deserializer<'de> for &mut Deserializer<R, S, E>
 where
     R: Read,
     S: SizeLimit,
@@ -93,232 +256,235 @@ where
     where
         V: de::Visitor<'de>,
     {
-        Err(Error::DeserializeAnyNotSupported)
+        self.located(|_| Err(Error::DeserializeAnyNotSupported))
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        let value: u8 = de::Deserialize::deserialize(self)?;
-        match value {
-            1 => visitor.visit_bool(true),
-            0 => visitor.visit_bool(false),
-            value => Err(Error::InvalidBoolEncoding(value)),
-        }
+        self.located(|this| {
+            let value: u8 = de::Deserialize::deserialize(&mut *this)?;
+            match value {
+                1 => visitor.visit_bool(true),
+                0 => visitor.visit_bool(false),
+                value => Err(Error::InvalidBoolEncoding(value)),
+            }
+        })
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.read_size_of::<u8>()?;
-        visitor.visit_u8(self.reader.read_u8()?)
+        self.located(|this| {
+            this.read_size_of::<u8>()?;
+            visitor.visit_u8(this.reader.read_u8()?)
+        })
     }
 
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.read_padding_of::<u16>()?;
-        self.read_size_of::<u16>()?;
-        visitor.visit_u16(self.reader.read_u16::<E>()?)
+        self.located(|this| {
+            this.read_padding_of::<u16>()?;
+            this.read_size_of::<u16>()?;
+            visitor.visit_u16(this.reader.read_u16::<E>()?)
+        })
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.read_padding_of::<u32>()?;
-        self.read_size_of::<u32>()?;
-        visitor.visit_u32(self.reader.read_u32::<E>()?)
+        self.located(|this| {
+            this.read_padding_of::<u32>()?;
+            this.read_size_of::<u32>()?;
+            visitor.visit_u32(this.reader.read_u32::<E>()?)
+        })
     }
 
     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.read_padding_of::<u64>()?;
-        self.read_size_of::<u64>()?;
-        visitor.visit_u64(self.reader.read_u64::<E>()?)
+        self.located(|this| {
+            this.read_padding_of::<u64>()?;
+            this.read_size_of::<u64>()?;
+            visitor.visit_u64(this.reader.read_u64::<E>()?)
+        })
     }
 
     fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.read_size_of::<i8>()?;
-        visitor.visit_i8(self.reader.read_i8()?)
+        self.located(|this| {
+            this.read_size_of::<i8>()?;
+            visitor.visit_i8(this.reader.read_i8()?)
+        })
     }
 
     fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.read_padding_of::<i16>()?;
-        self.read_size_of::<i16>()?;
-        visitor.visit_i16(self.reader.read_i16::<E>()?)
+        self.located(|this| {
+            this.read_padding_of::<i16>()?;
+            this.read_size_of::<i16>()?;
+            visitor.visit_i16(this.reader.read_i16::<E>()?)
+        })
     }
 
     fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.read_padding_of::<i32>()?;
-        self.read_size_of::<i32>()?;
-        visitor.visit_i32(self.reader.read_i32::<E>()?)
+        self.located(|this| {
+            this.read_padding_of::<i32>()?;
+            this.read_size_of::<i32>()?;
+            visitor.visit_i32(this.reader.read_i32::<E>()?)
+        })
     }
 
     fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.read_padding_of::<i64>()?;
-        self.read_size_of::<i64>()?;
-        visitor.visit_i64(self.reader.read_i64::<E>()?)
+        self.located(|this| {
+            this.read_padding_of::<i64>()?;
+            this.read_size_of::<i64>()?;
+            visitor.visit_i64(this.reader.read_i64::<E>()?)
+        })
     }
 
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.read_padding_of::<f32>()?;
-        self.read_size_of::<f32>()?;
-        visitor.visit_f32(self.reader.read_f32::<E>()?)
+        self.located(|this| {
+            this.read_padding_of::<f32>()?;
+            this.read_size_of::<f32>()?;
+            visitor.visit_f32(this.reader.read_f32::<E>()?)
+        })
     }
 
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.read_padding_of::<f64>()?;
-        self.read_size_of::<f64>()?;
-        visitor.visit_f64(self.reader.read_f64::<E>()?)
+        self.located(|this| {
+            this.read_padding_of::<f64>()?;
+            this.read_size_of::<f64>()?;
+            visitor.visit_f64(this.reader.read_f64::<E>()?)
+        })
     }
 
     fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        let mut buf = [0u8; 4];
-        self.reader.read_exact(&mut buf[..1])?;
-
-        let width = utf8_char_width(buf[0]);
-        if width != 1 {
-            Err(Error::InvalidCharEncoding)
-        } else {
-            self.read_size(width as u64)?;
-            visitor.visit_char(buf[0] as char)
-        }
+        self.located(|this| {
+            let mut buf = [0u8; 4];
+            this.value_pos = this.pos;
+            this.reader.read_exact(&mut buf[..1])?;
+
+            let width = utf8_char_width(buf[0]);
+            if width != 1 {
+                Err(Error::InvalidCharEncoding)
+            } else {
+                this.read_size(width as u64)?;
+                visitor.visit_char(buf[0] as char)
+            }
+        })
     }
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_str(&self.read_string()?)
+        self.located(|this| visitor.visit_str(&this.read_string()?))
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_string(self.read_string()?)
+        self.located(|this| visitor.visit_string(this.read_string()?))
     }
 
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_bytes(&self.read_vec()?)
+        self.located(|this| visitor.visit_bytes(&this.read_vec()?))
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_byte_buf(self.read_vec()?)
+        self.located(|this| visitor.visit_byte_buf(this.read_vec()?))
     }
 
     fn deserialize_option<V>(self, _visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        Err(Error::TypeNotSupported)
+        self.located(|_| Err(Error::TypeNotSupported))
     }
 
     fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_unit()
+        self.located(|_| visitor.visit_unit())
     }
 
     fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_unit()
+        self.located(|_| visitor.visit_unit())
     }
 
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        self.located(|this| {
+            if let Some(size) = bulk::element_size(name) {
+                return visitor.visit_byte_buf(this.read_bulk(size)?);
+            }
+            if name == DELIMITED {
+                return this.read_delimited(visitor);
+            }
+            visitor.visit_newtype_struct(this)
+        })
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        let len: u32 = de::Deserialize::deserialize(&mut *self)?;
-        self.deserialize_tuple(len as usize, visitor)
+        self.located(|this| {
+            let len: u32 = de::Deserialize::deserialize(&mut *this)?;
+            this.deserialize_tuple(len as usize, visitor)
+        })
     }
 
     fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        struct Access<'a, R: 'a, S: 'a, E: 'a>
-        where
-            R: Read,
-            S: SizeLimit,
-            E: ByteOrder,
-        {
-            deserializer: &'a mut Deserializer<R, S, E>,
-            len: usize,
-        }
-
-        impl<'de, 'a, R: 'a, S, E> de::SeqAccess<'de> for Access<'a, R, S, E>
-        where
-            R: Read,
-            S: SizeLimit,
-            E: ByteOrder,
-        {
-            type Error = Error;
-
-            fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
-            where
-                T: de::DeserializeSeed<'de>,
-            {
-                if self.len > 0 {
-                    self.len -= 1;
-                    let value = de::DeserializeSeed::deserialize(seed, &mut *self.deserializer)?;
-                    Ok(Some(value))
-                } else {
-                    Ok(None)
-                }
-            }
-
-            fn size_hint(&self) -> Option<usize> {
-                Some(self.len)
-            }
-        }
-
-        visitor.visit_seq(Access {
-            deserializer: self,
-            len,
+        self.located(|this| {
+            visitor.visit_seq(Access {
+                deserializer: this,
+                fields: None,
+                index: 0,
+                len,
+            })
         })
     }
 
@@ -331,14 +497,14 @@ where
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_tuple(len, visitor)
+        self.located(|this| this.deserialize_tuple(len, visitor))
     }
 
     fn deserialize_map<V>(self, _visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        Err(Error::TypeNotSupported)
+        self.located(|_| Err(Error::TypeNotSupported))
     }
 
     fn deserialize_struct<V>(
@@ -350,7 +516,7 @@ where
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_tuple(fields.len(), visitor)
+        self.located(|this| this.deserialize_fields(fields, visitor))
     }
 
     fn deserialize_enum<V>(
@@ -362,40 +528,21 @@ where
     where
         V: de::Visitor<'de>,
     {
-        impl<'de, 'a, R: 'a, S, E> de::EnumAccess<'de> for &'a mut Deserializer<R, S, E>
-        where
-            R: Read,
-            S: SizeLimit,
-            E: ByteOrder,
-        {
-            type Error = Error;
-            type Variant = Self;
-
-            fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
-            where
-                V: de::DeserializeSeed<'de>,
-            {
-                let idx: u32 = de::Deserialize::deserialize(&mut *self)?;
-                let val: Result<_> = seed.deserialize(idx.into_deserializer());
-                Ok((val?, self))
-            }
-        }
-
-        visitor.visit_enum(self)
+        self.located(|this| visitor.visit_enum(this))
     }
 
     fn deserialize_identifier<V>(self, _visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        Err(Error::TypeNotSupported)
+        self.located(|_| Err(Error::TypeNotSupported))
     }
 
     fn deserialize_ignored_any<V>(self, _visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        Err(Error::TypeNotSupported)
+        self.located(|_| Err(Error::TypeNotSupported))
     }
 
     fn is_human_readable(&self) -> bool {
@@ -403,7 +550,75 @@ where
     }
 }
 
-impl<'de, 'a, R, S, E> de::VariantAccess<'de> for &'a mut Deserializer<R, S, E>
+struct Access<'a, R: 'a, S: 'a, E: 'a>
+where
+    R: Read,
+    S: SizeLimit,
+    E: ByteOrder,
+{
+    deserializer: &'a mut Deserializer<R, S, E>,
+    fields: Option<&'static [&'static str]>,
+    index: usize,
+    len: usize,
+}
+
+impl<'de, 'a, R: 'a, S, E> de::SeqAccess<'de> for Access<'a, R, S, E>
+where
+    R: Read,
+    S: SizeLimit,
+    E: ByteOrder,
+{
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        if self.len > 0 {
+            self.len -= 1;
+            let start = self.deserializer.pos;
+            let segment = match self.fields {
+                Some(fields) => PathSegment::Field(fields[self.index]),
+                None => PathSegment::Index(self.index),
+            };
+            self.index += 1;
+            let value =
+                de::DeserializeSeed::deserialize(seed, &mut *self.deserializer).map_err(|e| {
+                    // Points at the primitive being read rather than at any padding before it
+                    let offset = std::cmp::max(start, self.deserializer.value_pos);
+                    e.within(segment, offset)
+                })?;
+            Ok(Some(value))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+impl<'de, R, S, E> de::EnumAccess<'de> for &mut Deserializer<R, S, E>
+where
+    R: Read,
+    S: SizeLimit,
+    E: ByteOrder,
+{
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let idx: u32 = de::Deserialize::deserialize(&mut *self)?;
+        let val: Result<_> = seed.deserialize(idx.into_deserializer());
+        Ok((val?, self))
+    }
+}
+
+impl<'de, R, S, E> de::VariantAccess<'de> for &mut Deserializer<R, S, E>
 where
     R: Read,
     S: SizeLimit,
@@ -433,7 +648,7 @@ where
     where
         V: de::Visitor<'de>,
     {
-        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+        self.deserialize_fields(fields, visitor)
     }
 }
 
@@ -443,6 +658,8 @@ impl<R, S> From<Deserializer<R, S, BigEndian>> for Deserializer<R, S, LittleEndi
             reader: t.reader,
             size_limit: t.size_limit,
             pos: t.pos,
+            value_pos: t.value_pos,
+            max_alignment: t.max_alignment,
             phantom: PhantomData,
         }
     }
@@ -493,3 +710,34 @@ where
     let mut deserializer = Deserializer::<_, S, E>::new(reader, size_limit);
     de::Deserialize::deserialize(&mut deserializer)
 }
+
+/// Deserializes an object from the octets of an encapsulation, in the byte
+/// order given by the first of them.
+pub fn deserialize_encapsulated<'de, T>(bytes: &[u8]) -> Result<T>
+where
+    T: de::Deserialize<'de>,
+{
+    read_encapsulated(bytes, 8)
+}
+
+fn read_encapsulated<'de, T>(bytes: &[u8], max_alignment: usize) -> Result<T>
+where
+    T: de::Deserialize<'de>,
+{
+    match bytes.split_first() {
+        Some((0, data)) => T::deserialize(&mut Deserializer::<_, _, BigEndian>::with_alignment(
+            data,
+            Infinite,
+            1,
+            max_alignment,
+        )),
+        Some((1, data)) => T::deserialize(&mut Deserializer::<_, _, LittleEndian>::with_alignment(
+            data,
+            Infinite,
+            1,
+            max_alignment,
+        )),
+        Some(_) => Err(Error::InvalidEncapsulation),
+        None => Err(Error::Eof),
+    }
+}
@@ -1,5 +1,7 @@
 use byteorder::{BigEndian, ByteOrder, LittleEndian};
 
+use crate::error::{Error, Result};
+
 pub const ENCAPSULATION_HEADER_SIZE: u64 = 4;
 
 /// Data encapsulation scheme identifiers.
@@ -60,6 +62,78 @@ impl Encapsulation for PlCdrLe {
     }
 }
 
+/// The encapsulation scheme of a buffer, as identified by its header.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum EncapsulationKind {
+    CdrBe,
+    CdrLe,
+    PlCdrBe,
+    PlCdrLe,
+    /// XCDR2 big-endian encapsulation of a final type.
+    Cdr2Be,
+    /// XCDR2 little-endian encapsulation of a final type.
+    Cdr2Le,
+}
+
+impl EncapsulationKind {
+    /// Returns the scheme with the given identifier, if supported.
+    pub fn from_id(id: [u8; 2]) -> Option<Self> {
+        use self::EncapsulationKind::*;
+
+        match id {
+            [0, 0] => Some(CdrBe),
+            [0, 1] => Some(CdrLe),
+            [0, 2] => Some(PlCdrBe),
+            [0, 3] => Some(PlCdrLe),
+            [0, 6] => Some(Cdr2Be),
+            [0, 7] => Some(Cdr2Le),
+            _ => None,
+        }
+    }
+
+    /// Returns the scheme of a slice of bytes that starts with an
+    /// encapsulation header.
+    pub fn from_header(bytes: &[u8]) -> Result<Self> {
+        match *bytes {
+            [a, b, _, _, ..] => Self::from_id([a, b]).ok_or(Error::InvalidEncapsulation),
+            _ => Err(Error::Eof),
+        }
+    }
+
+    /// Returns the identifier written in the header.
+    pub fn id(self) -> [u8; 2] {
+        use self::EncapsulationKind::*;
+
+        match self {
+            CdrBe => [0, 0],
+            CdrLe => [0, 1],
+            PlCdrBe => [0, 2],
+            PlCdrLe => [0, 3],
+            Cdr2Be => [0, 6],
+            Cdr2Le => [0, 7],
+        }
+    }
+
+    /// Returns whether the data is little-endian.
+    pub fn is_little_endian(self) -> bool {
+        use self::EncapsulationKind::*;
+
+        matches!(self, CdrLe | PlCdrLe | Cdr2Le)
+    }
+
+    /// Returns the largest alignment of the primitives of the data, which XCDR2
+    /// caps at 4.
+    pub fn max_alignment(self) -> usize {
+        use self::EncapsulationKind::*;
+
+        match self {
+            Cdr2Be | Cdr2Le => 4,
+            _ => 8,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -83,4 +157,26 @@ mod tests {
             (PlCdrLe::id().len() + PlCdrLe::option().len()) as u64
         );
     }
+
+    #[test]
+    fn test_kind() {
+        for &kind in &[
+            EncapsulationKind::CdrBe,
+            EncapsulationKind::CdrLe,
+            EncapsulationKind::PlCdrBe,
+            EncapsulationKind::PlCdrLe,
+            EncapsulationKind::Cdr2Be,
+            EncapsulationKind::Cdr2Le,
+        ] {
+            assert_eq!(EncapsulationKind::from_id(kind.id()), Some(kind));
+            assert_eq!(kind.is_little_endian(), kind.id()[1] & 1 == 1);
+        }
+        assert_eq!(EncapsulationKind::from_id([0, 4]), None);
+        assert_eq!(EncapsulationKind::from_id([1, 0]), None);
+        assert_eq!(
+            EncapsulationKind::from_header(&[0, 7, 0, 0, 1]),
+            Ok(EncapsulationKind::Cdr2Le)
+        );
+        assert_eq!(EncapsulationKind::from_header(&[0, 7, 0]), Err(Error::Eof));
+    }
 }
@@ -1,18 +1,23 @@
 use std::{
     self,
     fmt::{self, Display},
-    io,
+    io, mem,
     str::Utf8Error,
+    sync::Arc,
 };
 
 /// Convenient wrapper around `std::Result`.
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// The Error type.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum Error {
-    Message(String),
-    Io(io::Error),
+    /// A custom error raised by a `Serialize` or `Deserialize` implementation.
+    Custom(String),
+    /// The input ended before the value was complete.
+    Eof,
+    Io(Arc<io::Error>),
     DeserializeAnyNotSupported,
     InvalidBoolEncoding(u8),
     InvalidChar(char),
@@ -23,6 +28,124 @@ pub enum Error {
     SequenceMustHaveLength,
     SizeLimit,
     TypeNotSupported,
+    /// An error that occurred while deserializing the member at `path`, which
+    /// is empty for the top-level value. `offset` is the position, relative
+    /// to the alignment origin, of the value being read when the error
+    /// occurred.
+    At {
+        offset: u64,
+        path: String,
+        error: Box<Error>,
+    },
+}
+
+/// The broad category of an `Error`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Category {
+    /// The input ended before the value was complete.
+    Eof,
+    /// The underlying reader or writer failed.
+    Io,
+    /// The input is not a valid encoding of the value, or the value cannot
+    /// be represented in CDR.
+    InvalidData,
+    /// A size limit or the range of a length field was exceeded.
+    LimitExceeded,
+    /// The type or operation is not supported by CDR.
+    Unsupported,
+    /// A custom error raised by a `Serialize` or `Deserialize` implementation.
+    Custom,
+}
+
+impl Error {
+    /// Returns the category of the error.
+    pub fn category(&self) -> Category {
+        use Error::*;
+
+        match *self {
+            Custom(_) => Category::Custom,
+            Eof => Category::Eof,
+            Io(_) => Category::Io,
+            InvalidBoolEncoding(_)
+            | InvalidChar(_)
+            | InvalidCharEncoding
+            | InvalidEncapsulation
+            | InvalidUtf8Encoding(_) => Category::InvalidData,
+            NumberOutOfRange | SizeLimit => Category::LimitExceeded,
+            DeserializeAnyNotSupported | SequenceMustHaveLength | TypeNotSupported => {
+                Category::Unsupported
+            }
+            At { ref error, .. } => error.category(),
+        }
+    }
+
+    /// Returns the error without the location it occurred at.
+    pub fn root(&self) -> &Error {
+        match *self {
+            Error::At { ref error, .. } => error.root(),
+            ref error => error,
+        }
+    }
+
+    /// Returns the position of the value being read when the error occurred,
+    /// if known.
+    pub fn offset(&self) -> Option<u64> {
+        match *self {
+            Error::At { offset, .. } => Some(offset),
+            _ => None,
+        }
+    }
+
+    /// Returns the path of the member in which the error occurred, e.g.
+    /// `msg_info.points[42].intensity`, if known and the error did not occur
+    /// in the top-level value.
+    pub fn path(&self) -> Option<&str> {
+        match *self {
+            Error::At { ref path, .. } if !path.is_empty() => Some(path),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn within(self, segment: PathSegment, offset: u64) -> Self {
+        match self {
+            Error::At {
+                offset,
+                path,
+                error,
+            } => Error::At {
+                offset,
+                path: segment.prepend_to(&path),
+                error,
+            },
+            error => Error::At {
+                offset,
+                path: segment.prepend_to(""),
+                error: Box::new(error),
+            },
+        }
+    }
+}
+
+/// A component of the path to a member.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum PathSegment {
+    Field(&'static str),
+    Index(usize),
+}
+
+impl PathSegment {
+    fn prepend_to(self, path: &str) -> String {
+        let sep = if path.is_empty() || path.starts_with('[') {
+            ""
+        } else {
+            "."
+        };
+        match self {
+            PathSegment::Field(name) => format!("{}{}{}", name, sep, path),
+            PathSegment::Index(index) => format!("[{}]{}{}", index, sep, path),
+        }
+    }
 }
 
 impl Display for Error {
@@ -30,7 +153,8 @@ impl Display for Error {
         use Error::*;
 
         match *self {
-            Message(ref msg) => Display::fmt(msg, f),
+            Custom(ref msg) => Display::fmt(msg, f),
+            Eof => write!(f, "unexpected end of input"),
             Io(ref err) => Display::fmt(err, f),
             DeserializeAnyNotSupported => write!(
                 f,
@@ -47,38 +171,68 @@ impl Display for Error {
             }
             SizeLimit => write!(f, "the size limit has been reached"),
             TypeNotSupported => write!(f, "unsupported type"),
+            At {
+                offset,
+                ref path,
+                ref error,
+            } if path.is_empty() => write!(f, "{} (offset {})", error, offset),
+            At {
+                offset,
+                ref path,
+                ref error,
+            } => write!(f, "{} at {} (offset {})", error, path, offset),
         }
     }
 }
 
 impl std::error::Error for Error {
-    fn description(&self) -> &str {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         use Error::*;
 
         match *self {
-            Io(ref err) => err.description(),
-            InvalidUtf8Encoding(ref err) => err.description(),
-            _ => {
-                // If you want a better message, use Display::fmt or to_string().
-                "CDR error"
-            }
+            Io(ref err) => Some(&**err),
+            InvalidUtf8Encoding(ref err) => Some(err),
+            At { ref error, .. } => error.source(),
+            _ => None,
         }
     }
+}
 
-    fn cause(&self) -> Option<&dyn std::error::Error> {
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
         use Error::*;
 
-        match *self {
-            Io(ref err) => Some(err),
-            InvalidUtf8Encoding(ref err) => Some(err),
-            _ => None,
+        match (self, other) {
+            (Custom(a), Custom(b)) => a == b,
+            (Io(a), Io(b)) => {
+                Arc::ptr_eq(a, b) || (a.kind() == b.kind() && a.to_string() == b.to_string())
+            }
+            (InvalidBoolEncoding(a), InvalidBoolEncoding(b)) => a == b,
+            (InvalidChar(a), InvalidChar(b)) => a == b,
+            (InvalidUtf8Encoding(a), InvalidUtf8Encoding(b)) => a == b,
+            (
+                At {
+                    offset: a_offset,
+                    path: a_path,
+                    error: a_error,
+                },
+                At {
+                    offset: b_offset,
+                    path: b_path,
+                    error: b_error,
+                },
+            ) => a_offset == b_offset && a_path == b_path && a_error == b_error,
+            _ => mem::discriminant(self) == mem::discriminant(other),
         }
     }
 }
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self {
-        Error::Io(err)
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => Error::Eof,
+            _ => Error::Io(Arc::new(err)),
+        }
     }
 }
 
@@ -87,7 +241,7 @@ impl serde::de::Error for Error {
     where
         T: fmt::Display,
     {
-        Error::Message(msg.to_string())
+        Error::Custom(msg.to_string())
     }
 }
 
@@ -96,6 +250,6 @@ impl serde::ser::Error for Error {
     where
         T: fmt::Display,
     {
-        Error::Message(msg.to_string())
+        Error::Custom(msg.to_string())
     }
 }
@@ -0,0 +1,510 @@
+//! Showing where every member of a value ends up in its encoding.
+//!
+//! ```rust
+//! use cdr::EncapsulationKind;
+//! use serde_derive::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct Reading {
+//!     id: u8,
+//!     value: f64,
+//! }
+//!
+//! let explanation = cdr::explain(&Reading { id: 1, value: 0.5 }, EncapsulationKind::CdrLe).unwrap();
+//! let value = &explanation.fields[1];
+//! assert_eq!((value.path.as_str(), value.offset, value.padding, value.len), ("value", 12, 7, 8));
+//! print!("{}", explanation);
+//! ```
+//!
+//! prints
+//!
+//! ```text
+//! 0x0000  00 01 00 00               (header)
+//! 0x0004  01                        id
+//! 0x0005  00 00 00 00 00 00 00      (padding)
+//! 0x000c  00 00 00 00 00 00 e0 3f   value
+//! ```
+
+use std::fmt;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use serde::ser::{self, Serialize};
+
+use crate::bulk;
+use crate::encapsulation::{EncapsulationKind, ENCAPSULATION_HEADER_SIZE};
+use crate::error::{Error, Result};
+use crate::ser::Serializer;
+
+/// The encoding of a value along with the layout of its members.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Explanation {
+    /// The encoding, starting with the encapsulation header.
+    pub bytes: Vec<u8>,
+    /// The primitives, strings and lengths of sequences in the encoding, in
+    /// order.
+    pub fields: Vec<Field>,
+}
+
+/// The location of a primitive, string or sequence length in an encoding.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Field {
+    /// The path of the member, e.g. `points[1].intensity`. The length of a
+    /// sequence and the discriminator of an enum have the path of the
+    /// sequence or the enum.
+    pub path: String,
+    /// The position of the first byte in `Explanation::bytes`.
+    pub offset: usize,
+    /// The number of bytes of padding that precede it.
+    pub padding: usize,
+    /// The number of bytes it occupies.
+    pub len: usize,
+}
+
+/// The number of bytes shown on a line.
+const BYTES_PER_LINE: usize = 8;
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let header = ENCAPSULATION_HEADER_SIZE as usize;
+        write_bytes(f, 0, &self.bytes[..header], "(header)")?;
+        for field in &self.fields {
+            if field.padding > 0 {
+                let start = field.offset - field.padding;
+                write_bytes(f, start, &self.bytes[start..field.offset], "(padding)")?;
+            }
+            let end = field.offset + field.len;
+            write_bytes(f, field.offset, &self.bytes[field.offset..end], &field.path)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_bytes(f: &mut fmt::Formatter, offset: usize, bytes: &[u8], label: &str) -> fmt::Result {
+    for (i, chunk) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        let hex = chunk
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let label = if i == 0 { label } else { "" };
+        let line = format!(
+            "{:#06x}  {:<w$}  {}",
+            offset + i * BYTES_PER_LINE,
+            hex,
+            label,
+            w = 3 * BYTES_PER_LINE
+        );
+        writeln!(f, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Serializes a value with the encapsulation and describes where each of its
+/// members is written.
+pub fn explain<T>(value: &T, kind: EncapsulationKind) -> Result<Explanation>
+where
+    T: ?Sized + Serialize,
+{
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&kind.id());
+    bytes.extend_from_slice(&[0; 2]);
+    let fields = if kind.is_little_endian() {
+        explain_data::<_, LittleEndian>(&mut bytes, value, kind.max_alignment())?
+    } else {
+        explain_data::<_, BigEndian>(&mut bytes, value, kind.max_alignment())?
+    };
+    Ok(Explanation { bytes, fields })
+}
+
+fn explain_data<T, E>(bytes: &mut Vec<u8>, value: &T, max_alignment: usize) -> Result<Vec<Field>>
+where
+    T: ?Sized + Serialize,
+    E: ByteOrder,
+{
+    let mut explainer = Explainer {
+        ser: Serializer::<_, E>::with_alignment(bytes, 0, max_alignment),
+        max_alignment,
+        path: Vec::new(),
+        fields: Vec::new(),
+    };
+    value.serialize(&mut explainer)?;
+    Ok(explainer.fields)
+}
+
+#[derive(Clone, Copy)]
+enum Segment {
+    Field(&'static str),
+    Index(usize),
+}
+
+/// A serializer that records the position of everything it writes.
+struct Explainer<'a, E> {
+    ser: Serializer<&'a mut Vec<u8>, E>,
+    max_alignment: usize,
+    path: Vec<Segment>,
+    fields: Vec<Field>,
+}
+
+impl<'a, E> Explainer<'a, E>
+where
+    E: ByteOrder,
+{
+    /// Writes a value whose first primitive is `size` bytes long and records
+    /// it under the current path.
+    fn record<F>(&mut self, size: usize, write: F) -> Result<()>
+    where
+        F: FnOnce(&mut Serializer<&'a mut Vec<u8>, E>) -> Result<()>,
+    {
+        let start = self.ser.position() as usize;
+        let alignment = std::cmp::min(size, self.max_alignment);
+        let padding = (alignment - start % alignment) % alignment;
+        write(&mut self.ser)?;
+        let end = self.ser.position() as usize;
+        self.fields.push(Field {
+            path: self.path(),
+            offset: ENCAPSULATION_HEADER_SIZE as usize + start + padding,
+            padding,
+            len: end - start - padding,
+        });
+        Ok(())
+    }
+
+    fn path(&self) -> String {
+        let mut path = String::new();
+        for segment in &self.path {
+            match *segment {
+                Segment::Field(name) => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(name);
+                }
+                Segment::Index(index) => path.push_str(&format!("[{}]", index)),
+            }
+        }
+        path
+    }
+
+    fn within<T>(&mut self, segment: Segment, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.path.push(segment);
+        let result = value.serialize(&mut *self);
+        self.path.pop();
+        result
+    }
+}
+
+macro_rules! impl_record {
+    ($($method:ident: $ty:ty,)*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<()> {
+                self.record(std::mem::size_of::<$ty>(), |ser| ser.$method(v))
+            }
+        )*
+    };
+}
+
+impl<'a, 'b, E> ser::Serializer for &'b mut Explainer<'a, E>
+where
+    E: ByteOrder,
+{
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Compound<'a, 'b, E>;
+    type SerializeTuple = Compound<'a, 'b, E>;
+    type SerializeTupleStruct = Compound<'a, 'b, E>;
+    type SerializeTupleVariant = Compound<'a, 'b, E>;
+    type SerializeMap = ser::Impossible<(), Error>;
+    type SerializeStruct = Compound<'a, 'b, E>;
+    type SerializeStructVariant = Compound<'a, 'b, E>;
+
+    impl_record! {
+        serialize_bool: bool,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64,
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_f32: f32,
+        serialize_f64: f64,
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.record(1, |ser| ser.serialize_char(v))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.record(4, |ser| ser.serialize_str(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.record(4, |ser| ser.serialize_bytes(v))
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        self.ser.serialize_none()
+    }
+
+    fn serialize_some<T>(self, v: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.ser.serialize_some(v)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<()> {
+        self.serialize_u32(variant_index)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if bulk::element_size(name).is_some() {
+            return self.record(4, |ser| ser.serialize_newtype_struct(name, value));
+        }
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.serialize_u32(variant_index)?;
+        self.within(Segment::Field(variant), value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.record(4, |ser| ser.serialize_seq(len).map(drop))?;
+        Ok(Compound::new(self, false))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Ok(Compound::new(self, false))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Ok(Compound::new(self, false))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        self.serialize_u32(variant_index)?;
+        self.path.push(Segment::Field(variant));
+        Ok(Compound::new(self, true))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(Error::TypeNotSupported)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(Compound::new(self, false))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        self.serialize_u32(variant_index)?;
+        self.path.push(Segment::Field(variant));
+        Ok(Compound::new(self, true))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct Compound<'a, 'b, E> {
+    explainer: &'b mut Explainer<'a, E>,
+    index: usize,
+    /// Whether the segment of a variant is to be popped at the end.
+    variant: bool,
+}
+
+impl<'a, 'b, E> Compound<'a, 'b, E>
+where
+    E: ByteOrder,
+{
+    fn new(explainer: &'b mut Explainer<'a, E>, variant: bool) -> Self {
+        Compound {
+            explainer,
+            index: 0,
+            variant,
+        }
+    }
+
+    fn element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let segment = Segment::Index(self.index);
+        self.index += 1;
+        self.explainer.within(segment, value)
+    }
+
+    fn field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.explainer.within(Segment::Field(key), value)
+    }
+
+    fn end(self) -> Result<()> {
+        if self.variant {
+            self.explainer.path.pop();
+        }
+        Ok(())
+    }
+}
+
+impl<'a, 'b, E> ser::SerializeSeq for Compound<'a, 'b, E>
+where
+    E: ByteOrder,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, 'b, E> ser::SerializeTuple for Compound<'a, 'b, E>
+where
+    E: ByteOrder,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, 'b, E> ser::SerializeTupleStruct for Compound<'a, 'b, E>
+where
+    E: ByteOrder,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, 'b, E> ser::SerializeTupleVariant for Compound<'a, 'b, E>
+where
+    E: ByteOrder,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, 'b, E> ser::SerializeStruct for Compound<'a, 'b, E>
+where
+    E: ByteOrder,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, 'b, E> ser::SerializeStructVariant for Compound<'a, 'b, E>
+where
+    E: ByteOrder,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        Compound::end(self)
+    }
+}
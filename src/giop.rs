@@ -0,0 +1,684 @@
+//! GIOP messages, which carry CORBA requests and replies.
+//!
+//! A GIOP message is a 12-byte header followed by a body in CDR whose
+//! alignment origin is the start of the header, as specified in CORBA 3.3,
+//! Part 2, 9.4. The byte order of the body is given by the header. Versions
+//! 1.0 to 1.2 are supported.
+//!
+//! ```rust
+//! use cdr::giop::{self, RequestHeader, Version};
+//!
+//! let header = RequestHeader::new(1, b"NameService", "resolve_str");
+//! let request = giop::encode_request(Version::V1_2, false, &header, "Echo").unwrap();
+//! let bytes = request.to_bytes();
+//!
+//! let request = giop::Message::read_from(&bytes[..]).unwrap();
+//! let (header, mut body) = giop::decode_request(&request).unwrap();
+//! assert_eq!(header.operation, "resolve_str");
+//! assert_eq!(body.read::<String>().unwrap(), "Echo");
+//! ```
+
+use std::{fmt, io::Read};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use serde::{de, de::DeserializeOwned, ser, Deserialize, Serialize};
+
+use crate::de::Deserializer;
+use crate::error::{Error, Result};
+use crate::ior::{Ior, TaggedProfile};
+use crate::ser::Serializer;
+use crate::size::Infinite;
+
+const MAGIC: [u8; 4] = *b"GIOP";
+
+/// The version of GIOP.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl Version {
+    pub const V1_0: Version = Version { major: 1, minor: 0 };
+    pub const V1_1: Version = Version { major: 1, minor: 1 };
+    pub const V1_2: Version = Version { major: 1, minor: 2 };
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// The type of a message.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MessageType {
+    Request = 0,
+    Reply = 1,
+    CancelRequest = 2,
+    LocateRequest = 3,
+    LocateReply = 4,
+    CloseConnection = 5,
+    MessageError = 6,
+    /// The continuation of a fragmented message, since GIOP 1.1.
+    Fragment = 7,
+}
+
+impl MessageType {
+    fn from_u8(value: u8) -> Option<Self> {
+        use self::MessageType::*;
+
+        [
+            Request,
+            Reply,
+            CancelRequest,
+            LocateRequest,
+            LocateReply,
+            CloseConnection,
+            MessageError,
+            Fragment,
+        ]
+        .get(value as usize)
+        .copied()
+    }
+}
+
+/// The header that starts every message.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MessageHeader {
+    pub version: Version,
+    /// The byte order of the message in bit 0 and, since GIOP 1.1, whether
+    /// more fragments follow in bit 1.
+    pub flags: u8,
+    pub message_type: MessageType,
+    /// The number of bytes of the body.
+    pub message_size: u32,
+}
+
+impl MessageHeader {
+    /// The number of bytes of a header.
+    pub const SIZE: usize = 12;
+
+    const LITTLE_ENDIAN: u8 = 0x01;
+    const MORE_FRAGMENTS: u8 = 0x02;
+
+    /// Creates the header of an unfragmented message.
+    pub fn new(
+        version: Version,
+        message_type: MessageType,
+        little_endian: bool,
+        message_size: u32,
+    ) -> Self {
+        MessageHeader {
+            version,
+            flags: if little_endian {
+                Self::LITTLE_ENDIAN
+            } else {
+                0
+            },
+            message_type,
+            message_size,
+        }
+    }
+
+    /// Parses a header from the first 12 bytes of a message.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let bytes = bytes.get(..Self::SIZE).ok_or(Error::Eof)?;
+        if bytes[..4] != MAGIC {
+            return Err(Error::Custom("not a GIOP message".to_string()));
+        }
+        let version = Version {
+            major: bytes[4],
+            minor: bytes[5],
+        };
+        if version.major != 1 || version.minor > 2 {
+            return Err(Error::Custom(format!(
+                "unsupported GIOP version {}",
+                version
+            )));
+        }
+        let message_type = MessageType::from_u8(bytes[7])
+            .filter(|&t| t != MessageType::Fragment || version >= Version::V1_1)
+            .ok_or_else(|| Error::Custom(format!("unknown GIOP message type {}", bytes[7])))?;
+        let header = MessageHeader {
+            version,
+            flags: bytes[6],
+            message_type,
+            message_size: 0,
+        };
+        let message_size = if header.is_little_endian() {
+            LittleEndian::read_u32(&bytes[8..])
+        } else {
+            BigEndian::read_u32(&bytes[8..])
+        };
+        Ok(MessageHeader {
+            message_size,
+            ..header
+        })
+    }
+
+    /// Returns the bytes of the header.
+    pub fn to_bytes(&self) -> [u8; 12] {
+        let mut bytes = [0; 12];
+        bytes[..4].copy_from_slice(&MAGIC);
+        bytes[4] = self.version.major;
+        bytes[5] = self.version.minor;
+        bytes[6] = self.flags;
+        bytes[7] = self.message_type as u8;
+        if self.is_little_endian() {
+            LittleEndian::write_u32(&mut bytes[8..], self.message_size);
+        } else {
+            BigEndian::write_u32(&mut bytes[8..], self.message_size);
+        }
+        bytes
+    }
+
+    /// Returns whether the message is in little-endian byte order.
+    pub fn is_little_endian(&self) -> bool {
+        self.flags & Self::LITTLE_ENDIAN != 0
+    }
+
+    /// Returns whether more fragments of the message follow.
+    pub fn has_more_fragments(&self) -> bool {
+        self.version >= Version::V1_1 && self.flags & Self::MORE_FRAGMENTS != 0
+    }
+}
+
+/// A message with its body.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Message {
+    pub header: MessageHeader,
+    pub body: Vec<u8>,
+}
+
+impl Message {
+    /// Reads a message, such as from a connection.
+    pub fn read_from<R>(mut reader: R) -> Result<Self>
+    where
+        R: Read,
+    {
+        let mut header = [0; MessageHeader::SIZE];
+        reader.read_exact(&mut header)?;
+        let header = MessageHeader::parse(&header)?;
+        let size = u64::from(header.message_size);
+        let mut body = Vec::new();
+        reader.take(size).read_to_end(&mut body)?;
+        if (body.len() as u64) < size {
+            return Err(Error::Eof);
+        }
+        Ok(Message { header, body })
+    }
+
+    /// Returns the bytes of the message.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MessageHeader::SIZE + self.body.len());
+        bytes.extend_from_slice(&self.header.to_bytes());
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+
+    /// Returns a reader of the body from its start.
+    pub fn body(&self) -> BodyReader<'_> {
+        BodyReader {
+            body: &self.body,
+            pos: 0,
+            little_endian: self.header.is_little_endian(),
+        }
+    }
+}
+
+/// Writes values one after another into the body of a message.
+#[derive(Clone, Debug)]
+pub struct BodyWriter {
+    body: Vec<u8>,
+    little_endian: bool,
+}
+
+impl BodyWriter {
+    /// Creates a writer of an empty body in the given byte order.
+    pub fn new(little_endian: bool) -> Self {
+        BodyWriter {
+            body: Vec::new(),
+            little_endian,
+        }
+    }
+
+    /// Serializes a value, aligned relative to the start of the message.
+    pub fn write<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let offset = (MessageHeader::SIZE + self.body.len()) as u64;
+        if self.little_endian {
+            value.serialize(&mut Serializer::<_, LittleEndian>::with_alignment(
+                &mut self.body,
+                offset,
+                8,
+            ))
+        } else {
+            value.serialize(&mut Serializer::<_, BigEndian>::with_alignment(
+                &mut self.body,
+                offset,
+                8,
+            ))
+        }
+    }
+
+    /// Pads the body so that the next value starts at a multiple of
+    /// `alignment` bytes from the start of the message.
+    pub fn align(&mut self, alignment: usize) {
+        let pos = MessageHeader::SIZE + self.body.len();
+        let padding = (alignment - pos % alignment) % alignment;
+        self.body.resize(self.body.len() + padding, 0);
+    }
+
+    /// Completes a message of the given type.
+    pub fn finish(self, version: Version, message_type: MessageType) -> Result<Message> {
+        if self.body.len() > u32::MAX as usize {
+            return Err(Error::SizeLimit);
+        }
+        Ok(Message {
+            header: MessageHeader::new(
+                version,
+                message_type,
+                self.little_endian,
+                self.body.len() as u32,
+            ),
+            body: self.body,
+        })
+    }
+}
+
+/// Reads values one after another from the body of a message.
+#[derive(Clone, Debug)]
+pub struct BodyReader<'a> {
+    body: &'a [u8],
+    pos: usize,
+    little_endian: bool,
+}
+
+impl<'a> BodyReader<'a> {
+    /// Deserializes a value, aligned relative to the start of the message.
+    pub fn read<T>(&mut self) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let rest = &self.body[self.pos..];
+        let offset = (MessageHeader::SIZE + self.pos) as u64;
+        let (value, end) = if self.little_endian {
+            let mut de =
+                Deserializer::<_, _, LittleEndian>::with_alignment(rest, Infinite, offset, 8);
+            (T::deserialize(&mut de)?, de.position())
+        } else {
+            let mut de = Deserializer::<_, _, BigEndian>::with_alignment(rest, Infinite, offset, 8);
+            (T::deserialize(&mut de)?, de.position())
+        };
+        self.pos = end as usize - MessageHeader::SIZE;
+        Ok(value)
+    }
+
+    /// Skips the padding before a value aligned to `alignment` bytes from
+    /// the start of the message, if any data follows.
+    pub fn align(&mut self, alignment: usize) {
+        let pos = MessageHeader::SIZE + self.pos;
+        let padding = (alignment - pos % alignment) % alignment;
+        self.pos = std::cmp::min(self.pos + padding, self.body.len());
+    }
+
+    /// Returns the bytes that have not been read.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.body[self.pos..]
+    }
+}
+
+/// Context information that accompanies a request or a reply.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ServiceContext {
+    pub context_id: u32,
+    pub context_data: Vec<u8>,
+}
+
+impl Serialize for ServiceContext {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        (self.context_id, &self.context_data).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ServiceContext {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let (context_id, context_data) = Deserialize::deserialize(deserializer)?;
+        Ok(ServiceContext {
+            context_id,
+            context_data,
+        })
+    }
+}
+
+/// The object that a request is addressed to.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TargetAddress {
+    /// The object key from the profile of the object reference.
+    Key(Vec<u8>),
+    /// The profile of the object reference, since GIOP 1.2.
+    Profile(TaggedProfile),
+    /// The object reference and the index of the profile chosen by the
+    /// client, since GIOP 1.2.
+    Reference {
+        selected_profile_index: u32,
+        ior: Ior,
+    },
+}
+
+/// The header of a request.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RequestHeader {
+    pub request_id: u32,
+    /// Whether a reply is expected in bit 0 and, since GIOP 1.2, whether
+    /// the server should reply once the request reaches the target in bit 1.
+    /// GIOP 1.0 and 1.1 only carry bit 0.
+    pub response_flags: u8,
+    /// The target, which has to be a `TargetAddress::Key` before GIOP 1.2.
+    pub target: TargetAddress,
+    pub operation: String,
+    /// The principal of the caller, which is only carried before GIOP 1.2.
+    pub requesting_principal: Vec<u8>,
+    pub service_context: Vec<ServiceContext>,
+}
+
+impl RequestHeader {
+    /// Creates the header of a request that expects a reply.
+    pub fn new(request_id: u32, object_key: &[u8], operation: &str) -> Self {
+        RequestHeader {
+            request_id,
+            response_flags: 0x03,
+            target: TargetAddress::Key(object_key.to_vec()),
+            operation: operation.to_string(),
+            requesting_principal: Vec::new(),
+            service_context: Vec::new(),
+        }
+    }
+
+    /// Returns whether a reply is expected.
+    pub fn response_expected(&self) -> bool {
+        self.response_flags & 0x01 != 0
+    }
+
+    /// Serializes the header as specified by the version.
+    pub fn write(&self, writer: &mut BodyWriter, version: Version) -> Result<()> {
+        if version >= Version::V1_2 {
+            writer.write(&(self.request_id, self.response_flags, [0u8; 3]))?;
+            match self.target {
+                TargetAddress::Key(ref key) => writer.write(&(0i16, key))?,
+                TargetAddress::Profile(ref profile) => writer.write(&(1i16, profile))?,
+                TargetAddress::Reference {
+                    selected_profile_index,
+                    ref ior,
+                } => writer.write(&(2i16, selected_profile_index, ior))?,
+            }
+            writer.write(&(&self.operation, &self.service_context))
+        } else {
+            let key = match self.target {
+                TargetAddress::Key(ref key) => key,
+                _ => {
+                    return Err(Error::Custom(format!(
+                        "GIOP {} addresses targets only by object key",
+                        version
+                    )))
+                }
+            };
+            writer.write(&(&self.service_context, self.request_id))?;
+            writer.write(&self.response_expected())?;
+            if version == Version::V1_1 {
+                writer.write(&[0u8; 3])?;
+            }
+            writer.write(&(key, &self.operation, &self.requesting_principal))
+        }
+    }
+
+    /// Deserializes a header as specified by the version.
+    pub fn read(reader: &mut BodyReader, version: Version) -> Result<Self> {
+        if version >= Version::V1_2 {
+            let (request_id, response_flags, _): (u32, u8, [u8; 3]) = reader.read()?;
+            let target = match reader.read::<i16>()? {
+                0 => TargetAddress::Key(reader.read()?),
+                1 => TargetAddress::Profile(reader.read()?),
+                2 => {
+                    let (selected_profile_index, ior) = reader.read()?;
+                    TargetAddress::Reference {
+                        selected_profile_index,
+                        ior,
+                    }
+                }
+                disc => {
+                    return Err(Error::Custom(format!(
+                        "unknown addressing disposition {}",
+                        disc
+                    )))
+                }
+            };
+            let (operation, service_context) = reader.read()?;
+            Ok(RequestHeader {
+                request_id,
+                response_flags,
+                target,
+                operation,
+                requesting_principal: Vec::new(),
+                service_context,
+            })
+        } else {
+            let (service_context, request_id) = reader.read()?;
+            let response_expected: bool = reader.read()?;
+            if version == Version::V1_1 {
+                reader.read::<[u8; 3]>()?;
+            }
+            let (key, operation, requesting_principal) = reader.read()?;
+            Ok(RequestHeader {
+                request_id,
+                response_flags: response_expected as u8,
+                target: TargetAddress::Key(key),
+                operation,
+                requesting_principal,
+                service_context,
+            })
+        }
+    }
+}
+
+/// The outcome of a request.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ReplyStatus {
+    NoException = 0,
+    UserException = 1,
+    SystemException = 2,
+    LocationForward = 3,
+    /// Since GIOP 1.2.
+    LocationForwardPerm = 4,
+    /// Since GIOP 1.2.
+    NeedsAddressingMode = 5,
+}
+
+impl ReplyStatus {
+    fn from_u32(value: u32) -> Option<Self> {
+        use self::ReplyStatus::*;
+
+        [
+            NoException,
+            UserException,
+            SystemException,
+            LocationForward,
+            LocationForwardPerm,
+            NeedsAddressingMode,
+        ]
+        .get(value as usize)
+        .copied()
+    }
+}
+
+/// The header of a reply.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ReplyHeader {
+    pub request_id: u32,
+    pub reply_status: ReplyStatus,
+    pub service_context: Vec<ServiceContext>,
+}
+
+impl ReplyHeader {
+    /// Serializes the header as specified by the version.
+    pub fn write(&self, writer: &mut BodyWriter, version: Version) -> Result<()> {
+        let status = self.reply_status as u32;
+        if version >= Version::V1_2 {
+            writer.write(&(self.request_id, status, &self.service_context))
+        } else {
+            writer.write(&(&self.service_context, self.request_id, status))
+        }
+    }
+
+    /// Deserializes a header as specified by the version.
+    pub fn read(reader: &mut BodyReader, version: Version) -> Result<Self> {
+        let (request_id, status, service_context) = if version >= Version::V1_2 {
+            reader.read()?
+        } else {
+            let (service_context, request_id, status) = reader.read()?;
+            (request_id, status, service_context)
+        };
+        let reply_status = ReplyStatus::from_u32(status)
+            .ok_or_else(|| Error::Custom(format!("unknown reply status {}", status)))?;
+        Ok(ReplyHeader {
+            request_id,
+            reply_status,
+            service_context,
+        })
+    }
+}
+
+/// The body of a reply whose status is `ReplyStatus::SystemException`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SystemException {
+    /// The repository id of the exception, e.g.
+    /// `IDL:omg.org/CORBA/OBJECT_NOT_EXIST:1.0`.
+    pub exception_id: String,
+    pub minor_code_value: u32,
+    /// Whether the operation completed: 0 for yes, 1 for no and 2 for maybe.
+    pub completion_status: u32,
+}
+
+impl Serialize for SystemException {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        (
+            &self.exception_id,
+            self.minor_code_value,
+            self.completion_status,
+        )
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SystemException {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let (exception_id, minor_code_value, completion_status) =
+            Deserialize::deserialize(deserializer)?;
+        Ok(SystemException {
+            exception_id,
+            minor_code_value,
+            completion_status,
+        })
+    }
+}
+
+/// Builds a request message whose body holds the arguments.
+///
+/// Multiple arguments are passed as a tuple. Since GIOP 1.2, the arguments
+/// are aligned to 8 bytes.
+pub fn encode_request<T>(
+    version: Version,
+    little_endian: bool,
+    header: &RequestHeader,
+    arguments: &T,
+) -> Result<Message>
+where
+    T: ?Sized + Serialize,
+{
+    let mut writer = BodyWriter::new(little_endian);
+    header.write(&mut writer, version)?;
+    let header_len = writer.body.len();
+    if version >= Version::V1_2 {
+        writer.align(8);
+    }
+    let aligned_len = writer.body.len();
+    writer.write(arguments)?;
+    if writer.body.len() == aligned_len {
+        // The body is aligned only if it is not empty.
+        writer.body.truncate(header_len);
+    }
+    writer.finish(version, MessageType::Request)
+}
+
+/// Builds a reply message whose body holds the result.
+pub fn encode_reply<T>(
+    version: Version,
+    little_endian: bool,
+    header: &ReplyHeader,
+    result: &T,
+) -> Result<Message>
+where
+    T: ?Sized + Serialize,
+{
+    let mut writer = BodyWriter::new(little_endian);
+    header.write(&mut writer, version)?;
+    let header_len = writer.body.len();
+    if version >= Version::V1_2 {
+        writer.align(8);
+    }
+    let aligned_len = writer.body.len();
+    writer.write(result)?;
+    if writer.body.len() == aligned_len {
+        writer.body.truncate(header_len);
+    }
+    writer.finish(version, MessageType::Reply)
+}
+
+/// Reads the header of a request message and returns it along with a reader
+/// positioned at the arguments.
+pub fn decode_request(message: &Message) -> Result<(RequestHeader, BodyReader<'_>)> {
+    if message.header.message_type != MessageType::Request {
+        return Err(Error::Custom(format!(
+            "expected a request, found {:?}",
+            message.header.message_type
+        )));
+    }
+    let mut reader = message.body();
+    let header = RequestHeader::read(&mut reader, message.header.version)?;
+    if message.header.version >= Version::V1_2 {
+        reader.align(8);
+    }
+    Ok((header, reader))
+}
+
+/// Reads the header of a reply message and returns it along with a reader
+/// positioned at the result.
+pub fn decode_reply(message: &Message) -> Result<(ReplyHeader, BodyReader<'_>)> {
+    if message.header.message_type != MessageType::Reply {
+        return Err(Error::Custom(format!(
+            "expected a reply, found {:?}",
+            message.header.message_type
+        )));
+    }
+    let mut reader = message.body();
+    let header = ReplyHeader::read(&mut reader, message.header.version)?;
+    if message.header.version >= Version::V1_2 {
+        reader.align(8);
+    }
+    Ok((header, reader))
+}
@@ -0,0 +1,898 @@
+//! Reading type descriptors from OMG IDL.
+//!
+//! The subset of IDL 4 needed to describe plain data is supported: modules,
+//! structs (including inheritance), enums, unions, typedefs, integer
+//! constants, strings, sequences and arrays. Preprocessor directives are
+//! ignored, and so are annotations, except for those that change the encoding
+//! of a type: `@appendable`, `@mutable` and `@optional` are rejected, since
+//! type descriptors only describe final types without optional members.
+//!
+//! A union may be discriminated by an integer, a `boolean`, a `char`, an
+//! `octet` or an enum, and its cases may have several labels and a default.
+//!
+//! ```rust
+//! let definitions = cdr::idl::parse(
+//!     r#"
+//!     module geometry {
+//!         struct Point { double x; double y; };
+//!         typedef sequence<Point, 16> Polygon;
+//!     };
+//!     "#,
+//! )
+//! .unwrap();
+//! assert!(definitions.get("geometry::Polygon").is_some());
+//! ```
+
+use std::{collections::BTreeMap, convert::TryFrom, error, fmt, iter::Peekable, str::CharIndices};
+
+use crate::value::{Case, Member, StructDescriptor, TypeDescriptor, UnionDescriptor};
+
+/// The number of modules, sequences or parentheses that can be nested in one
+/// another.
+const MAX_NESTING: usize = 128;
+
+/// The types defined by an IDL file, by fully scoped name.
+#[derive(Clone, Debug, Default)]
+pub struct Definitions {
+    types: BTreeMap<String, TypeDescriptor>,
+}
+
+impl Definitions {
+    /// Returns the type with the given scoped name, e.g. `geometry::Point`.
+    pub fn get(&self, name: &str) -> Option<&TypeDescriptor> {
+        self.types.get(name.trim_start_matches("::"))
+    }
+
+    /// Returns the scoped names of the defined types.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.types.keys().map(String::as_str)
+    }
+}
+
+/// An error in an IDL file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(line: usize, column: usize, message: String) -> Self {
+        ParseError {
+            line,
+            column,
+            message,
+        }
+    }
+
+    /// Returns the line, starting at 1, at which the error occurred.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the column, starting at 1, at which the error occurred.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl error::Error for ParseError {}
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+/// Parses the definitions of an IDL file.
+pub fn parse(source: &str) -> Result<Definitions> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        index: 0,
+        depth: 0,
+        scope: Vec::new(),
+        definitions: Definitions::default(),
+        consts: BTreeMap::new(),
+    };
+    while !parser.at(&Token::Eof) {
+        parser.definition()?;
+    }
+    Ok(parser.definitions)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(u64),
+    Float,
+    Str,
+    Punct(char),
+    Scope,
+    Eof,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Token::Ident(ref name) => write!(f, "`{}`", name),
+            Token::Int(v) => write!(f, "`{}`", v),
+            Token::Float => write!(f, "floating point literal"),
+            Token::Str => write!(f, "string literal"),
+            Token::Punct(c) => write!(f, "`{}`", c),
+            Token::Scope => write!(f, "`::`"),
+            Token::Eof => write!(f, "end of file"),
+        }
+    }
+}
+
+struct Spanned {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn bump(&mut self) -> Option<char> {
+        let (_, c) = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.bump() {
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        ParseError::new(self.line, self.column, message)
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Spanned>> {
+    let mut lexer = Lexer {
+        source,
+        chars: source.char_indices().peekable(),
+        line: 1,
+        column: 1,
+    };
+    let mut tokens = Vec::new();
+    let mut line_start = true;
+    loop {
+        let (line, column) = (lexer.line, lexer.column);
+        let start = match lexer.chars.peek() {
+            Some(&(start, _)) => start,
+            None => {
+                tokens.push(Spanned {
+                    token: Token::Eof,
+                    line,
+                    column,
+                });
+                return Ok(tokens);
+            }
+        };
+        let c = lexer.bump().unwrap();
+        let token = match c {
+            '\n' => {
+                line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            '#' if line_start => {
+                lexer.skip_line();
+                continue;
+            }
+            '/' if lexer.peek() == Some('/') => {
+                lexer.skip_line();
+                line_start = true;
+                continue;
+            }
+            '/' if lexer.peek() == Some('*') => {
+                lexer.bump();
+                let mut last = ' ';
+                loop {
+                    match lexer.bump() {
+                        Some('/') if last == '*' => break,
+                        Some(c) => last = c,
+                        None => return Err(lexer.error("unterminated comment".to_string())),
+                    }
+                }
+                continue;
+            }
+            ':' if lexer.peek() == Some(':') => {
+                lexer.bump();
+                Token::Scope
+            }
+            '\'' => {
+                let c = match lexer.bump() {
+                    Some('\\') => match lexer.bump() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('0') => '\0',
+                        Some(c) => c,
+                        None => return Err(lexer.error("unterminated character".to_string())),
+                    },
+                    Some(c) => c,
+                    None => return Err(lexer.error("unterminated character".to_string())),
+                };
+                if lexer.bump() != Some('\'') {
+                    return Err(lexer.error("unterminated character".to_string()));
+                }
+                Token::Int(u64::from(u32::from(c)))
+            }
+            '"' => {
+                loop {
+                    match lexer.bump() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            lexer.bump();
+                        }
+                        Some(_) => {}
+                        None => return Err(lexer.error("unterminated string".to_string())),
+                    }
+                }
+                Token::Str
+            }
+            c if c.is_ascii_digit() => {
+                let mut end = start + 1;
+                while let Some(c) = lexer
+                    .peek()
+                    .filter(|c| c.is_ascii_alphanumeric() || *c == '.')
+                {
+                    lexer.bump();
+                    end += c.len_utf8();
+                }
+                let literal = &lexer.source[start..end];
+                let parsed = if let Some(hex) = literal
+                    .strip_prefix("0x")
+                    .or_else(|| literal.strip_prefix("0X"))
+                {
+                    u64::from_str_radix(hex, 16)
+                } else if literal.len() > 1 && literal.starts_with('0') && !literal.contains('.') {
+                    u64::from_str_radix(&literal[1..], 8)
+                } else {
+                    literal.parse()
+                };
+                match parsed {
+                    Ok(v) => Token::Int(v),
+                    Err(_) if literal.parse::<f64>().is_ok() => Token::Float,
+                    Err(_) => {
+                        return Err(ParseError {
+                            line,
+                            column,
+                            message: format!("invalid integer literal `{}`", literal),
+                        })
+                    }
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some(c) = lexer.peek().filter(|c| c.is_alphanumeric() || *c == '_') {
+                    lexer.bump();
+                    end += c.len_utf8();
+                }
+                Token::Ident(lexer.source[start..end].to_string())
+            }
+            c => Token::Punct(c),
+        };
+        line_start = false;
+        tokens.push(Spanned {
+            token,
+            line,
+            column,
+        });
+    }
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    index: usize,
+    depth: usize,
+    scope: Vec<String>,
+    definitions: Definitions,
+    consts: BTreeMap<String, i64>,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index].token
+    }
+
+    fn at(&self, token: &Token) -> bool {
+        self.peek() == token
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(*self.peek(), Token::Ident(ref name) if name == keyword)
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token != Token::Eof {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.at(token) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.at_keyword(keyword) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error<T>(&self, message: String) -> Result<T> {
+        self.error_at(self.index, message)
+    }
+
+    fn error_at<T>(&self, index: usize, message: String) -> Result<T> {
+        let spanned = &self.tokens[index];
+        Err(ParseError::new(spanned.line, spanned.column, message))
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> Result<T> {
+        self.error(format!("expected {}, found {}", expected, self.peek()))
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(&Token::Punct(c)) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{}`", c))
+        }
+    }
+
+    /// Parses something nested in a construct being parsed.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth >= MAX_NESTING {
+            return self.error("definitions are nested too deeply".to_string());
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match *self.peek() {
+            Token::Ident(_) => match self.next() {
+                Token::Ident(name) => Ok(name),
+                _ => unreachable!(),
+            },
+            _ => self.unexpected("an identifier"),
+        }
+    }
+
+    fn scoped(&self, name: &str) -> String {
+        let mut scoped = self.scope.join("::");
+        if !scoped.is_empty() {
+            scoped.push_str("::");
+        }
+        scoped.push_str(name);
+        scoped
+    }
+
+    /// Skips annotations such as `@key` or `@range(min = 0, max = 9)`, and
+    /// rejects those that change the encoding of a type.
+    fn annotations(&mut self) -> Result<()> {
+        while self.eat(&Token::Punct('@')) {
+            let start = self.index;
+            let mut name = self.ident()?;
+            while self.eat(&Token::Scope) {
+                name = self.ident()?;
+            }
+            let mut arguments = Vec::new();
+            if self.eat(&Token::Punct('(')) {
+                let mut depth = 1;
+                loop {
+                    match self.next() {
+                        Token::Punct('(') => depth += 1,
+                        Token::Punct(')') if depth == 1 => break,
+                        Token::Punct(')') => depth -= 1,
+                        Token::Eof => return self.unexpected("`)`"),
+                        token => arguments.push(token),
+                    }
+                }
+            }
+            let unsupported = match name.as_str() {
+                "appendable" | "mutable" | "optional" => true,
+                "extensibility" => arguments != [Token::Ident("FINAL".to_string())],
+                _ => false,
+            };
+            if unsupported {
+                return self.error_at(start, format!("`@{}` is not supported", name));
+            }
+        }
+        Ok(())
+    }
+
+    fn definition(&mut self) -> Result<()> {
+        self.annotations()?;
+        if self.eat(&Token::Punct(';')) {
+            return Ok(());
+        }
+        let keyword = match *self.peek() {
+            Token::Ident(ref keyword) => keyword.clone(),
+            _ => return self.unexpected("a definition"),
+        };
+        match keyword.as_str() {
+            "module" => self.module(),
+            "struct" => self.structure(),
+            "union" => self.union(),
+            "enum" => self.enumeration(),
+            "typedef" => self.typedef(),
+            "const" => self.constant(),
+            _ => self.error(format!("unsupported definition `{}`", keyword)),
+        }
+    }
+
+    fn module(&mut self) -> Result<()> {
+        self.next();
+        let name = self.ident()?;
+        self.expect('{')?;
+        self.scope.push(name);
+        while !self.eat(&Token::Punct('}')) {
+            if self.at(&Token::Eof) {
+                return self.unexpected("`}`");
+            }
+            self.nested(Self::definition)?;
+        }
+        self.scope.pop();
+        self.expect(';')
+    }
+
+    fn structure(&mut self) -> Result<()> {
+        self.next();
+        let name = self.ident()?;
+        let name = self.scoped(&name);
+        if self.eat(&Token::Punct(';')) {
+            // A forward declaration
+            return Ok(());
+        }
+        let mut members = Vec::new();
+        if self.eat(&Token::Punct(':')) {
+            match self.scoped_type()? {
+                TypeDescriptor::Struct(base) => members = base.members,
+                _ => return self.error("the base of a struct must be a struct".to_string()),
+            }
+        }
+        self.expect('{')?;
+        while !self.eat(&Token::Punct('}')) {
+            self.annotations()?;
+            let ty = self.type_spec()?;
+            for (name, ty) in self.declarators(ty)? {
+                members.push(Member::new(name, ty));
+            }
+            self.expect(';')?;
+        }
+        self.expect(';')?;
+        self.define(
+            name.clone(),
+            TypeDescriptor::Struct(StructDescriptor { name, members }),
+        );
+        Ok(())
+    }
+
+    fn union(&mut self) -> Result<()> {
+        self.next();
+        let name = self.ident()?;
+        let name = self.scoped(&name);
+        if self.eat(&Token::Punct(';')) {
+            return Ok(());
+        }
+        if !self.eat_keyword("switch") {
+            return self.unexpected("`switch`");
+        }
+        self.expect('(')?;
+        self.annotations()?;
+        let start = self.index;
+        let discriminator = self.type_spec()?;
+        match discriminator {
+            TypeDescriptor::Bool
+            | TypeDescriptor::Char
+            | TypeDescriptor::I8
+            | TypeDescriptor::I16
+            | TypeDescriptor::I32
+            | TypeDescriptor::I64
+            | TypeDescriptor::U8
+            | TypeDescriptor::U16
+            | TypeDescriptor::U32
+            | TypeDescriptor::U64 => {}
+            TypeDescriptor::Union(ref desc) if desc.is_enumeration() => {}
+            _ => {
+                return self.error_at(
+                    start,
+                    "unions must be discriminated by an integer, a boolean, a char, an octet \
+                     or an enum"
+                        .to_string(),
+                )
+            }
+        }
+        self.expect(')')?;
+        self.expect('{')?;
+        let mut desc = UnionDescriptor {
+            name: name.clone(),
+            discriminator: Box::new(discriminator),
+            cases: Vec::new(),
+            default: None,
+        };
+        while !self.eat(&Token::Punct('}')) {
+            let mut labels = Vec::new();
+            let mut default = false;
+            loop {
+                let start = self.index;
+                if self.eat_keyword("default") {
+                    if default || desc.default.is_some() {
+                        return self.error_at(start, "duplicate default case".to_string());
+                    }
+                    default = true;
+                } else if self.eat_keyword("case") {
+                    let start = self.index;
+                    let label = self.const_expr()?;
+                    if desc.discriminator_value(label).is_none() {
+                        return self.error_at(
+                            start,
+                            format!("invalid label {} for the discriminator", label),
+                        );
+                    }
+                    if labels.contains(&label)
+                        || desc.cases.iter().any(|case| case.labels.contains(&label))
+                    {
+                        return self.error_at(start, format!("duplicate label {}", label));
+                    }
+                    labels.push(label);
+                } else {
+                    return self.unexpected("`case` or `default`");
+                }
+                self.expect(':')?;
+                if !self.at_keyword("case") && !self.at_keyword("default") {
+                    break;
+                }
+            }
+            self.annotations()?;
+            let ty = self.type_spec()?;
+            let (member, ty) = self.declarator(ty)?;
+            let member = Member::new(member, ty);
+            if default {
+                desc.default = Some(Box::new(member.clone()));
+            }
+            if !labels.is_empty() {
+                desc.cases.push(Case { labels, member });
+            }
+            self.expect(';')?;
+        }
+        self.expect(';')?;
+        self.define(name, TypeDescriptor::Union(desc));
+        Ok(())
+    }
+
+    fn enumeration(&mut self) -> Result<()> {
+        self.next();
+        let name = self.ident()?;
+        let name = self.scoped(&name);
+        self.expect('{')?;
+        let mut variants = Vec::new();
+        loop {
+            self.annotations()?;
+            let enumerator = self.ident()?;
+            // Enumerators are scoped like the enum itself
+            self.consts
+                .insert(self.scoped(&enumerator), variants.len() as i64);
+            variants.push(Member::new(enumerator, TypeDescriptor::Unit));
+            if !self.eat(&Token::Punct(',')) {
+                break;
+            }
+        }
+        self.expect('}')?;
+        self.expect(';')?;
+        self.define(
+            name.clone(),
+            TypeDescriptor::Union(UnionDescriptor::from_variants(name, variants)),
+        );
+        Ok(())
+    }
+
+    fn typedef(&mut self) -> Result<()> {
+        self.next();
+        let ty = self.type_spec()?;
+        for (name, ty) in self.declarators(ty)? {
+            let name = self.scoped(&name);
+            self.define(name, ty);
+        }
+        self.expect(';')
+    }
+
+    fn constant(&mut self) -> Result<()> {
+        self.next();
+        match self.type_spec()? {
+            TypeDescriptor::I8
+            | TypeDescriptor::I16
+            | TypeDescriptor::I32
+            | TypeDescriptor::I64
+            | TypeDescriptor::U8
+            | TypeDescriptor::U16
+            | TypeDescriptor::U32
+            | TypeDescriptor::U64 => {}
+            _ => {
+                // Constants of other types cannot size anything, so they are skipped
+                while !self.eat(&Token::Punct(';')) {
+                    if self.next() == Token::Eof {
+                        return self.unexpected("`;`");
+                    }
+                }
+                return Ok(());
+            }
+        }
+        let name = self.ident()?;
+        let name = self.scoped(&name);
+        self.expect('=')?;
+        let value = self.const_expr()?;
+        self.consts.insert(name, value);
+        self.expect(';')
+    }
+
+    fn define(&mut self, name: String, ty: TypeDescriptor) {
+        self.definitions.types.insert(name, ty);
+    }
+
+    fn declarators(&mut self, ty: TypeDescriptor) -> Result<Vec<(String, TypeDescriptor)>> {
+        let mut declarators = vec![self.declarator(ty.clone())?];
+        while self.eat(&Token::Punct(',')) {
+            declarators.push(self.declarator(ty.clone())?);
+        }
+        Ok(declarators)
+    }
+
+    fn declarator(&mut self, ty: TypeDescriptor) -> Result<(String, TypeDescriptor)> {
+        let name = self.ident()?;
+        let mut dims = Vec::new();
+        while self.eat(&Token::Punct('[')) {
+            dims.push(self.bound()?);
+            self.expect(']')?;
+        }
+        let ty = dims
+            .into_iter()
+            .rev()
+            .fold(ty, |ty, len| TypeDescriptor::Array(Box::new(ty), len));
+        Ok((name, ty))
+    }
+
+    fn type_spec(&mut self) -> Result<TypeDescriptor> {
+        use crate::value::TypeDescriptor as T;
+
+        let keyword = match *self.peek() {
+            Token::Ident(ref keyword) => keyword.clone(),
+            Token::Scope => return self.scoped_type(),
+            _ => return self.unexpected("a type"),
+        };
+        let ty = match keyword.as_str() {
+            "boolean" => T::Bool,
+            "char" => T::Char,
+            "octet" | "uint8" => T::U8,
+            "int8" => T::I8,
+            "short" | "int16" => T::I16,
+            "int32" => T::I32,
+            "int64" => T::I64,
+            "uint16" => T::U16,
+            "uint32" => T::U32,
+            "uint64" => T::U64,
+            "float" => T::F32,
+            "double" => T::F64,
+            "long" => {
+                self.next();
+                if self.eat_keyword("long") {
+                    return Ok(T::I64);
+                }
+                if self.at_keyword("double") {
+                    return self.error("`long double` is not supported".to_string());
+                }
+                return Ok(T::I32);
+            }
+            "unsigned" => {
+                self.next();
+                if self.eat_keyword("short") {
+                    return Ok(T::U16);
+                }
+                if !self.eat_keyword("long") {
+                    return self.unexpected("`short` or `long`");
+                }
+                if self.eat_keyword("long") {
+                    return Ok(T::U64);
+                }
+                return Ok(T::U32);
+            }
+            "string" => {
+                self.next();
+                if self.eat(&Token::Punct('<')) {
+                    let bound = self.bound()?;
+                    self.expect('>')?;
+                    return Ok(T::BoundedString(bound));
+                }
+                return Ok(T::String);
+            }
+            "sequence" => {
+                self.next();
+                self.expect('<')?;
+                let element = Box::new(self.nested(Self::type_spec)?);
+                let ty = if self.eat(&Token::Punct(',')) {
+                    T::BoundedSequence(element, self.bound()?)
+                } else {
+                    T::Sequence(element)
+                };
+                self.expect('>')?;
+                return Ok(ty);
+            }
+            "wchar" | "wstring" | "any" | "fixed" | "map" | "Object" | "ValueBase" => {
+                return self.error(format!("`{}` is not supported", keyword));
+            }
+            _ => return self.scoped_type(),
+        };
+        self.next();
+        Ok(ty)
+    }
+
+    /// Parses a possibly scoped name, returning it and whether it is absolute.
+    fn scoped_name(&mut self) -> Result<(String, bool)> {
+        let absolute = self.eat(&Token::Scope);
+        let mut name = self.ident()?;
+        while self.eat(&Token::Scope) {
+            name.push_str("::");
+            name.push_str(&self.ident()?);
+        }
+        Ok((name, absolute))
+    }
+
+    /// Returns the candidates for a name referred to from the current scope,
+    /// innermost first.
+    fn candidates(&self, name: &str, absolute: bool) -> Vec<String> {
+        let depth = if absolute { 0 } else { self.scope.len() };
+        (0..=depth)
+            .rev()
+            .map(|i| {
+                let mut scoped = self.scope[..i].join("::");
+                if !scoped.is_empty() {
+                    scoped.push_str("::");
+                }
+                scoped.push_str(name);
+                scoped
+            })
+            .collect()
+    }
+
+    fn scoped_type(&mut self) -> Result<TypeDescriptor> {
+        let (name, absolute) = self.scoped_name()?;
+        let found = self
+            .candidates(&name, absolute)
+            .iter()
+            .find_map(|candidate| self.definitions.types.get(candidate))
+            .cloned();
+        match found {
+            Some(ty) => Ok(ty),
+            None => {
+                self.index -= 1;
+                self.error(format!("unknown type `{}`", name))
+            }
+        }
+    }
+
+    /// Parses the bound of a string or sequence or the length of an array.
+    fn bound(&mut self) -> Result<usize> {
+        let start = self.index;
+        let value = self.const_expr()?;
+        match usize::try_from(value) {
+            Ok(bound) => Ok(bound),
+            Err(_) => self.error_at(start, format!("invalid bound {}", value)),
+        }
+    }
+
+    fn const_expr(&mut self) -> Result<i64> {
+        let mut value = self.const_term()?;
+        loop {
+            if self.eat(&Token::Punct('+')) {
+                value = value.wrapping_add(self.const_term()?);
+            } else if self.eat(&Token::Punct('-')) {
+                value = value.wrapping_sub(self.const_term()?);
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn const_term(&mut self) -> Result<i64> {
+        let mut value = self.const_unary()?;
+        loop {
+            if self.eat(&Token::Punct('*')) {
+                value = value.wrapping_mul(self.const_unary()?);
+            } else if self.eat(&Token::Punct('/')) {
+                match self.const_unary()? {
+                    0 => return self.error("division by zero".to_string()),
+                    divisor => value = value.wrapping_div(divisor),
+                }
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn const_unary(&mut self) -> Result<i64> {
+        if self.eat(&Token::Punct('-')) {
+            Ok(self.nested(Self::const_unary)?.wrapping_neg())
+        } else if self.eat(&Token::Punct('+')) {
+            self.nested(Self::const_unary)
+        } else if self.eat(&Token::Punct('~')) {
+            Ok(!self.nested(Self::const_unary)?)
+        } else {
+            self.const_primary()
+        }
+    }
+
+    fn const_primary(&mut self) -> Result<i64> {
+        match *self.peek() {
+            // Literals above `i64::MAX` keep their bits
+            Token::Int(v) => {
+                self.next();
+                Ok(v as i64)
+            }
+            Token::Punct('(') => {
+                self.next();
+                let value = self.nested(Self::const_expr)?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            Token::Ident(ref name) if name == "TRUE" || name == "FALSE" => {
+                let value = i64::from(name == "TRUE");
+                self.next();
+                Ok(value)
+            }
+            Token::Ident(_) | Token::Scope => {
+                let (name, absolute) = self.scoped_name()?;
+                let found = self
+                    .candidates(&name, absolute)
+                    .iter()
+                    .find_map(|candidate| self.consts.get(candidate))
+                    .copied();
+                match found {
+                    Some(v) => Ok(v),
+                    None => {
+                        self.index -= 1;
+                        self.error(format!("unknown constant `{}`", name))
+                    }
+                }
+            }
+            _ => self.unexpected("an integer constant"),
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! Interoperable object references, which locate CORBA objects.
+//!
+//! An `Ior` names the type of an object and lists profiles, each of which
+//! tells how to reach the object with one protocol. These are the types of
+//! the `IOP` module of CORBA 3.3, Part 2, 7.6.2. An IIOP profile, which gives
+//! the host, port and object key to send GIOP requests to, is an
+//! `IiopProfile`.
+//!
+//! References are exchanged as text in their stringified form, `IOR:`
+//! followed by the hexadecimal octets of an encapsulation of the reference.
+//!
+//! ```rust
+//! use cdr::ior::{IiopProfile, Ior};
+//!
+//! let profile = IiopProfile::new("naming.example.com", 2809, b"NameService");
+//! let ior = Ior::new("IDL:omg.org/CosNaming/NamingContext:1.0", vec![profile.to_tagged().unwrap()]);
+//! let stringified = ior.to_string();
+//! assert!(stringified.starts_with("IOR:"));
+//!
+//! let ior: Ior = stringified.parse().unwrap();
+//! let profiles = ior.iiop_profiles().unwrap();
+//! assert_eq!(profiles[0].host, "naming.example.com");
+//! assert_eq!(profiles[0].object_key, b"NameService");
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use byteorder::BigEndian;
+use serde::de::{SeqAccess, Visitor};
+use serde::{de, ser, Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::giop::Version;
+
+/// A profile of an object reference.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TaggedProfile {
+    /// The protocol of the profile.
+    pub tag: u32,
+    /// The profile, usually an encapsulation.
+    pub profile_data: Vec<u8>,
+}
+
+impl TaggedProfile {
+    /// The tag of an IIOP profile.
+    pub const TAG_INTERNET_IOP: u32 = 0;
+    /// The tag of a profile made only of tagged components.
+    pub const TAG_MULTIPLE_COMPONENTS: u32 = 1;
+
+    /// Decodes the body of an IIOP profile.
+    pub fn iiop(&self) -> Result<IiopProfile> {
+        self.expect_tag(Self::TAG_INTERNET_IOP)?;
+        crate::de::deserialize_encapsulated(&self.profile_data)
+    }
+
+    /// Decodes the components of a multiple components profile.
+    pub fn components(&self) -> Result<Vec<TaggedComponent>> {
+        self.expect_tag(Self::TAG_MULTIPLE_COMPONENTS)?;
+        crate::de::deserialize_encapsulated(&self.profile_data)
+    }
+
+    fn expect_tag(&self, tag: u32) -> Result<()> {
+        if self.tag != tag {
+            return Err(Error::Custom(format!(
+                "expected a profile with tag {}, found {}",
+                tag, self.tag
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for TaggedProfile {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        (self.tag, &self.profile_data).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TaggedProfile {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let (tag, profile_data) = Deserialize::deserialize(deserializer)?;
+        Ok(TaggedProfile { tag, profile_data })
+    }
+}
+
+/// An interoperable object reference.
+///
+/// A reference with an empty type id and no profiles is a nil reference.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Ior {
+    /// The repository id of the most derived type of the object, e.g.
+    /// `IDL:omg.org/CosNaming/NamingContext:1.0`.
+    pub type_id: String,
+    pub profiles: Vec<TaggedProfile>,
+}
+
+impl Ior {
+    pub fn new<N>(type_id: N, profiles: Vec<TaggedProfile>) -> Self
+    where
+        N: Into<String>,
+    {
+        Ior {
+            type_id: type_id.into(),
+            profiles,
+        }
+    }
+
+    /// Returns whether this is a nil reference.
+    pub fn is_nil(&self) -> bool {
+        self.type_id.is_empty() && self.profiles.is_empty()
+    }
+
+    /// Decodes the IIOP profiles, in order.
+    pub fn iiop_profiles(&self) -> Result<Vec<IiopProfile>> {
+        self.profiles
+            .iter()
+            .filter(|p| p.tag == TaggedProfile::TAG_INTERNET_IOP)
+            .map(TaggedProfile::iiop)
+            .collect()
+    }
+}
+
+/// Writes the stringified reference.
+impl fmt::Display for Ior {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bytes =
+            crate::ser::serialize_encapsulated::<_, BigEndian>(self).map_err(|_| fmt::Error)?;
+        f.write_str("IOR:")?;
+        for b in bytes {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a stringified reference.
+impl FromStr for Ior {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let hex = match s.get(..4) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("IOR:") => &s[4..],
+            _ => return Err(Error::Custom("missing IOR: prefix".to_string())),
+        };
+        if hex.len() % 2 != 0 {
+            return Err(Error::Custom(
+                "odd number of hexadecimal digits in IOR".to_string(),
+            ));
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|b| u8::from_str_radix(b, 16).ok())
+                    .ok_or_else(|| Error::Custom("invalid hexadecimal digit in IOR".to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        crate::de::deserialize_encapsulated(&bytes)
+    }
+}
+
+impl Serialize for Ior {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        (&self.type_id, &self.profiles).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ior {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let (type_id, profiles) = Deserialize::deserialize(deserializer)?;
+        Ok(Ior { type_id, profiles })
+    }
+}
+
+/// A piece of information about how to reach an object, such as the code
+/// sets or the ORB type of the server.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TaggedComponent {
+    pub tag: u32,
+    /// The component, usually an encapsulation.
+    pub component_data: Vec<u8>,
+}
+
+impl TaggedComponent {
+    /// The tag of the vendor of the ORB.
+    pub const TAG_ORB_TYPE: u32 = 0;
+    /// The tag of the code sets supported by the server.
+    pub const TAG_CODE_SETS: u32 = 1;
+    /// The tag of the policies of the object.
+    pub const TAG_POLICIES: u32 = 2;
+    /// The tag of another host and port the object can be reached at.
+    pub const TAG_ALTERNATE_IIOP_ADDRESS: u32 = 3;
+}
+
+impl Serialize for TaggedComponent {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        (self.tag, &self.component_data).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TaggedComponent {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let (tag, component_data) = Deserialize::deserialize(deserializer)?;
+        Ok(TaggedComponent {
+            tag,
+            component_data,
+        })
+    }
+}
+
+/// The body of an IIOP profile, as specified in CORBA 3.3, Part 2, 9.7.2.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct IiopProfile {
+    pub version: Version,
+    pub host: String,
+    pub port: u16,
+    pub object_key: Vec<u8>,
+    /// The components of the profile, which IIOP 1.0 does not carry.
+    pub components: Vec<TaggedComponent>,
+}
+
+impl IiopProfile {
+    /// Creates an IIOP 1.2 profile without components.
+    pub fn new<H>(host: H, port: u16, object_key: &[u8]) -> Self
+    where
+        H: Into<String>,
+    {
+        IiopProfile {
+            version: Version::V1_2,
+            host: host.into(),
+            port,
+            object_key: object_key.to_vec(),
+            components: Vec::new(),
+        }
+    }
+
+    /// Encodes the profile into a tagged profile of an IOR.
+    pub fn to_tagged(&self) -> Result<TaggedProfile> {
+        Ok(TaggedProfile {
+            tag: TaggedProfile::TAG_INTERNET_IOP,
+            profile_data: crate::ser::serialize_encapsulated::<_, BigEndian>(self)?,
+        })
+    }
+}
+
+impl Serialize for IiopProfile {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let version = (self.version.major, self.version.minor);
+        if self.version.minor == 0 {
+            if !self.components.is_empty() {
+                return Err(ser::Error::custom(
+                    "IIOP 1.0 profiles cannot carry components",
+                ));
+            }
+            (version, &self.host, self.port, &self.object_key).serialize(serializer)
+        } else {
+            (
+                version,
+                &self.host,
+                self.port,
+                &self.object_key,
+                &self.components,
+            )
+                .serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for IiopProfile {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple(6, IiopProfileVisitor)
+    }
+}
+
+struct IiopProfileVisitor;
+
+impl<'de> Visitor<'de> for IiopProfileVisitor {
+    type Value = IiopProfile;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an IIOP profile")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<IiopProfile, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        fn next<'de, A, T>(seq: &mut A, index: usize) -> std::result::Result<T, A::Error>
+        where
+            A: SeqAccess<'de>,
+            T: Deserialize<'de>,
+        {
+            seq.next_element()?
+                .ok_or_else(|| de::Error::invalid_length(index, &IiopProfileVisitor))
+        }
+
+        let major = next(&mut seq, 0)?;
+        let minor = next(&mut seq, 1)?;
+        let host = next(&mut seq, 2)?;
+        let port = next(&mut seq, 3)?;
+        let object_key = next(&mut seq, 4)?;
+        let components = if minor == 0 {
+            Vec::new()
+        } else {
+            next(&mut seq, 5)?
+        };
+        Ok(IiopProfile {
+            version: Version { major, minor },
+            host,
+            port,
+            object_key,
+            components,
+        })
+    }
+}
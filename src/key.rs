@@ -0,0 +1,312 @@
+//! Serializing the key members of a value.
+//!
+//! DDS identifies the instances of a keyed type by their key members, which
+//! are marked with `@key` in IDL. With the `derive` feature, the same members
+//! are marked with `#[key]` in a struct that derives `Keyed`:
+//!
+//! ```rust
+//! # #[cfg(feature = "derive")]
+//! # {
+//! use cdr::{EncapsulationKind, Keyed};
+//! use serde_derive::Serialize;
+//!
+//! #[derive(Keyed, Serialize)]
+//! struct Reading {
+//!     #[key]
+//!     sensor: u16,
+//!     value: f64,
+//!     #[key]
+//!     channel: u8,
+//! }
+//!
+//! let reading = Reading { sensor: 1, value: 0.5, channel: 2 };
+//! let key = cdr::key::serialize_key(&reading, EncapsulationKind::CdrBe).unwrap();
+//! assert_eq!(key, [0, 0, 0, 0, 0, 1, 2]);
+//! # }
+//! ```
+//!
+//! The key members are serialized in declaration order as if they were the
+//! only members of the type, which is the key holder form of DDS-XTypes 1.3,
+//! 7.6.8. A key member whose type is keyed contributes only its own key
+//! members, and a struct without key members contributes all of its members.
+
+use byteorder::{BigEndian, LittleEndian};
+use serde::ser::{self, SerializeTuple};
+
+use crate::bounded::{BoundedSeq, BoundedString};
+use crate::encapsulation::EncapsulationKind;
+use crate::error::Result;
+use crate::ser::Serializer;
+use crate::size::Layout;
+
+// Used by `#[derive(Keyed)]`.
+#[doc(hidden)]
+pub mod __private {
+    pub use serde::ser::{Serialize, SerializeTuple, Serializer};
+}
+
+/// Types whose values have key members.
+///
+/// This trait can be derived for structs and enums without fields with the
+/// `derive` feature. It is implemented for primitives, strings, sequences,
+/// arrays and tuples, all of whose contents are their key.
+pub trait Keyed {
+    #[doc(hidden)]
+    const KEY_LAYOUT: Layout;
+
+    #[doc(hidden)]
+    const KEY_LAYOUT_XCDR2: Layout;
+
+    /// Serializes the key members of the value.
+    fn serialize_key<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer;
+}
+
+/// A value that serializes to its key members only.
+#[derive(Clone, Copy, Debug)]
+pub struct KeyHolder<'a, T: ?Sized>(pub &'a T);
+
+impl<T> ser::Serialize for KeyHolder<'_, T>
+where
+    T: ?Sized + Keyed,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        self.0.serialize_key(serializer)
+    }
+}
+
+/// Serializes the key members of a value with the encapsulation.
+pub fn serialize_key<T>(value: &T, kind: EncapsulationKind) -> Result<Vec<u8>>
+where
+    T: ?Sized + Keyed,
+{
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&kind.id());
+    buf.extend_from_slice(&[0; 2]);
+    serialize_key_data(&mut buf, value, kind)?;
+    Ok(buf)
+}
+
+/// Appends the serialized key members of a value to `buf` without an
+/// encapsulation header.
+pub(crate) fn serialize_key_data<T>(
+    buf: &mut Vec<u8>,
+    value: &T,
+    kind: EncapsulationKind,
+) -> Result<()>
+where
+    T: ?Sized + Keyed,
+{
+    let holder = KeyHolder(value);
+    if kind.is_little_endian() {
+        let mut serializer =
+            Serializer::<_, LittleEndian>::with_alignment(buf, 0, kind.max_alignment());
+        ser::Serialize::serialize(&holder, &mut serializer)
+    } else {
+        let mut serializer =
+            Serializer::<_, BigEndian>::with_alignment(buf, 0, kind.max_alignment());
+        ser::Serialize::serialize(&holder, &mut serializer)
+    }
+}
+
+/// Returns the largest size that the key members of a `T` would be if
+/// serialized with the encapsulation, or `None` if it has no upper bound.
+pub fn max_serialized_key_size<T>(kind: EncapsulationKind) -> Option<u64>
+where
+    T: ?Sized + Keyed,
+{
+    let layout = if kind.max_alignment() == 8 {
+        T::KEY_LAYOUT
+    } else {
+        T::KEY_LAYOUT_XCDR2
+    };
+    if layout.is_bounded() {
+        Some(layout.max_size() as u64)
+    } else {
+        None
+    }
+}
+
+macro_rules! impl_keyed_for_primitive {
+    ($size:expr => $($ty:ty),*) => {
+        $(
+            impl Keyed for $ty {
+                const KEY_LAYOUT: Layout = Layout::primitive($size);
+                const KEY_LAYOUT_XCDR2: Layout =
+                    Layout::aligned($size, if $size < 4 { $size } else { 4 });
+
+                fn serialize_key<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+                where
+                    S: ser::Serializer,
+                {
+                    ser::Serialize::serialize(self, serializer)
+                }
+            }
+        )*
+    };
+}
+
+impl_keyed_for_primitive!(1 => bool, char, u8, i8);
+impl_keyed_for_primitive!(2 => u16, i16);
+impl_keyed_for_primitive!(4 => u32, i32, f32);
+impl_keyed_for_primitive!(8 => u64, i64, f64);
+
+macro_rules! impl_keyed_for_string {
+    ($($ty:ty),*) => {
+        $(
+            impl Keyed for $ty {
+                const KEY_LAYOUT: Layout = Layout::UNBOUNDED;
+                const KEY_LAYOUT_XCDR2: Layout = Layout::UNBOUNDED;
+
+                fn serialize_key<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+                where
+                    S: ser::Serializer,
+                {
+                    ser::Serialize::serialize(self, serializer)
+                }
+            }
+        )*
+    };
+}
+
+impl_keyed_for_string!(str, String);
+
+impl<const N: usize> Keyed for BoundedString<N> {
+    const KEY_LAYOUT: Layout = Layout::primitive(4).then(Layout::bytes(1, N + 1));
+    const KEY_LAYOUT_XCDR2: Layout = Self::KEY_LAYOUT;
+
+    fn serialize_key<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        ser::Serialize::serialize(self, serializer)
+    }
+}
+
+impl<T> Keyed for [T]
+where
+    T: Keyed,
+{
+    const KEY_LAYOUT: Layout = Layout::UNBOUNDED;
+    const KEY_LAYOUT_XCDR2: Layout = Layout::UNBOUNDED;
+
+    fn serialize_key<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.collect_seq(self.iter().map(KeyHolder))
+    }
+}
+
+impl<T> Keyed for Vec<T>
+where
+    T: Keyed,
+{
+    const KEY_LAYOUT: Layout = Layout::UNBOUNDED;
+    const KEY_LAYOUT_XCDR2: Layout = Layout::UNBOUNDED;
+
+    fn serialize_key<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.collect_seq(self.iter().map(KeyHolder))
+    }
+}
+
+impl<T, const N: usize> Keyed for BoundedSeq<T, N>
+where
+    T: Keyed,
+{
+    const KEY_LAYOUT: Layout = Layout::primitive(4).then(T::KEY_LAYOUT.repeat_up_to(N));
+    const KEY_LAYOUT_XCDR2: Layout = Layout::primitive(4).then(T::KEY_LAYOUT_XCDR2.repeat_up_to(N));
+
+    fn serialize_key<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.collect_seq(self.iter().map(KeyHolder))
+    }
+}
+
+impl<T, const N: usize> Keyed for [T; N]
+where
+    T: Keyed,
+{
+    const KEY_LAYOUT: Layout = T::KEY_LAYOUT.repeat(N);
+    const KEY_LAYOUT_XCDR2: Layout = T::KEY_LAYOUT_XCDR2.repeat(N);
+
+    fn serialize_key<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for element in self {
+            tuple.serialize_element(&KeyHolder(element))?;
+        }
+        tuple.end()
+    }
+}
+
+impl<T> Keyed for &T
+where
+    T: ?Sized + Keyed,
+{
+    const KEY_LAYOUT: Layout = T::KEY_LAYOUT;
+    const KEY_LAYOUT_XCDR2: Layout = T::KEY_LAYOUT_XCDR2;
+
+    fn serialize_key<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        (**self).serialize_key(serializer)
+    }
+}
+
+impl<T> Keyed for Box<T>
+where
+    T: ?Sized + Keyed,
+{
+    const KEY_LAYOUT: Layout = T::KEY_LAYOUT;
+    const KEY_LAYOUT_XCDR2: Layout = T::KEY_LAYOUT_XCDR2;
+
+    fn serialize_key<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        (**self).serialize_key(serializer)
+    }
+}
+
+macro_rules! impl_keyed_for_tuple {
+    ($len:expr => $($name:ident $index:tt)+) => {
+        impl<$($name),+> Keyed for ($($name,)+)
+        where
+            $($name: Keyed,)+
+        {
+            const KEY_LAYOUT: Layout = Layout::EMPTY$(.then($name::KEY_LAYOUT))+;
+            const KEY_LAYOUT_XCDR2: Layout = Layout::EMPTY$(.then($name::KEY_LAYOUT_XCDR2))+;
+
+            fn serialize_key<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: ser::Serializer,
+            {
+                let mut tuple = serializer.serialize_tuple($len)?;
+                $(tuple.serialize_element(&KeyHolder(&self.$index))?;)+
+                tuple.end()
+            }
+        }
+    };
+}
+
+impl_keyed_for_tuple!(1 => T0 0);
+impl_keyed_for_tuple!(2 => T0 0 T1 1);
+impl_keyed_for_tuple!(3 => T0 0 T1 1 T2 2);
+impl_keyed_for_tuple!(4 => T0 0 T1 1 T2 2 T3 3);
+impl_keyed_for_tuple!(5 => T0 0 T1 1 T2 2 T3 3 T4 4);
+impl_keyed_for_tuple!(6 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5);
+impl_keyed_for_tuple!(7 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6);
+impl_keyed_for_tuple!(8 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7);
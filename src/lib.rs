@@ -29,15 +29,47 @@
 
 pub use byteorder::{BigEndian, LittleEndian};
 
+pub mod bounded;
+pub use crate::bounded::{BoundedSeq, BoundedString};
+
+pub mod bulk;
+
 pub mod de;
 #[doc(inline)]
 pub use crate::de::Deserializer;
 
 mod encapsulation;
-pub use crate::encapsulation::{CdrBe, CdrLe, Encapsulation, PlCdrBe, PlCdrLe};
+pub use crate::encapsulation::{CdrBe, CdrLe, Encapsulation, EncapsulationKind, PlCdrBe, PlCdrLe};
 
 mod error;
-pub use crate::error::{Error, Result};
+pub use crate::error::{Category, Error, Result};
+
+pub mod explain;
+pub use crate::explain::{explain, Explanation};
+
+pub mod giop;
+
+pub mod idl;
+
+pub mod ior;
+
+pub mod key;
+#[doc(inline)]
+pub use crate::key::Keyed;
+
+#[cfg(feature = "mcap")]
+pub mod mcap;
+
+pub mod parameter_list;
+
+mod reencode;
+pub use crate::reencode::{reencode, reencode_dynamic};
+
+#[cfg(feature = "ros2")]
+pub mod ros2;
+
+#[cfg(feature = "rtps")]
+pub mod rtps;
 
 pub mod ser;
 #[doc(inline)]
@@ -45,23 +77,53 @@ pub use crate::ser::Serializer;
 
 pub mod size;
 #[doc(inline)]
-pub use crate::size::{Bounded, Infinite, SizeLimit};
+pub use crate::size::{Bounded, CdrFixedSize, Infinite, SizeLimit};
+
+#[cfg(feature = "derive")]
+pub use cdr_derive::{CdrFixedSize, Described, Keyed};
+
+#[cfg(feature = "json")]
+pub mod transcode;
+
+pub mod typecode;
+
+pub mod value;
+#[doc(inline)]
+pub use crate::value::{Described, TypeDescriptor, Value};
 
-use std::io::{Read, Write};
+pub mod view;
+
+#[cfg(feature = "xtypes")]
+pub mod xtypes;
+
+use std::{
+    io::{Read, Write},
+    marker::PhantomData,
+};
 
 /// Returns the size that an object would be if serialized with a encapsulation.
-pub fn calc_serialized_size<T: ?Sized>(value: &T) -> u64
+pub fn calc_serialized_size<T>(value: &T) -> u64
 where
-    T: serde::Serialize,
+    T: ?Sized + serde::Serialize,
 {
     size::calc_serialized_data_size(value) + encapsulation::ENCAPSULATION_HEADER_SIZE
 }
 
+/// Returns the size that every value of type `T` would be if serialized with
+/// a encapsulation, or `None` if the size depends on the value.
+pub fn calc_serialized_size_fixed<T>() -> Option<u64>
+where
+    T: ?Sized + CdrFixedSize,
+{
+    size::calc_serialized_data_size_fixed::<T>()
+        .map(|size| size + encapsulation::ENCAPSULATION_HEADER_SIZE)
+}
+
 /// Given a maximum size limit, check how large an object would be if it were
 /// to be serialized with a encapsulation.
-pub fn calc_serialized_size_bounded<T: ?Sized>(value: &T, max: u64) -> Result<u64>
+pub fn calc_serialized_size_bounded<T>(value: &T, max: u64) -> Result<u64>
 where
-    T: serde::Serialize,
+    T: ?Sized + serde::Serialize,
 {
     use crate::encapsulation::ENCAPSULATION_HEADER_SIZE;
 
@@ -73,33 +135,36 @@ where
     }
 }
 
+/// Returns the largest size that a value of type `T` would be if serialized
+/// with a encapsulation, or `None` if it has no upper bound.
+pub fn max_serialized_size<T>() -> Option<u64>
+where
+    T: ?Sized + CdrFixedSize,
+{
+    size::max_serialized_data_size::<T>()
+        .map(|size| size + encapsulation::ENCAPSULATION_HEADER_SIZE)
+}
+
 /// Serializes a serializable object into a `Vec` of bytes with the encapsulation.
-pub fn serialize<T: ?Sized, S, C>(value: &T, size_limit: S) -> Result<Vec<u8>>
+///
+/// The object is serialized in a single pass, without computing its size
+/// beforehand.
+pub fn serialize<T, S, C>(value: &T, size_limit: S) -> Result<Vec<u8>>
 where
-    T: serde::Serialize,
+    T: ?Sized + serde::Serialize,
     S: SizeLimit,
     C: Encapsulation,
 {
-    let mut writer = match size_limit.limit() {
-        Some(limit) => {
-            let actual_size = calc_serialized_size_bounded(value, limit)?;
-            Vec::with_capacity(actual_size as usize)
-        }
-        None => {
-            let size = calc_serialized_size(value) as usize;
-            Vec::with_capacity(size)
-        }
-    };
-
-    serialize_into::<_, _, _, C>(&mut writer, value, Infinite)?;
+    let mut writer = Vec::new();
+    serialize_into_vec::<_, _, C>(&mut writer, value, size_limit)?;
     Ok(writer)
 }
 
 /// Serializes an object directly into a `Write` with the encapsulation.
-pub fn serialize_into<W, T: ?Sized, S, C>(writer: W, value: &T, size_limit: S) -> Result<()>
+pub fn serialize_into<W, T, S, C>(writer: W, value: &T, size_limit: S) -> Result<()>
 where
     W: Write,
-    T: serde::ser::Serialize,
+    T: ?Sized + serde::ser::Serialize,
     S: SizeLimit,
     C: Encapsulation,
 {
@@ -115,6 +180,28 @@ where
     serde::Serialize::serialize(value, &mut serializer)
 }
 
+/// Serializes an object with the encapsulation into a `Vec`, appending to its
+/// contents, in a single pass.
+///
+/// The size of the object is not computed beforehand; `buf` grows as needed,
+/// and reusing it across calls avoids reallocations.
+/// If an error occurs, `buf` is truncated to its original length.
+pub fn serialize_into_vec<T, S, C>(buf: &mut Vec<u8>, value: &T, size_limit: S) -> Result<()>
+where
+    T: ?Sized + serde::Serialize,
+    S: SizeLimit,
+    C: Encapsulation,
+{
+    ser::write_limited(buf, size_limit, |writer| {
+        let mut serializer = Serializer::<_, C::E>::new(writer);
+
+        serde::Serialize::serialize(&C::id(), &mut serializer)?;
+        serde::Serialize::serialize(&C::option(), &mut serializer)?;
+        serializer.reset_pos();
+        serde::Serialize::serialize(value, &mut serializer)
+    })
+}
+
 /// Deserializes a slice of bytes into an object.
 pub fn deserialize<'de, T>(bytes: &[u8]) -> Result<T>
 where
@@ -129,19 +216,51 @@ where
     R: Read,
     T: serde::Deserialize<'de>,
     S: SizeLimit,
+{
+    deserialize_seed_from(reader, PhantomData::<T>, size_limit)
+}
+
+/// Deserializes a slice of bytes using a stateful `DeserializeSeed`, such as
+/// a `TypeDescriptor`.
+pub fn deserialize_seed<'de, T>(bytes: &[u8], seed: T) -> Result<T::Value>
+where
+    T: serde::de::DeserializeSeed<'de>,
+{
+    deserialize_seed_from(bytes, seed, Infinite)
+}
+
+/// Deserializes an object directly from a `Read` using a stateful
+/// `DeserializeSeed`.
+pub fn deserialize_seed_from<'de, R, T, S>(
+    mut reader: R,
+    seed: T,
+    mut size_limit: S,
+) -> Result<T::Value>
+where
+    R: Read,
+    T: serde::de::DeserializeSeed<'de>,
+    S: SizeLimit,
 {
     use crate::encapsulation::ENCAPSULATION_HEADER_SIZE;
 
-    let mut deserializer = Deserializer::<_, S, BigEndian>::new(reader, size_limit);
-
-    let v: [u8; ENCAPSULATION_HEADER_SIZE as usize] =
-        serde::Deserialize::deserialize(&mut deserializer)?;
-    deserializer.reset_pos();
-    match v[1] {
-        0 | 2 => serde::Deserialize::deserialize(&mut deserializer),
-        1 | 3 => serde::Deserialize::deserialize(
-            &mut Into::<Deserializer<_, _, LittleEndian>>::into(deserializer),
-        ),
-        _ => Err(Error::InvalidEncapsulation),
+    size_limit.add(ENCAPSULATION_HEADER_SIZE)?;
+    let mut header = [0; ENCAPSULATION_HEADER_SIZE as usize];
+    reader.read_exact(&mut header)?;
+    let kind = EncapsulationKind::from_header(&header)?;
+    let max_alignment = kind.max_alignment();
+    if kind.is_little_endian() {
+        seed.deserialize(&mut Deserializer::<_, _, LittleEndian>::with_alignment(
+            reader,
+            size_limit,
+            0,
+            max_alignment,
+        ))
+    } else {
+        seed.deserialize(&mut Deserializer::<_, _, BigEndian>::with_alignment(
+            reader,
+            size_limit,
+            0,
+            max_alignment,
+        ))
     }
 }
@@ -0,0 +1,835 @@
+//! Reading and writing MCAP files of CDR messages, as recorded by rosbag2.
+//!
+//! The reader yields the messages of a file in order, whether or not they are
+//! in chunks, and decodes them statically with `Message::deserialize` or
+//! dynamically from the schema of their channel with `Reader::decode`. The
+//! writer puts messages in chunks followed by their message indexes, and ends
+//! the file with a summary of its schemas, channels, statistics and chunks.
+//!
+//! The writer does not compress chunks. The reader decompresses chunks
+//! compressed with zstd or lz4, and rejects other compression schemes.
+//!
+//! ```rust
+//! use cdr::mcap::{Reader, Writer};
+//! use cdr::Value;
+//! use std::collections::BTreeMap;
+//!
+//! let mut writer = Writer::new(Vec::new(), "ros2").unwrap();
+//! let schema = writer
+//!     .add_schema("std_msgs/msg/String", "ros2msg", b"string data\n")
+//!     .unwrap();
+//! let channel = writer
+//!     .add_channel(schema, "/chatter", "cdr", BTreeMap::new())
+//!     .unwrap();
+//! writer.write_sample(channel, 1, 100, 100, &"hello").unwrap();
+//! let file = writer.finish().unwrap();
+//!
+//! let mut reader = Reader::new(&file[..]).unwrap();
+//! let message = reader.next().unwrap().unwrap();
+//! assert_eq!(message.deserialize::<String>().unwrap(), "hello");
+//! assert_eq!(
+//!     reader.decode(&message).unwrap(),
+//!     Value::Struct(vec![Value::String("hello".to_string())])
+//! );
+//! assert!(reader.next().is_none());
+//! ```
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::ros2::Interfaces;
+use crate::size::Infinite;
+use crate::value::{self, TypeDescriptor, Value};
+use crate::{idl, CdrLe};
+
+/// The bytes at the start and the end of an MCAP file.
+pub const MAGIC: [u8; 8] = [0x89, b'M', b'C', b'A', b'P', b'0', b'\r', b'\n'];
+
+const OP_HEADER: u8 = 0x01;
+const OP_FOOTER: u8 = 0x02;
+const OP_SCHEMA: u8 = 0x03;
+const OP_CHANNEL: u8 = 0x04;
+const OP_MESSAGE: u8 = 0x05;
+const OP_CHUNK: u8 = 0x06;
+const OP_MESSAGE_INDEX: u8 = 0x07;
+const OP_CHUNK_INDEX: u8 = 0x08;
+const OP_STATISTICS: u8 = 0x0b;
+const OP_DATA_END: u8 = 0x0f;
+
+/// The number of bytes of records after which the writer closes a chunk.
+const DEFAULT_CHUNK_SIZE: usize = 768 * 1024;
+
+/// The definition of the messages of channels.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Schema {
+    /// The ID of the schema, which is never 0.
+    pub id: u16,
+    /// The name of the type, such as `std_msgs/msg/String`.
+    pub name: String,
+    /// The format of `data`, such as `ros2msg` or `ros2idl`.
+    pub encoding: String,
+    pub data: Vec<u8>,
+}
+
+impl Schema {
+    /// Returns the descriptor of the type defined by a `ros2msg`, `ros2idl`
+    /// or `omgidl` schema.
+    ///
+    /// The definitions of the dependencies of a ROS 2 type follow it, each
+    /// after a line of `=` and a line naming it.
+    pub fn descriptor(&self) -> Result<TypeDescriptor> {
+        let text = std::str::from_utf8(&self.data).map_err(Error::InvalidUtf8Encoding)?;
+        let sections = sections(&self.name, text);
+        match self.encoding.as_str() {
+            "ros2msg" => {
+                let mut interfaces = Interfaces::new();
+                let mut pending = Vec::new();
+                for (name, text) in sections {
+                    let mut parts = name.split('/');
+                    match (parts.next(), parts.next_back()) {
+                        (Some(package), Some(name)) => pending.push((package, name, text)),
+                        _ => return Err(Error::Custom(format!("invalid type name `{}`", name))),
+                    }
+                }
+                // Add the definitions once the types they use are defined.
+                while !pending.is_empty() {
+                    let count = pending.len();
+                    let mut error = None;
+                    pending.retain(|&(package, name, text)| {
+                        match interfaces.add_msg(package, name, text) {
+                            Ok(_) => false,
+                            Err(e) => {
+                                error = Some(e);
+                                true
+                            }
+                        }
+                    });
+                    if let (true, Some(error)) = (pending.len() == count, error) {
+                        return Err(Error::Custom(error.to_string()));
+                    }
+                }
+                interfaces.get(&self.name).cloned()
+            }
+            "ros2idl" | "omgidl" => {
+                let mut source = String::new();
+                let mut definitions = None;
+                let mut pending: Vec<&str> = sections.iter().map(|&(_, text)| text).collect();
+                while !pending.is_empty() {
+                    let count = pending.len();
+                    let mut error = None;
+                    pending.retain(|text| {
+                        let extended = format!("{}\n{}", source, text);
+                        match idl::parse(&extended) {
+                            Ok(parsed) => {
+                                source = extended;
+                                definitions = Some(parsed);
+                                false
+                            }
+                            Err(e) => {
+                                error = Some(e);
+                                true
+                            }
+                        }
+                    });
+                    if let (true, Some(error)) = (pending.len() == count, error) {
+                        return Err(Error::Custom(error.to_string()));
+                    }
+                }
+                definitions
+                    .and_then(|definitions| definitions.get(&self.name.replace('/', "::")).cloned())
+            }
+            encoding => {
+                return Err(Error::Custom(format!(
+                    "unsupported schema encoding `{}`",
+                    encoding
+                )))
+            }
+        }
+        .ok_or_else(|| Error::Custom(format!("schema does not define `{}`", self.name)))
+    }
+}
+
+/// Splits a ROS 2 schema into the names and definitions of its types.
+fn sections<'a>(name: &'a str, text: &'a str) -> Vec<(&'a str, &'a str)> {
+    let mut sections = Vec::new();
+    let (mut name, mut start) = (name, 0);
+    let mut lines = text.split_inclusive('\n');
+    let mut offset = 0;
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && trimmed.chars().all(|c| c == '=') {
+            sections.push((name, &text[start..offset]));
+            offset += line.len();
+            if let Some(line) = lines.next() {
+                let header = line.trim();
+                name = header
+                    .split_once(':')
+                    .map_or(header, |(_, name)| name)
+                    .trim();
+                offset += line.len();
+            }
+            start = offset;
+        } else {
+            offset += line.len();
+        }
+    }
+    sections.push((name, &text[start..]));
+    sections
+}
+
+/// A stream of messages, such as a ROS 2 topic.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Channel {
+    pub id: u16,
+    /// The ID of the schema of the messages, or 0 if they have none.
+    pub schema_id: u16,
+    pub topic: String,
+    /// The encoding of the messages, `cdr` for ROS 2.
+    pub message_encoding: String,
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// A message of a channel.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Message {
+    pub channel_id: u16,
+    pub sequence: u32,
+    /// The time, in nanoseconds, at which the message was recorded.
+    pub log_time: u64,
+    /// The time, in nanoseconds, at which the message was published.
+    pub publish_time: u64,
+    /// The encoded message, a CDR encapsulation for ROS 2.
+    pub data: Vec<u8>,
+}
+
+impl Message {
+    /// Deserializes the CDR encapsulation of the message.
+    pub fn deserialize<'de, T>(&self) -> Result<T>
+    where
+        T: Deserialize<'de>,
+    {
+        crate::deserialize(&self.data)
+    }
+}
+
+/// Reads the fields of a record.
+struct Fields<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    fn take(&mut self, len: u64) -> Result<&'a [u8]> {
+        let len = usize::try_from(len).map_err(|_| Error::NumberOutOfRange)?;
+        if len > self.bytes.len() {
+            return Err(Error::Eof);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let mut bytes = [0; 2];
+        bytes.copy_from_slice(self.take(2)?);
+        Ok(u16::from_le_bytes(bytes))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.u32()?;
+        self.take(u64::from(len))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let bytes = self.bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|e| Error::InvalidUtf8Encoding(e.utf8_error()))
+    }
+
+    fn string_map(&mut self) -> Result<BTreeMap<String, String>> {
+        let mut entries = Fields {
+            bytes: self.bytes()?,
+        };
+        let mut map = BTreeMap::new();
+        while !entries.bytes.is_empty() {
+            let key = entries.string()?;
+            map.insert(key, entries.string()?);
+        }
+        Ok(map)
+    }
+
+    fn schema(&mut self) -> Result<Schema> {
+        Ok(Schema {
+            id: self.u16()?,
+            name: self.string()?,
+            encoding: self.string()?,
+            data: self.bytes()?.to_vec(),
+        })
+    }
+
+    fn channel(&mut self) -> Result<Channel> {
+        Ok(Channel {
+            id: self.u16()?,
+            schema_id: self.u16()?,
+            topic: self.string()?,
+            message_encoding: self.string()?,
+            metadata: self.string_map()?,
+        })
+    }
+
+    fn message(&mut self) -> Result<Message> {
+        Ok(Message {
+            channel_id: self.u16()?,
+            sequence: self.u32()?,
+            log_time: self.u64()?,
+            publish_time: self.u64()?,
+            data: std::mem::take(&mut self.bytes).to_vec(),
+        })
+    }
+
+    /// Splits records into their opcodes and contents.
+    fn record(&mut self) -> Result<(u8, &'a [u8])> {
+        let opcode = self.u8()?;
+        let len = self.u64()?;
+        Ok((opcode, self.take(len)?))
+    }
+}
+
+/// Reads the messages of an MCAP file in order.
+pub struct Reader<R> {
+    reader: R,
+    profile: String,
+    schemas: BTreeMap<u16, Schema>,
+    channels: BTreeMap<u16, Channel>,
+    descriptors: BTreeMap<u16, TypeDescriptor>,
+    /// The messages of the chunk being read.
+    pending: VecDeque<Message>,
+    done: bool,
+}
+
+impl<R> Reader<R>
+where
+    R: Read,
+{
+    /// Reads the magic and the header of a file.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(Error::Custom("not an MCAP file".to_string()));
+        }
+        let mut reader = Reader {
+            reader,
+            profile: String::new(),
+            schemas: BTreeMap::new(),
+            channels: BTreeMap::new(),
+            descriptors: BTreeMap::new(),
+            pending: VecDeque::new(),
+            done: false,
+        };
+        match reader.read_record()? {
+            (OP_HEADER, content) => {
+                reader.profile = Fields { bytes: &content }.string()?;
+                Ok(reader)
+            }
+            _ => Err(Error::Custom("missing MCAP header".to_string())),
+        }
+    }
+
+    /// Returns the profile of the file, `ros2` for rosbag2 recordings.
+    pub fn profile(&self) -> &str {
+        &self.profile
+    }
+
+    /// Returns the schema with the given ID, once it has been read.
+    pub fn schema(&self, id: u16) -> Option<&Schema> {
+        self.schemas.get(&id)
+    }
+
+    /// Returns the channel with the given ID, once it has been read.
+    pub fn channel(&self, id: u16) -> Option<&Channel> {
+        self.channels.get(&id)
+    }
+
+    /// Decodes a CDR message from the schema of its channel.
+    pub fn decode(&mut self, message: &Message) -> Result<Value> {
+        if !self.descriptors.contains_key(&message.channel_id) {
+            let channel = self
+                .channel(message.channel_id)
+                .ok_or_else(|| Error::Custom(format!("unknown channel {}", message.channel_id)))?;
+            if channel.message_encoding != "cdr" {
+                return Err(Error::Custom(format!(
+                    "unsupported message encoding `{}`",
+                    channel.message_encoding
+                )));
+            }
+            let schema = self
+                .schema(channel.schema_id)
+                .ok_or_else(|| Error::Custom(format!("unknown schema {}", channel.schema_id)))?;
+            let descriptor = schema.descriptor()?;
+            self.descriptors.insert(message.channel_id, descriptor);
+        }
+        value::deserialize(&message.data, &self.descriptors[&message.channel_id])
+    }
+
+    fn read_record(&mut self) -> Result<(u8, Vec<u8>)> {
+        let mut header = [0; 9];
+        self.reader.read_exact(&mut header)?;
+        let mut len = [0; 8];
+        len.copy_from_slice(&header[1..]);
+        let len = u64::from_le_bytes(len);
+        let mut content = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut content)?;
+        if (content.len() as u64) < len {
+            return Err(Error::Eof);
+        }
+        Ok((header[0], content))
+    }
+
+    /// Handles a record, returning it if it is a message.
+    fn handle(&mut self, opcode: u8, content: &[u8]) -> Result<Option<Message>> {
+        let mut fields = Fields { bytes: content };
+        match opcode {
+            OP_SCHEMA => {
+                let schema = fields.schema()?;
+                self.schemas.insert(schema.id, schema);
+            }
+            OP_CHANNEL => {
+                let channel = fields.channel()?;
+                self.descriptors.remove(&channel.id);
+                self.channels.insert(channel.id, channel);
+            }
+            OP_MESSAGE => return fields.message().map(Some),
+            OP_CHUNK => {
+                fields.take(16)?; // message start and end times
+                let uncompressed_size = fields.u64()?;
+                let crc = fields.u32()?;
+                let compression = fields.string()?;
+                let len = fields.u64()?;
+                let records = decompress(&compression, fields.take(len)?, uncompressed_size)?;
+                if crc != 0 && crc32fast::hash(&records) != crc {
+                    return Err(Error::Custom("chunk CRC mismatch".to_string()));
+                }
+                let mut records = Fields { bytes: &records };
+                while !records.bytes.is_empty() {
+                    let (opcode, content) = records.record()?;
+                    if let Some(message) = self.handle(opcode, content)? {
+                        self.pending.push_back(message);
+                    }
+                }
+            }
+            OP_FOOTER => self.done = true,
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn read_message(&mut self) -> Result<Option<Message>> {
+        loop {
+            if let Some(message) = self.pending.pop_front() {
+                return Ok(Some(message));
+            }
+            if self.done {
+                return Ok(None);
+            }
+            let (opcode, content) = self.read_record()?;
+            if let Some(message) = self.handle(opcode, &content)? {
+                return Ok(Some(message));
+            }
+        }
+    }
+}
+
+/// Returns the records of a chunk, decompressing them if needed.
+fn decompress<'a>(compression: &str, records: &'a [u8], size: u64) -> Result<Cow<'a, [u8]>> {
+    let mut decompressed = Vec::new();
+    // One byte more than announced is read to detect a size mismatch without
+    // decompressing an arbitrary amount.
+    let limit = size.saturating_add(1);
+    match compression {
+        "" => return Ok(Cow::Borrowed(records)),
+        "zstd" => ruzstd::decoding::StreamingDecoder::new(records)
+            .map_err(|e| Error::Custom(format!("invalid zstd chunk: {}", e)))?
+            .take(limit)
+            .read_to_end(&mut decompressed)?,
+        "lz4" => lz4_flex::frame::FrameDecoder::new(records)
+            .take(limit)
+            .read_to_end(&mut decompressed)?,
+        _ => {
+            return Err(Error::Custom(format!(
+                "unsupported chunk compression `{}`",
+                compression
+            )))
+        }
+    };
+    if decompressed.len() as u64 != size {
+        return Err(Error::Custom(format!(
+            "chunk does not decompress to its size of {} bytes",
+            size
+        )));
+    }
+    Ok(Cow::Owned(decompressed))
+}
+
+impl<R> Iterator for Reader<R>
+where
+    R: Read,
+{
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Result<Message>> {
+        let message = self.read_message();
+        if message.is_err() {
+            self.done = true;
+            self.pending.clear();
+        }
+        message.transpose()
+    }
+}
+
+/// Writes the fields of a record.
+#[derive(Default)]
+struct Record {
+    bytes: Vec<u8>,
+}
+
+impl Record {
+    fn u16(&mut self, value: u16) -> &mut Self {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn u32(&mut self, value: u32) -> &mut Self {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn u64(&mut self, value: u64) -> &mut Self {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn bytes(&mut self, value: &[u8]) -> Result<&mut Self> {
+        let len = u32::try_from(value.len()).map_err(|_| Error::NumberOutOfRange)?;
+        self.u32(len);
+        self.bytes.extend_from_slice(value);
+        Ok(self)
+    }
+
+    fn string(&mut self, value: &str) -> Result<&mut Self> {
+        self.bytes(value.as_bytes())
+    }
+
+    fn schema(&mut self, schema: &Schema) -> Result<&mut Self> {
+        self.u16(schema.id)
+            .string(&schema.name)?
+            .string(&schema.encoding)?
+            .bytes(&schema.data)
+    }
+
+    fn channel(&mut self, channel: &Channel) -> Result<&mut Self> {
+        let mut metadata = Record::default();
+        for (key, value) in &channel.metadata {
+            metadata.string(key)?.string(value)?;
+        }
+        self.u16(channel.id)
+            .u16(channel.schema_id)
+            .string(&channel.topic)?
+            .string(&channel.message_encoding)?
+            .bytes(&metadata.bytes)
+    }
+
+    /// Appends the content of another record with its opcode and length.
+    fn record(&mut self, opcode: u8, content: &Record) -> &mut Self {
+        self.bytes.push(opcode);
+        self.u64(content.bytes.len() as u64);
+        self.bytes.extend_from_slice(&content.bytes);
+        self
+    }
+}
+
+/// Writes messages into an MCAP file.
+pub struct Writer<W> {
+    writer: W,
+    /// The number of bytes written.
+    pos: u64,
+    chunk_size: usize,
+    /// The records of the open chunk.
+    chunk: Record,
+    chunk_times: Option<(u64, u64)>,
+    /// The log times and offsets in the open chunk of its messages, by
+    /// channel.
+    message_indexes: BTreeMap<u16, Vec<(u64, u64)>>,
+    chunk_indexes: Record,
+    schemas: Vec<Schema>,
+    channels: Vec<Channel>,
+    message_count: u64,
+    chunk_count: u32,
+    times: Option<(u64, u64)>,
+    channel_message_counts: BTreeMap<u16, u64>,
+}
+
+impl<W> Writer<W>
+where
+    W: Write,
+{
+    /// Writes the magic and the header of a file with the given profile,
+    /// `ros2` for rosbag2 recordings.
+    pub fn new(writer: W, profile: &str) -> Result<Self> {
+        let mut writer = Writer {
+            writer,
+            pos: 0,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            chunk: Record::default(),
+            chunk_times: None,
+            message_indexes: BTreeMap::new(),
+            chunk_indexes: Record::default(),
+            schemas: Vec::new(),
+            channels: Vec::new(),
+            message_count: 0,
+            chunk_count: 0,
+            times: None,
+            channel_message_counts: BTreeMap::new(),
+        };
+        writer.write(&MAGIC)?;
+        let mut header = Record::default();
+        header.string(profile)?.string("cdr-rs")?;
+        writer.write_record(OP_HEADER, &header)?;
+        Ok(writer)
+    }
+
+    /// Sets the number of bytes of records after which a chunk is closed.
+    pub fn set_chunk_size(&mut self, chunk_size: usize) {
+        self.chunk_size = chunk_size;
+    }
+
+    /// Adds a schema, returning its ID.
+    pub fn add_schema(&mut self, name: &str, encoding: &str, data: &[u8]) -> Result<u16> {
+        let id = u16::try_from(self.schemas.len() + 1).map_err(|_| Error::NumberOutOfRange)?;
+        let schema = Schema {
+            id,
+            name: name.to_string(),
+            encoding: encoding.to_string(),
+            data: data.to_vec(),
+        };
+        let mut record = Record::default();
+        record.schema(&schema)?;
+        self.chunk.record(OP_SCHEMA, &record);
+        self.schemas.push(schema);
+        Ok(id)
+    }
+
+    /// Adds a channel of messages with the given schema, or 0 for none,
+    /// returning its ID.
+    pub fn add_channel(
+        &mut self,
+        schema_id: u16,
+        topic: &str,
+        message_encoding: &str,
+        metadata: BTreeMap<String, String>,
+    ) -> Result<u16> {
+        if schema_id != 0 && usize::from(schema_id) > self.schemas.len() {
+            return Err(Error::Custom(format!("unknown schema {}", schema_id)));
+        }
+        let id = u16::try_from(self.channels.len()).map_err(|_| Error::NumberOutOfRange)?;
+        let channel = Channel {
+            id,
+            schema_id,
+            topic: topic.to_string(),
+            message_encoding: message_encoding.to_string(),
+            metadata,
+        };
+        let mut record = Record::default();
+        record.channel(&channel)?;
+        self.chunk.record(OP_CHANNEL, &record);
+        self.channels.push(channel);
+        Ok(id)
+    }
+
+    /// Writes a message.
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        if usize::from(message.channel_id) >= self.channels.len() {
+            return Err(Error::Custom(format!(
+                "unknown channel {}",
+                message.channel_id
+            )));
+        }
+        let mut record = Record::default();
+        record
+            .u16(message.channel_id)
+            .u32(message.sequence)
+            .u64(message.log_time)
+            .u64(message.publish_time);
+        record.bytes.extend_from_slice(&message.data);
+
+        self.message_indexes
+            .entry(message.channel_id)
+            .or_default()
+            .push((message.log_time, self.chunk.bytes.len() as u64));
+        self.chunk.record(OP_MESSAGE, &record);
+        for times in [&mut self.chunk_times, &mut self.times] {
+            let (start, end) = times.get_or_insert((message.log_time, message.log_time));
+            *start = (*start).min(message.log_time);
+            *end = (*end).max(message.log_time);
+        }
+        self.message_count += 1;
+        *self
+            .channel_message_counts
+            .entry(message.channel_id)
+            .or_default() += 1;
+
+        if self.chunk.bytes.len() >= self.chunk_size {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Serializes a value as a little-endian CDR encapsulation, as ROS 2
+    /// does, and writes it as a message.
+    pub fn write_sample<T>(
+        &mut self,
+        channel_id: u16,
+        sequence: u32,
+        log_time: u64,
+        publish_time: u64,
+        value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.write_message(&Message {
+            channel_id,
+            sequence,
+            log_time,
+            publish_time,
+            data: crate::serialize::<_, _, CdrLe>(value, Infinite)?,
+        })
+    }
+
+    /// Closes the open chunk, writes the summary and the footer, and returns
+    /// the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.flush_chunk()?;
+        let mut data_end = Record::default();
+        data_end.u32(0);
+        self.write_record(OP_DATA_END, &data_end)?;
+
+        let summary_start = self.pos;
+        let mut summary = Record::default();
+        for schema in &self.schemas {
+            let mut record = Record::default();
+            record.schema(schema)?;
+            summary.record(OP_SCHEMA, &record);
+        }
+        for channel in &self.channels {
+            let mut record = Record::default();
+            record.channel(channel)?;
+            summary.record(OP_CHANNEL, &record);
+        }
+        let (start, end) = self.times.unwrap_or_default();
+        let mut counts = Record::default();
+        for (&channel_id, &count) in &self.channel_message_counts {
+            counts.u16(channel_id).u64(count);
+        }
+        let mut statistics = Record::default();
+        statistics
+            .u64(self.message_count)
+            .u16(self.schemas.len() as u16)
+            .u32(self.channels.len() as u32)
+            .u32(0) // attachments
+            .u32(0) // metadata
+            .u32(self.chunk_count)
+            .u64(start)
+            .u64(end)
+            .bytes(&counts.bytes)?;
+        summary.record(OP_STATISTICS, &statistics);
+        summary.bytes.extend_from_slice(&self.chunk_indexes.bytes);
+        self.write(&summary.bytes)?;
+
+        let mut footer = Record::default();
+        footer.u64(summary_start).u64(0).u32(0);
+        self.write_record(OP_FOOTER, &footer)?;
+        self.write(&MAGIC)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn flush_chunk(&mut self) -> Result<()> {
+        if self.chunk.bytes.is_empty() {
+            return Ok(());
+        }
+        let records = std::mem::take(&mut self.chunk);
+        let (start, end) = self.chunk_times.take().unwrap_or_default();
+        let len = records.bytes.len() as u64;
+        let mut chunk = Record::default();
+        chunk
+            .u64(start)
+            .u64(end)
+            .u64(len)
+            .u32(crc32fast::hash(&records.bytes))
+            .string("")?
+            .u64(len);
+        chunk.bytes.extend_from_slice(&records.bytes);
+        let chunk_start = self.pos;
+        self.write_record(OP_CHUNK, &chunk)?;
+        let chunk_length = self.pos - chunk_start;
+
+        let mut offsets = Record::default();
+        for (channel_id, entries) in std::mem::take(&mut self.message_indexes) {
+            offsets.u16(channel_id).u64(self.pos);
+            let mut entry_bytes = Record::default();
+            for (log_time, offset) in entries {
+                entry_bytes.u64(log_time).u64(offset);
+            }
+            let mut index = Record::default();
+            index.u16(channel_id).bytes(&entry_bytes.bytes)?;
+            self.write_record(OP_MESSAGE_INDEX, &index)?;
+        }
+
+        let mut chunk_index = Record::default();
+        chunk_index
+            .u64(start)
+            .u64(end)
+            .u64(chunk_start)
+            .u64(chunk_length)
+            .bytes(&offsets.bytes)?
+            .u64(self.pos - chunk_start - chunk_length)
+            .string("")?
+            .u64(len)
+            .u64(len);
+        self.chunk_indexes.record(OP_CHUNK_INDEX, &chunk_index);
+        self.chunk_count += 1;
+        Ok(())
+    }
+
+    fn write_record(&mut self, opcode: u8, content: &Record) -> Result<()> {
+        let mut record = Record::default();
+        record.record(opcode, content);
+        self.write(&record.bytes)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.writer.write_all(bytes)?;
+        self.pos += bytes.len() as u64;
+        Ok(())
+    }
+}
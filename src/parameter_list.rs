@@ -0,0 +1,262 @@
+//! Parameter lists, the data of the PL_CDR encapsulations.
+//!
+//! A parameter list is a sequence of parameters, each of which is a 16-bit
+//! parameter id and a 16-bit length followed by that many bytes of CDR data,
+//! ended by `ParameterId::SENTINEL`. Every parameter starts at a multiple of
+//! 4 bytes. RTPS uses parameter lists for discovery data and inline QoS, and
+//! new ids can be added to a list without breaking its existing readers.
+//!
+//! ```rust
+//! use cdr::parameter_list::{ParameterId, ParameterList};
+//!
+//! let mut list = ParameterList::new(true);
+//! list.push(ParameterId(0x0005), &"Square").unwrap();
+//! list.push(ParameterId(0x0070), &[1u8; 16]).unwrap();
+//! let bytes = list.to_bytes();
+//!
+//! let list = ParameterList::from_bytes(&bytes).unwrap();
+//! assert_eq!(list.read::<String>(ParameterId(0x0005)).unwrap().as_deref(), Some("Square"));
+//! assert_eq!(list.read::<u8>(ParameterId(0x0006)).unwrap(), None);
+//! ```
+//!
+//! The value of each parameter is aligned relative to its own first byte.
+
+use std::fmt;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::de::Deserializer;
+use crate::encapsulation::{EncapsulationKind, ENCAPSULATION_HEADER_SIZE};
+use crate::error::{Error, Result};
+use crate::ser::Serializer;
+use crate::size::Infinite;
+
+/// The identifier of a parameter.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ParameterId(pub u16);
+
+impl ParameterId {
+    /// A parameter that is skipped by readers.
+    pub const PAD: ParameterId = ParameterId(0x0000);
+    /// The end of a list.
+    pub const SENTINEL: ParameterId = ParameterId(0x0001);
+
+    const MUST_UNDERSTAND: u16 = 0x4000;
+    const VENDOR_SPECIFIC: u16 = 0x8000;
+
+    /// Returns whether a reader that does not know the parameter must reject
+    /// the list rather than skip it.
+    pub fn must_understand(self) -> bool {
+        self.0 & Self::MUST_UNDERSTAND != 0
+    }
+
+    /// Returns whether the meaning of the parameter depends on the vendor of
+    /// the writer.
+    pub fn is_vendor_specific(self) -> bool {
+        self.0 & Self::VENDOR_SPECIFIC != 0
+    }
+}
+
+impl fmt::Display for ParameterId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#06x}", self.0)
+    }
+}
+
+/// A parameter of a list.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Parameter {
+    pub id: ParameterId,
+    /// The serialized value, including the padding that follows it.
+    pub value: Vec<u8>,
+}
+
+/// A parameter list along with its byte order.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParameterList {
+    little_endian: bool,
+    parameters: Vec<Parameter>,
+}
+
+impl ParameterList {
+    /// Creates an empty list whose values are serialized in the given byte
+    /// order.
+    pub fn new(little_endian: bool) -> Self {
+        ParameterList {
+            little_endian,
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Parses a list from a slice of bytes that starts with a PL_CDR_BE or
+    /// PL_CDR_LE encapsulation header.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let kind = EncapsulationKind::from_header(bytes)?;
+        let little_endian = match kind {
+            EncapsulationKind::PlCdrBe => false,
+            EncapsulationKind::PlCdrLe => true,
+            _ => return Err(Error::InvalidEncapsulation),
+        };
+        let data = &bytes[ENCAPSULATION_HEADER_SIZE as usize..];
+        Self::from_data(data, little_endian).map(|(list, _)| list)
+    }
+
+    /// Parses a list without an encapsulation header, such as the inline QoS
+    /// of an RTPS submessage, and returns it along with the number of bytes
+    /// it occupies including the sentinel.
+    pub fn from_data(data: &[u8], little_endian: bool) -> Result<(Self, usize)> {
+        let mut list = Self::new(little_endian);
+        let mut pos = 0;
+        loop {
+            let header = data.get(pos..pos + 4).ok_or(Error::Eof)?;
+            let (id, len) = if little_endian {
+                (
+                    LittleEndian::read_u16(header),
+                    LittleEndian::read_u16(&header[2..]),
+                )
+            } else {
+                (
+                    BigEndian::read_u16(header),
+                    BigEndian::read_u16(&header[2..]),
+                )
+            };
+            let id = ParameterId(id);
+            let len = len as usize;
+            pos += 4;
+            if id == ParameterId::SENTINEL {
+                return Ok((list, pos));
+            }
+            if len & 3 != 0 {
+                return Err(Error::Custom(format!(
+                    "length {} of parameter {} is not a multiple of 4",
+                    len, id
+                )));
+            }
+            let value = data.get(pos..pos + len).ok_or(Error::Eof)?;
+            pos += len;
+            if id != ParameterId::PAD {
+                list.parameters.push(Parameter {
+                    id,
+                    value: value.to_vec(),
+                });
+            }
+        }
+    }
+
+    /// Returns whether the values are serialized in little-endian byte order.
+    pub fn is_little_endian(&self) -> bool {
+        self.little_endian
+    }
+
+    /// Returns the parameters in order.
+    pub fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    /// Returns the first parameter with the id.
+    pub fn get(&self, id: ParameterId) -> Option<&Parameter> {
+        self.parameters.iter().find(|p| p.id == id)
+    }
+
+    /// Deserializes the value of the first parameter with the id.
+    pub fn read<T>(&self, id: ParameterId) -> Result<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.get(id).map(|p| self.decode(p)).transpose()
+    }
+
+    /// Deserializes the values of all parameters with the id, such as the
+    /// locators of a participant.
+    pub fn read_all<T>(&self, id: ParameterId) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.parameters
+            .iter()
+            .filter(|p| p.id == id)
+            .map(|p| self.decode(p))
+            .collect()
+    }
+
+    /// Deserializes the value of a parameter in the byte order of the list.
+    pub fn decode<T>(&self, parameter: &Parameter) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let value = &parameter.value[..];
+        if self.little_endian {
+            let mut deserializer =
+                Deserializer::<_, _, LittleEndian>::with_alignment(value, Infinite, 0, 8);
+            T::deserialize(&mut deserializer)
+        } else {
+            let mut deserializer =
+                Deserializer::<_, _, BigEndian>::with_alignment(value, Infinite, 0, 8);
+            T::deserialize(&mut deserializer)
+        }
+    }
+
+    /// Serializes a value in the byte order of the list and appends it as a
+    /// parameter.
+    pub fn push<T>(&mut self, id: ParameterId, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut buf = Vec::new();
+        if self.little_endian {
+            value.serialize(&mut Serializer::<_, LittleEndian>::new(&mut buf))?;
+        } else {
+            value.serialize(&mut Serializer::<_, BigEndian>::new(&mut buf))?;
+        }
+        self.push_raw(Parameter { id, value: buf })
+    }
+
+    /// Appends a parameter whose value is already serialized, padding it to
+    /// a multiple of 4 bytes.
+    pub fn push_raw(&mut self, mut parameter: Parameter) -> Result<()> {
+        let padded = (parameter.value.len() + 3) & !3;
+        if padded > u16::MAX as usize {
+            return Err(Error::SizeLimit);
+        }
+        parameter.value.resize(padded, 0);
+        self.parameters.push(parameter);
+        Ok(())
+    }
+
+    /// Returns the list with a PL_CDR_BE or PL_CDR_LE encapsulation header.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let kind = if self.little_endian {
+            EncapsulationKind::PlCdrLe
+        } else {
+            EncapsulationKind::PlCdrBe
+        };
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&kind.id());
+        bytes.extend_from_slice(&[0; 2]);
+        self.write_data(&mut bytes);
+        bytes
+    }
+
+    /// Appends the list to `buf` without an encapsulation header, ending it
+    /// with a sentinel.
+    pub fn write_data(&self, buf: &mut Vec<u8>) {
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|p| (p.id, &p.value[..]))
+            .chain(Some((ParameterId::SENTINEL, &[][..])));
+        for (id, value) in parameters {
+            let mut header = [0; 4];
+            if self.little_endian {
+                LittleEndian::write_u16(&mut header, id.0);
+                LittleEndian::write_u16(&mut header[2..], value.len() as u16);
+            } else {
+                BigEndian::write_u16(&mut header, id.0);
+                BigEndian::write_u16(&mut header[2..], value.len() as u16);
+            }
+            buf.extend_from_slice(&header);
+            buf.extend_from_slice(value);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Converting encapsulated data between encapsulation schemes.
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use serde::{de, ser};
+
+use crate::de::Deserializer;
+use crate::encapsulation::{EncapsulationKind, ENCAPSULATION_HEADER_SIZE};
+use crate::error::{Error, Result};
+use crate::ser::Serializer;
+use crate::size::Infinite;
+use crate::value::{Described, TypeDescriptor};
+
+/// Rewrites a slice of bytes with the encapsulation holding a `T` into the
+/// `target` encapsulation, e.g. from big-endian to little-endian or from
+/// XCDR1 to XCDR2.
+///
+/// The data is converted through the descriptor of `T`, as with
+/// `reencode_dynamic`, so no `T` is built.
+///
+/// ```rust
+/// use cdr::{CdrBe, EncapsulationKind, Infinite};
+///
+/// let encoded = cdr::serialize::<_, _, CdrBe>(&(1u8, 2.5f64), Infinite).unwrap();
+/// let reencoded = cdr::reencode::<(u8, f64)>(&encoded, EncapsulationKind::Cdr2Le).unwrap();
+/// assert_eq!(reencoded, [0, 7, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 64]);
+/// ```
+pub fn reencode<T>(bytes: &[u8], target: EncapsulationKind) -> Result<Vec<u8>>
+where
+    T: ?Sized + Described,
+{
+    reencode_dynamic(bytes, &T::descriptor(), target)
+}
+
+/// Rewrites a slice of bytes with the encapsulation holding a value of the
+/// described type into the `target` encapsulation, e.g. from big-endian to
+/// little-endian or from XCDR1 to XCDR2.
+///
+/// The data is converted in a single pass, and only one primitive or string
+/// is held in memory at a time. The options of the encapsulation are carried
+/// over, except for the padding bits, as trailing padding is not. Parameter
+/// lists, the data of the PL_CDR encapsulations, are not supported, and
+/// neither are conversions between XCDR1 and XCDR2 of sequences and arrays of
+/// non-primitive elements, which XCDR2 prefixes with a DHEADER.
+///
+/// ```rust
+/// use cdr::{CdrBe, EncapsulationKind, Infinite, TypeDescriptor};
+/// use cdr::value::{Member, StructDescriptor};
+///
+/// let ty = TypeDescriptor::Struct(StructDescriptor {
+///     name: "Sample".to_string(),
+///     members: vec![
+///         Member::new("id", TypeDescriptor::U8),
+///         Member::new("value", TypeDescriptor::F64),
+///     ],
+/// });
+/// let encoded = cdr::serialize::<_, _, CdrBe>(&(1u8, 2.5f64), Infinite).unwrap();
+/// let reencoded = cdr::reencode_dynamic(&encoded, &ty, EncapsulationKind::Cdr2Le).unwrap();
+/// assert_eq!(reencoded, [0, 7, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 64]);
+/// ```
+pub fn reencode_dynamic(
+    bytes: &[u8],
+    ty: &TypeDescriptor,
+    target: EncapsulationKind,
+) -> Result<Vec<u8>> {
+    let source = EncapsulationKind::from_header(bytes)?;
+    if is_parameter_list(source) || is_parameter_list(target) {
+        return Err(Error::Custom(
+            "parameter lists cannot be reencoded".to_string(),
+        ));
+    }
+    if is_xcdr2(source) != is_xcdr2(target) && has_delimited_collection(ty) {
+        return Err(Error::Custom(
+            "sequences and arrays of non-primitive elements cannot be converted between XCDR1 \
+             and XCDR2"
+                .to_string(),
+        ));
+    }
+    let data = &bytes[ENCAPSULATION_HEADER_SIZE as usize..];
+
+    let mut buf = Vec::with_capacity(bytes.len());
+    buf.extend_from_slice(&target.id());
+    buf.extend_from_slice(&[bytes[2], bytes[3] & !PADDING_MASK]);
+    match (source.is_little_endian(), target.is_little_endian()) {
+        (false, false) => run::<BigEndian, BigEndian>(data, source, target, &mut buf, ty)?,
+        (false, true) => run::<BigEndian, LittleEndian>(data, source, target, &mut buf, ty)?,
+        (true, false) => run::<LittleEndian, BigEndian>(data, source, target, &mut buf, ty)?,
+        (true, true) => run::<LittleEndian, LittleEndian>(data, source, target, &mut buf, ty)?,
+    }
+    Ok(buf)
+}
+
+/// The bits of the options that hold the number of bytes of padding at the
+/// end of the data.
+const PADDING_MASK: u8 = 0b11;
+
+fn is_parameter_list(kind: EncapsulationKind) -> bool {
+    matches!(
+        kind,
+        EncapsulationKind::PlCdrBe | EncapsulationKind::PlCdrLe
+    )
+}
+
+fn is_xcdr2(kind: EncapsulationKind) -> bool {
+    matches!(kind, EncapsulationKind::Cdr2Be | EncapsulationKind::Cdr2Le)
+}
+
+/// Returns whether the type holds a sequence or an array of elements that are
+/// not primitives, whose XCDR2 encoding starts with a DHEADER.
+fn has_delimited_collection(ty: &TypeDescriptor) -> bool {
+    use crate::value::TypeDescriptor as D;
+
+    match *ty {
+        D::Sequence(ref element)
+        | D::BoundedSequence(ref element, _)
+        | D::Array(ref element, _) => !matches!(
+            **element,
+            D::Bool
+                | D::Char
+                | D::I8
+                | D::I16
+                | D::I32
+                | D::I64
+                | D::U8
+                | D::U16
+                | D::U32
+                | D::U64
+                | D::F32
+                | D::F64
+        ),
+        D::Struct(ref desc) => desc
+            .members
+            .iter()
+            .any(|member| has_delimited_collection(&member.ty)),
+        D::Union(ref desc) => desc
+            .cases
+            .iter()
+            .map(|case| &case.member)
+            .chain(desc.default.as_deref())
+            .any(|member| has_delimited_collection(&member.ty)),
+        _ => false,
+    }
+}
+
+fn run<S, T>(
+    data: &[u8],
+    source: EncapsulationKind,
+    target: EncapsulationKind,
+    buf: &mut Vec<u8>,
+    ty: &TypeDescriptor,
+) -> Result<()>
+where
+    S: ByteOrder,
+    T: ByteOrder,
+{
+    let mut reencoder = Reencoder {
+        de: Deserializer::<_, _, S>::with_alignment(data, Infinite, 0, source.max_alignment()),
+        ser: Serializer::<_, T>::with_alignment(buf, 0, target.max_alignment()),
+    };
+    reencoder.convert(ty)
+}
+
+/// Copies what it reads from a deserializer to a serializer.
+struct Reencoder<'a, S, T> {
+    de: Deserializer<&'a [u8], Infinite, S>,
+    ser: Serializer<&'a mut Vec<u8>, T>,
+}
+
+impl<'a, S, T> Reencoder<'a, S, T>
+where
+    S: ByteOrder,
+    T: ByteOrder,
+{
+    fn copy<V>(&mut self) -> Result<V>
+    where
+        V: de::DeserializeOwned + ser::Serialize,
+    {
+        let value = V::deserialize(&mut self.de)?;
+        value.serialize(&mut self.ser)?;
+        Ok(value)
+    }
+
+    /// Copies a value of the described type.
+    fn convert(&mut self, ty: &TypeDescriptor) -> Result<()> {
+        use crate::value::TypeDescriptor as D;
+
+        match *ty {
+            D::Unit => Ok(()),
+            D::Bool => self.copy::<bool>().map(drop),
+            D::Char => self.copy::<char>().map(drop),
+            D::I8 => self.copy::<i8>().map(drop),
+            D::I16 => self.copy::<i16>().map(drop),
+            D::I32 => self.copy::<i32>().map(drop),
+            D::I64 => self.copy::<i64>().map(drop),
+            D::U8 => self.copy::<u8>().map(drop),
+            D::U16 => self.copy::<u16>().map(drop),
+            D::U32 => self.copy::<u32>().map(drop),
+            D::U64 => self.copy::<u64>().map(drop),
+            D::F32 => self.copy::<f32>().map(drop),
+            D::F64 => self.copy::<f64>().map(drop),
+            D::String | D::BoundedString(_) => self.copy::<String>().map(drop),
+            D::Sequence(ref element) | D::BoundedSequence(ref element, _) => {
+                let len = self.copy::<u32>()?;
+                (0..len).try_for_each(|_| self.convert(element))
+            }
+            D::Array(ref element, len) => (0..len).try_for_each(|_| self.convert(element)),
+            D::Struct(ref desc) => desc
+                .members
+                .iter()
+                .try_for_each(|member| self.convert(&member.ty)),
+            D::Union(ref desc) => {
+                let label = self.copy_label(&desc.discriminator)?;
+                match desc.member(label) {
+                    Some(member) => self.convert(&member.ty),
+                    None => Err(de::Error::invalid_value(
+                        de::Unexpected::Signed(label),
+                        &desc.name.as_str(),
+                    )),
+                }
+            }
+        }
+    }
+
+    /// Copies the discriminator of a union and returns its label.
+    fn copy_label(&mut self, ty: &TypeDescriptor) -> Result<i64> {
+        use crate::value::TypeDescriptor as D;
+
+        Ok(match *ty {
+            D::Bool => i64::from(self.copy::<bool>()?),
+            D::Char => i64::from(u32::from(self.copy::<char>()?)),
+            D::I8 => i64::from(self.copy::<i8>()?),
+            D::I16 => i64::from(self.copy::<i16>()?),
+            D::I32 => i64::from(self.copy::<i32>()?),
+            D::I64 => self.copy::<i64>()?,
+            D::U8 => i64::from(self.copy::<u8>()?),
+            D::U16 => i64::from(self.copy::<u16>()?),
+            D::U32 => i64::from(self.copy::<u32>()?),
+            D::U64 => self.copy::<u64>()? as i64,
+            D::Union(ref desc) if desc.is_enumeration() => self.copy_label(&desc.discriminator)?,
+            _ => {
+                return Err(Error::Custom(
+                    "invalid union discriminator type".to_string(),
+                ))
+            }
+        })
+    }
+}
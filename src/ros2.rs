@@ -0,0 +1,509 @@
+//! Conventions of ROS 2 messages carried over DDS.
+//!
+//! The ROS 2 middleware implementations for Cyclone DDS and Fast DDS publish
+//! messages as little-endian classic CDR, so they are encoded with
+//! `cdr::serialize::<_, _, CdrLe>` and read with `cdr::deserialize`. This
+//! module provides the types and names that those implementations map ROS 2
+//! interfaces to, and reads `.msg` and `.srv` files into type descriptors.
+//!
+//! ```rust
+//! use cdr::ros2::Interfaces;
+//! use cdr::{CdrLe, Infinite, Value};
+//!
+//! let mut interfaces = Interfaces::new();
+//! interfaces
+//!     .add_msg("geometry_msgs", "Point", "float64 x\nfloat64 y\nfloat64 z\n")
+//!     .unwrap();
+//! let ty = interfaces
+//!     .add_msg(
+//!         "geometry_msgs",
+//!         "PointStamped",
+//!         "# A point with a timestamp.\nstd_msgs/Header header\nPoint point\n",
+//!     )
+//!     .unwrap();
+//!
+//! let encoded = cdr::serialize::<_, _, CdrLe>(&((1, 2u32, "map"), (1.0, 2.0, 3.0)), Infinite).unwrap();
+//! let value = cdr::value::deserialize(&encoded, ty).unwrap();
+//! assert_eq!(
+//!     value,
+//!     Value::Struct(vec![
+//!         Value::Struct(vec![
+//!             Value::Struct(vec![Value::I32(1), Value::U32(2)]),
+//!             Value::String("map".to_string()),
+//!         ]),
+//!         Value::Struct(vec![Value::F64(1.0), Value::F64(2.0), Value::F64(3.0)]),
+//!     ])
+//! );
+//! ```
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::idl::ParseError;
+use crate::size::{CdrFixedSize, Layout};
+use crate::value::{Described, Member, StructDescriptor, TypeDescriptor};
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+
+/// The name of the member that ROS 2 adds to messages without fields, since
+/// IDL structs cannot be empty.
+pub const EMPTY_MEMBER_NAME: &str = "structure_needs_at_least_one_member";
+
+/// A point in time, `builtin_interfaces/msg/Time`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Time {
+    pub sec: i32,
+    /// The nanoseconds past `sec`, less than 1 000 000 000.
+    pub nanosec: u32,
+}
+
+impl Time {
+    pub fn new(sec: i32, nanosec: u32) -> Self {
+        Time { sec, nanosec }
+    }
+
+    /// Returns the time a number of nanoseconds past the epoch, or `None` if
+    /// its seconds do not fit in an `i32`.
+    pub fn from_nanos(nanos: i64) -> Option<Self> {
+        let (sec, nanosec) = split_nanos(nanos)?;
+        Some(Time { sec, nanosec })
+    }
+
+    /// Returns the number of nanoseconds past the epoch.
+    pub fn as_nanos(&self) -> i64 {
+        i64::from(self.sec) * NANOS_PER_SEC + i64::from(self.nanosec)
+    }
+}
+
+/// A span of time, `builtin_interfaces/msg/Duration`, which may be negative.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Duration {
+    pub sec: i32,
+    /// The nanoseconds added to `sec`, less than 1 000 000 000.
+    pub nanosec: u32,
+}
+
+impl Duration {
+    pub fn new(sec: i32, nanosec: u32) -> Self {
+        Duration { sec, nanosec }
+    }
+
+    /// Returns the duration of a number of nanoseconds, or `None` if its
+    /// seconds do not fit in an `i32`.
+    pub fn from_nanos(nanos: i64) -> Option<Self> {
+        let (sec, nanosec) = split_nanos(nanos)?;
+        Some(Duration { sec, nanosec })
+    }
+
+    /// Returns the number of nanoseconds of the duration.
+    pub fn as_nanos(&self) -> i64 {
+        i64::from(self.sec) * NANOS_PER_SEC + i64::from(self.nanosec)
+    }
+}
+
+fn split_nanos(nanos: i64) -> Option<(i32, u32)> {
+    let sec = i32::try_from(nanos.div_euclid(NANOS_PER_SEC)).ok()?;
+    Some((sec, nanos.rem_euclid(NANOS_PER_SEC) as u32))
+}
+
+macro_rules! impl_sec_nanosec {
+    ($($ty:ident),*) => {
+        $(
+            impl Serialize for $ty {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    (self.sec, self.nanosec).serialize(serializer)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $ty {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    let (sec, nanosec) = Deserialize::deserialize(deserializer)?;
+                    Ok($ty { sec, nanosec })
+                }
+            }
+
+            impl CdrFixedSize for $ty {
+                const LAYOUT: Layout = <(i32, u32)>::LAYOUT;
+                const LAYOUT_XCDR2: Layout = <(i32, u32)>::LAYOUT_XCDR2;
+            }
+
+            impl Described for $ty {
+                fn descriptor() -> TypeDescriptor {
+                    TypeDescriptor::Struct(StructDescriptor {
+                        name: format!("builtin_interfaces::msg::dds_::{}_", stringify!($ty)),
+                        members: vec![
+                            Member::new("sec", TypeDescriptor::I32),
+                            Member::new("nanosec", TypeDescriptor::U32),
+                        ],
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_sec_nanosec!(Time, Duration);
+
+/// A message without fields, such as `std_msgs/msg/Empty`, which is encoded
+/// as the single octet of its `structure_needs_at_least_one_member`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Empty;
+
+impl Serialize for Empty {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        0u8.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Empty {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        u8::deserialize(deserializer).map(|_| Empty)
+    }
+}
+
+impl CdrFixedSize for Empty {
+    const LAYOUT: Layout = u8::LAYOUT;
+    const LAYOUT_XCDR2: Layout = u8::LAYOUT_XCDR2;
+}
+
+impl Described for Empty {
+    fn descriptor() -> TypeDescriptor {
+        TypeDescriptor::Struct(StructDescriptor {
+            name: "std_msgs::msg::dds_::Empty_".to_string(),
+            members: vec![Member::new(EMPTY_MEMBER_NAME, TypeDescriptor::U8)],
+        })
+    }
+}
+
+/// The header that rmw_cyclonedds puts before the data of service requests
+/// and replies, identifying the request. rmw_fastrtps carries it in the
+/// inline QoS instead.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ServiceHeader {
+    /// The GUID of the client, truncated to 64 bits.
+    pub guid: u64,
+    pub sequence_number: i64,
+}
+
+impl Serialize for ServiceHeader {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (self.guid, self.sequence_number).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ServiceHeader {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (guid, sequence_number) = Deserialize::deserialize(deserializer)?;
+        Ok(ServiceHeader {
+            guid,
+            sequence_number,
+        })
+    }
+}
+
+impl CdrFixedSize for ServiceHeader {
+    const LAYOUT: Layout = <(u64, i64)>::LAYOUT;
+    const LAYOUT_XCDR2: Layout = <(u64, i64)>::LAYOUT_XCDR2;
+}
+
+impl Described for ServiceHeader {
+    fn descriptor() -> TypeDescriptor {
+        TypeDescriptor::Struct(StructDescriptor {
+            name: "ServiceHeader".to_string(),
+            members: vec![
+                Member::new("guid", TypeDescriptor::U64),
+                Member::new("sequence_number", TypeDescriptor::I64),
+            ],
+        })
+    }
+}
+
+/// Returns the DDS topic of a fully qualified ROS 2 topic, e.g. `rt/chatter`
+/// for `/chatter`.
+pub fn topic_name(topic: &str) -> String {
+    format!("rt{}", topic)
+}
+
+/// Returns the DDS topic of the requests of a fully qualified ROS 2 service.
+pub fn request_topic_name(service: &str) -> String {
+    format!("rq{}Request", service)
+}
+
+/// Returns the DDS topic of the replies of a fully qualified ROS 2 service.
+pub fn reply_topic_name(service: &str) -> String {
+    format!("rr{}Reply", service)
+}
+
+/// Returns the DDS type name of a ROS 2 interface type, e.g.
+/// `std_msgs::msg::dds_::String_` for `std_msgs/msg/String`.
+pub fn dds_type_name(name: &str) -> Option<String> {
+    let mut parts = name.split('/');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(package), Some(kind), Some(name), None) => {
+            Some(format!("{}::{}::dds_::{}_", package, kind, name))
+        }
+        _ => None,
+    }
+}
+
+const BUILTIN: &[(&str, &str, &str)] = &[
+    ("builtin_interfaces", "Time", "int32 sec\nuint32 nanosec\n"),
+    (
+        "builtin_interfaces",
+        "Duration",
+        "int32 sec\nuint32 nanosec\n",
+    ),
+    (
+        "std_msgs",
+        "Header",
+        "builtin_interfaces/Time stamp\nstring frame_id\n",
+    ),
+];
+
+/// The types of ROS 2 interfaces read from `.msg` and `.srv` files, by name
+/// such as `std_msgs/msg/Header`.
+///
+/// `builtin_interfaces/msg/Time`, `builtin_interfaces/msg/Duration` and
+/// `std_msgs/msg/Header` are always defined. Other messages must be added
+/// before the interfaces that use them. A bare `Header` refers to the
+/// package's own `Header` message if it has one, and to `std_msgs/msg/Header`
+/// otherwise. Constants and default values are accepted and ignored, and
+/// `wstring` is not supported.
+#[derive(Clone, Debug)]
+pub struct Interfaces {
+    types: BTreeMap<String, TypeDescriptor>,
+}
+
+impl Default for Interfaces {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interfaces {
+    pub fn new() -> Self {
+        let mut interfaces = Interfaces {
+            types: BTreeMap::new(),
+        };
+        for &(package, name, source) in BUILTIN {
+            interfaces
+                .add_msg(package, name, source)
+                .expect("invalid builtin message");
+        }
+        interfaces
+    }
+
+    /// Returns the type with the given name, such as `std_msgs/msg/Header`
+    /// or `std_msgs/Header`.
+    pub fn get(&self, name: &str) -> Option<&TypeDescriptor> {
+        self.types.get(name).or_else(|| {
+            let (package, name) = name.split_once('/')?;
+            self.types.get(&format!("{}/msg/{}", package, name))
+        })
+    }
+
+    /// Returns the names of the defined types.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.types.keys().map(String::as_str)
+    }
+
+    /// Adds the message `package/msg/name` defined by the contents of a
+    /// `.msg` file.
+    pub fn add_msg(
+        &mut self,
+        package: &str,
+        name: &str,
+        source: &str,
+    ) -> Result<&TypeDescriptor, ParseError> {
+        let members = self.members(package, source, 1)?;
+        Ok(self.insert(package, "msg", name, members))
+    }
+
+    /// Adds the request and reply messages of the service `package/srv/name`,
+    /// `package/srv/name_Request` and `package/srv/name_Response`, defined by
+    /// the contents of a `.srv` file.
+    pub fn add_srv(&mut self, package: &str, name: &str, source: &str) -> Result<(), ParseError> {
+        let separator = source
+            .lines()
+            .position(|line| strip_comment(line).trim() == "---")
+            .ok_or_else(|| ParseError::new(1, 1, "missing `---` separator".to_string()))?;
+        let request: Vec<&str> = source.lines().take(separator).collect();
+        let response: Vec<&str> = source.lines().skip(separator + 1).collect();
+        let request = self.members(package, &request.join("\n"), 1)?;
+        let response = self.members(package, &response.join("\n"), separator + 2)?;
+        self.insert(package, "srv", &format!("{}_Request", name), request);
+        self.insert(package, "srv", &format!("{}_Response", name), response);
+        Ok(())
+    }
+
+    fn insert(
+        &mut self,
+        package: &str,
+        kind: &str,
+        name: &str,
+        members: Vec<Member>,
+    ) -> &TypeDescriptor {
+        let ty = TypeDescriptor::Struct(StructDescriptor {
+            name: format!("{}::{}::dds_::{}_", package, kind, name),
+            members,
+        });
+        let slot = self
+            .types
+            .entry(format!("{}/{}/{}", package, kind, name))
+            .or_insert(TypeDescriptor::Unit);
+        *slot = ty;
+        slot
+    }
+
+    fn members(
+        &self,
+        package: &str,
+        source: &str,
+        first_line: usize,
+    ) -> Result<Vec<Member>, ParseError> {
+        let mut members: Vec<Member> = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            let line_number = first_line + index;
+            let content = strip_comment(line);
+            let start = match content.find(|c: char| !c.is_whitespace()) {
+                Some(start) => start,
+                None => continue,
+            };
+            let error = |offset: usize, message: String| {
+                ParseError::new(line_number, line[..offset].chars().count() + 1, message)
+            };
+
+            let rest = &content[start..];
+            let type_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let (type_name, rest) = rest.split_at(type_end);
+            let name_start = start + type_end + (rest.len() - rest.trim_start().len());
+            let rest = rest.trim_start();
+            // A constant has `=` right after its name, while a default value
+            // of a field may contain one anywhere.
+            let name_end = rest
+                .find(|c: char| c.is_whitespace() || c == '=')
+                .unwrap_or(rest.len());
+            let (name, value) = rest.split_at(name_end);
+            let is_constant = value.trim_start().starts_with('=');
+            if !is_identifier(name) {
+                return Err(error(
+                    name_start,
+                    format!("expected a field name, found `{}`", name),
+                ));
+            }
+            let ty = self
+                .resolve(package, type_name)
+                .map_err(|message| error(start, message))?;
+            if is_constant {
+                continue;
+            }
+            if members.iter().any(|member| member.name == name) {
+                return Err(error(name_start, format!("duplicate field `{}`", name)));
+            }
+            members.push(Member::new(name, ty));
+        }
+        if members.is_empty() {
+            members.push(Member::new(EMPTY_MEMBER_NAME, TypeDescriptor::U8));
+        }
+        Ok(members)
+    }
+
+    fn resolve(&self, package: &str, type_name: &str) -> Result<TypeDescriptor, String> {
+        let (base, array) = match type_name.find('[') {
+            Some(index) => match type_name[index + 1..].strip_suffix(']') {
+                Some(bound) => (&type_name[..index], Some(bound)),
+                None => return Err(format!("invalid array type `{}`", type_name)),
+            },
+            None => (type_name, None),
+        };
+        let element = match base {
+            "bool" => TypeDescriptor::Bool,
+            "byte" | "char" | "uint8" => TypeDescriptor::U8,
+            "int8" => TypeDescriptor::I8,
+            "int16" => TypeDescriptor::I16,
+            "uint16" => TypeDescriptor::U16,
+            "int32" => TypeDescriptor::I32,
+            "uint32" => TypeDescriptor::U32,
+            "int64" => TypeDescriptor::I64,
+            "uint64" => TypeDescriptor::U64,
+            "float32" => TypeDescriptor::F32,
+            "float64" => TypeDescriptor::F64,
+            "string" => TypeDescriptor::String,
+            _ if base.starts_with("wstring") => return Err("wstring is not supported".to_string()),
+            _ if base.starts_with("string<=") => {
+                TypeDescriptor::BoundedString(parse_bound(&base["string<=".len()..])?)
+            }
+            _ => {
+                let key = match base.split_once('/') {
+                    Some((package, name)) if !name.contains('/') => {
+                        format!("{}/msg/{}", package, name)
+                    }
+                    Some(_) => base.to_string(),
+                    None => format!("{}/msg/{}", package, base),
+                };
+                let ty = match self.types.get(&key) {
+                    None if base == "Header" => self.types.get("std_msgs/msg/Header"),
+                    ty => ty,
+                };
+                ty.cloned()
+                    .ok_or_else(|| format!("unknown type `{}`", base))?
+            }
+        };
+        let element = Box::new(element);
+        Ok(match array {
+            None => *element,
+            Some("") => TypeDescriptor::Sequence(element),
+            Some(bound) => match bound.strip_prefix("<=") {
+                Some(bound) => TypeDescriptor::BoundedSequence(element, parse_bound(bound)?),
+                None => TypeDescriptor::Array(element, parse_bound(bound)?),
+            },
+        })
+    }
+}
+
+fn parse_bound(bound: &str) -> Result<usize, String> {
+    bound
+        .parse()
+        .map_err(|_| format!("invalid bound `{}`", bound))
+}
+
+/// Removes a comment from a line, leaving `#` within quoted default values.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (index, c) in line.char_indices() {
+        match (c, quote) {
+            ('#', None) => return &line[..index],
+            ('"', None) | ('\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            _ => {}
+        }
+    }
+    line
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
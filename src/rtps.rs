@@ -0,0 +1,229 @@
+//! Helpers for carrying CDR data in RTPS messages.
+//!
+//! The data of an RTPS `DATA` submessage is a `SerializedPayload`, which is
+//! an encapsulation header followed by the serialized value. Instances of a
+//! keyed type are identified by a `KeyHash` computed from their key members
+//! as specified in RTPS 2.5, 9.6.3.8.
+
+use std::fmt;
+
+use byteorder::{BigEndian, LittleEndian};
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+
+use crate::de::Deserializer;
+use crate::encapsulation::{EncapsulationKind, ENCAPSULATION_HEADER_SIZE};
+use crate::error::{Error, Result};
+use crate::key::{self, Keyed};
+use crate::ser::Serializer;
+use crate::size::Infinite;
+
+pub mod discovery;
+
+/// The mask of the bits of the encapsulation options that hold the number of
+/// bytes of padding at the end of the data.
+const PADDING_MASK: u8 = 0x03;
+
+/// An encapsulated value as carried by RTPS.
+///
+/// The data is padded to a multiple of 4 bytes so that the submessages that
+/// follow it stay aligned, and the amount of padding is recorded in the
+/// encapsulation options as specified in DDS-XTypes 1.3, 7.6.3.1.2.
+///
+/// ```rust
+/// use cdr::rtps::SerializedPayload;
+/// use cdr::EncapsulationKind;
+///
+/// let payload = SerializedPayload::serialize(&(1u32, 2u8), EncapsulationKind::CdrLe).unwrap();
+/// assert_eq!(payload.to_bytes(), [0, 1, 0, 3, 1, 0, 0, 0, 2, 0, 0, 0]);
+///
+/// let payload = SerializedPayload::from_bytes(&payload.to_bytes()).unwrap();
+/// assert_eq!(payload.data(), [1, 0, 0, 0, 2]);
+/// assert_eq!(payload.deserialize::<(u32, u8)>().unwrap(), (1, 2));
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SerializedPayload {
+    kind: EncapsulationKind,
+    options: [u8; 2],
+    /// The data including its padding.
+    data: Vec<u8>,
+}
+
+impl SerializedPayload {
+    /// Serializes a value with the encapsulation.
+    pub fn serialize<T>(value: &T, kind: EncapsulationKind) -> Result<Self>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut data = Vec::new();
+        if kind.is_little_endian() {
+            let mut serializer =
+                Serializer::<_, LittleEndian>::with_alignment(&mut data, 0, kind.max_alignment());
+            value.serialize(&mut serializer)?;
+        } else {
+            let mut serializer =
+                Serializer::<_, BigEndian>::with_alignment(&mut data, 0, kind.max_alignment());
+            value.serialize(&mut serializer)?;
+        }
+        Ok(Self::new(kind, data))
+    }
+
+    /// Wraps data that is already serialized with the encapsulation, padding
+    /// it to a multiple of 4 bytes.
+    pub fn new(kind: EncapsulationKind, mut data: Vec<u8>) -> Self {
+        let padding = (4 - data.len() % 4) % 4;
+        data.resize(data.len() + padding, 0);
+        SerializedPayload {
+            kind,
+            options: [0, padding as u8],
+            data,
+        }
+    }
+
+    /// Parses a payload from the bytes of a `DATA` submessage.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let kind = EncapsulationKind::from_header(bytes)?;
+        let header = ENCAPSULATION_HEADER_SIZE as usize;
+        let options = [bytes[2], bytes[3]];
+        let padding = (options[1] & PADDING_MASK) as usize;
+        if bytes.len() - header < padding {
+            return Err(Error::Eof);
+        }
+        Ok(SerializedPayload {
+            kind,
+            options,
+            data: bytes[header..].to_vec(),
+        })
+    }
+
+    /// Returns the encapsulation of the data.
+    pub fn kind(&self) -> EncapsulationKind {
+        self.kind
+    }
+
+    /// Returns the encapsulation options.
+    pub fn options(&self) -> [u8; 2] {
+        self.options
+    }
+
+    /// Returns the number of bytes of padding at the end of the data.
+    pub fn padding(&self) -> usize {
+        (self.options[1] & PADDING_MASK) as usize
+    }
+
+    /// Returns the serialized value without the padding.
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.data.len() - self.padding()]
+    }
+
+    /// Deserializes the value.
+    pub fn deserialize<'de, T>(&'de self) -> Result<T>
+    where
+        T: Deserialize<'de>,
+    {
+        let data = self.data();
+        let max_alignment = self.kind.max_alignment();
+        if self.kind.is_little_endian() {
+            let mut deserializer = Deserializer::<_, _, LittleEndian>::with_alignment(
+                data,
+                Infinite,
+                0,
+                max_alignment,
+            );
+            T::deserialize(&mut deserializer)
+        } else {
+            let mut deserializer =
+                Deserializer::<_, _, BigEndian>::with_alignment(data, Infinite, 0, max_alignment);
+            T::deserialize(&mut deserializer)
+        }
+    }
+
+    /// Returns the number of bytes of the payload, including its header.
+    pub fn len(&self) -> usize {
+        ENCAPSULATION_HEADER_SIZE as usize + self.data.len()
+    }
+
+    /// Returns `true` if the payload holds no data.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the bytes of the payload, including its header.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.len());
+        bytes.extend_from_slice(&self.kind.id());
+        bytes.extend_from_slice(&self.options);
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+}
+
+/// The 16-byte hash that identifies an instance of a keyed type.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct KeyHash(pub [u8; 16]);
+
+impl KeyHash {
+    /// Computes the hash of a key that is already serialized in big-endian
+    /// CDR. `max_size` is the largest size of a serialized key of the type,
+    /// or `None` if it has no upper bound.
+    ///
+    /// The key is used as is, padded with zeros, if no key of the type can be
+    /// longer than 16 bytes, and it is hashed with MD5 otherwise.
+    pub fn from_serialized_key(key: &[u8], max_size: Option<u64>) -> Self {
+        let mut hash = [0; 16];
+        match max_size {
+            Some(max_size) if max_size <= 16 && key.len() <= 16 => {
+                hash[..key.len()].copy_from_slice(key);
+            }
+            _ => hash.copy_from_slice(&Md5::digest(key)),
+        }
+        KeyHash(hash)
+    }
+}
+
+impl fmt::Display for KeyHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for b in &self.0 {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes the key hash of an instance from its key members serialized in
+/// big-endian CDR, as specified in RTPS 2.5, 9.6.3.8.
+///
+/// Keys that may be longer than 16 bytes when serialized, including those
+/// with unbounded strings or sequences, are hashed with MD5.
+///
+/// ```rust
+/// let hash = cdr::rtps::key_hash(&(7u32, 1u16)).unwrap();
+/// assert_eq!(hash.0, [0, 0, 0, 7, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+/// ```
+pub fn key_hash<K>(value: &K) -> Result<KeyHash>
+where
+    K: ?Sized + Keyed,
+{
+    key_hash_with(value, EncapsulationKind::CdrBe)
+}
+
+/// Computes the key hash of an instance from its key members serialized in
+/// big-endian XCDR2, as specified in DDS-XTypes 1.3, 7.6.8.
+pub fn key_hash_xcdr2<K>(value: &K) -> Result<KeyHash>
+where
+    K: ?Sized + Keyed,
+{
+    key_hash_with(value, EncapsulationKind::Cdr2Be)
+}
+
+fn key_hash_with<K>(value: &K, kind: EncapsulationKind) -> Result<KeyHash>
+where
+    K: ?Sized + Keyed,
+{
+    let mut serialized = Vec::new();
+    key::serialize_key_data(&mut serialized, value, kind)?;
+    Ok(KeyHash::from_serialized_key(
+        &serialized,
+        key::max_serialized_key_size::<K>(kind),
+    ))
+}
@@ -0,0 +1,336 @@
+//! The parameters of the RTPS built-in discovery protocols.
+//!
+//! SPDP and SEDP announce participants and endpoints with PL_CDR parameter
+//! lists whose parameter ids and value types are specified in RTPS 2.5,
+//! 9.6.2.2 and 9.6.3.
+//!
+//! ```rust
+//! use cdr::rtps::discovery::{self, DiscoveryParameter as P, Locator};
+//!
+//! let announced = vec![
+//!     P::TopicName("Square".to_string()),
+//!     P::UnicastLocator(Locator::udpv4([192, 168, 0, 2], 7411)),
+//! ];
+//! let bytes = discovery::to_parameter_list(&announced, true).unwrap().to_bytes();
+//!
+//! let list = cdr::parameter_list::ParameterList::from_bytes(&bytes).unwrap();
+//! assert_eq!(discovery::parse(&list).unwrap(), announced);
+//! ```
+
+use std::fmt;
+
+use serde::{de, ser, Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::parameter_list::{Parameter, ParameterId, ParameterList};
+
+/// The ids of the parameters of discovery data.
+pub mod pid {
+    use crate::parameter_list::ParameterId;
+
+    pub const PARTICIPANT_LEASE_DURATION: ParameterId = ParameterId(0x0002);
+    pub const TIME_BASED_FILTER: ParameterId = ParameterId(0x0004);
+    pub const TOPIC_NAME: ParameterId = ParameterId(0x0005);
+    pub const OWNERSHIP_STRENGTH: ParameterId = ParameterId(0x0006);
+    pub const TYPE_NAME: ParameterId = ParameterId(0x0007);
+    pub const DOMAIN_ID: ParameterId = ParameterId(0x000f);
+    pub const PROTOCOL_VERSION: ParameterId = ParameterId(0x0015);
+    pub const VENDOR_ID: ParameterId = ParameterId(0x0016);
+    pub const RELIABILITY: ParameterId = ParameterId(0x001a);
+    pub const LIVELINESS: ParameterId = ParameterId(0x001b);
+    pub const DURABILITY: ParameterId = ParameterId(0x001d);
+    pub const OWNERSHIP: ParameterId = ParameterId(0x001f);
+    pub const DEADLINE: ParameterId = ParameterId(0x0023);
+    pub const LATENCY_BUDGET: ParameterId = ParameterId(0x0027);
+    pub const PARTITION: ParameterId = ParameterId(0x0029);
+    pub const LIFESPAN: ParameterId = ParameterId(0x002b);
+    pub const USER_DATA: ParameterId = ParameterId(0x002c);
+    pub const GROUP_DATA: ParameterId = ParameterId(0x002d);
+    pub const TOPIC_DATA: ParameterId = ParameterId(0x002e);
+    pub const UNICAST_LOCATOR: ParameterId = ParameterId(0x002f);
+    pub const MULTICAST_LOCATOR: ParameterId = ParameterId(0x0030);
+    pub const DEFAULT_UNICAST_LOCATOR: ParameterId = ParameterId(0x0031);
+    pub const METATRAFFIC_UNICAST_LOCATOR: ParameterId = ParameterId(0x0032);
+    pub const METATRAFFIC_MULTICAST_LOCATOR: ParameterId = ParameterId(0x0033);
+    pub const PARTICIPANT_MANUAL_LIVELINESS_COUNT: ParameterId = ParameterId(0x0034);
+    pub const HISTORY: ParameterId = ParameterId(0x0040);
+    pub const EXPECTS_INLINE_QOS: ParameterId = ParameterId(0x0043);
+    pub const DEFAULT_MULTICAST_LOCATOR: ParameterId = ParameterId(0x0048);
+    pub const TRANSPORT_PRIORITY: ParameterId = ParameterId(0x0049);
+    pub const PARTICIPANT_GUID: ParameterId = ParameterId(0x0050);
+    pub const GROUP_GUID: ParameterId = ParameterId(0x0052);
+    pub const BUILTIN_ENDPOINT_SET: ParameterId = ParameterId(0x0058);
+    pub const ENDPOINT_GUID: ParameterId = ParameterId(0x005a);
+    pub const TYPE_MAX_SIZE_SERIALIZED: ParameterId = ParameterId(0x0060);
+    pub const ENTITY_NAME: ParameterId = ParameterId(0x0062);
+    pub const KEY_HASH: ParameterId = ParameterId(0x0070);
+    pub const STATUS_INFO: ParameterId = ParameterId(0x0071);
+    pub const DOMAIN_TAG: ParameterId = ParameterId(0x4014);
+}
+
+/// The version of the RTPS protocol.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct ProtocolVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+/// The vendor of an RTPS implementation.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct VendorId(pub [u8; 2]);
+
+/// The globally unique identifier of an RTPS entity.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Guid {
+    /// The prefix shared by the entities of a participant.
+    pub prefix: [u8; 12],
+    pub entity_id: [u8; 4],
+}
+
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, b) in self.prefix.iter().chain(&self.entity_id).enumerate() {
+            if i > 0 && i % 4 == 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// The address of an RTPS endpoint.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct Locator {
+    pub kind: i32,
+    pub port: u32,
+    /// The address, an IPv4 address being in the last 4 bytes.
+    pub address: [u8; 16],
+}
+
+impl Locator {
+    pub const KIND_UDPV4: i32 = 1;
+    pub const KIND_UDPV6: i32 = 2;
+
+    /// Creates a UDP locator with an IPv4 address.
+    pub fn udpv4(address: [u8; 4], port: u32) -> Self {
+        let mut bytes = [0; 16];
+        bytes[12..].copy_from_slice(&address);
+        Locator {
+            kind: Self::KIND_UDPV4,
+            port,
+            address: bytes,
+        }
+    }
+}
+
+/// A span of time in seconds and fractions of a second in units of 2^-32
+/// seconds.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Duration {
+    pub seconds: i32,
+    pub fraction: u32,
+}
+
+impl Duration {
+    pub const ZERO: Duration = Duration {
+        seconds: 0,
+        fraction: 0,
+    };
+    pub const INFINITE: Duration = Duration {
+        seconds: 0x7fff_ffff,
+        fraction: 0xffff_ffff,
+    };
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum DurabilityKind {
+    Volatile,
+    TransientLocal,
+    Transient,
+    Persistent,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum HistoryKind {
+    KeepLast,
+    KeepAll,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum LivelinessKind {
+    Automatic,
+    ManualByParticipant,
+    ManualByTopic,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum OwnershipKind {
+    Shared,
+    Exclusive,
+}
+
+/// The kind of reliability, which, unlike other kinds, is numbered from 1.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ReliabilityKind {
+    BestEffort = 1,
+    Reliable = 2,
+}
+
+impl Serialize for ReliabilityKind {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_u32(*self as u32)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReliabilityKind {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        match u32::deserialize(deserializer)? {
+            1 => Ok(ReliabilityKind::BestEffort),
+            2 => Ok(ReliabilityKind::Reliable),
+            kind => Err(de::Error::invalid_value(
+                de::Unexpected::Unsigned(kind.into()),
+                &"1 or 2",
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct Reliability {
+    pub kind: ReliabilityKind,
+    pub max_blocking_time: Duration,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    pub kind: HistoryKind,
+    pub depth: i32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct Liveliness {
+    pub kind: LivelinessKind,
+    pub lease_duration: Duration,
+}
+
+macro_rules! discovery_parameters {
+    ($($(#[$attr:meta])* $variant:ident($ty:ty) = $pid:ident,)*) => {
+        /// A parameter of discovery data.
+        #[derive(Clone, Debug, PartialEq)]
+        #[non_exhaustive]
+        pub enum DiscoveryParameter {
+            $($(#[$attr])* $variant($ty),)*
+            /// A parameter that is not known, such as one specific to a
+            /// vendor.
+            Other(Parameter),
+        }
+
+        impl DiscoveryParameter {
+            /// Returns the id of the parameter.
+            pub fn id(&self) -> ParameterId {
+                match *self {
+                    $(DiscoveryParameter::$variant(_) => pid::$pid,)*
+                    DiscoveryParameter::Other(ref parameter) => parameter.id,
+                }
+            }
+
+            fn decode(list: &ParameterList, parameter: &Parameter) -> Result<Self> {
+                match parameter.id {
+                    $(pid::$pid => list.decode(parameter).map(DiscoveryParameter::$variant),)*
+                    _ => Ok(DiscoveryParameter::Other(parameter.clone())),
+                }
+            }
+
+            fn encode(&self, list: &mut ParameterList) -> Result<()> {
+                match *self {
+                    $(DiscoveryParameter::$variant(ref value) => list.push(pid::$pid, value),)*
+                    DiscoveryParameter::Other(ref parameter) => list.push_raw(parameter.clone()),
+                }
+            }
+        }
+    };
+}
+
+discovery_parameters! {
+    ParticipantGuid(Guid) = PARTICIPANT_GUID,
+    GroupGuid(Guid) = GROUP_GUID,
+    EndpointGuid(Guid) = ENDPOINT_GUID,
+    ProtocolVersion(ProtocolVersion) = PROTOCOL_VERSION,
+    VendorId(VendorId) = VENDOR_ID,
+    DomainId(u32) = DOMAIN_ID,
+    DomainTag(String) = DOMAIN_TAG,
+    TopicName(String) = TOPIC_NAME,
+    TypeName(String) = TYPE_NAME,
+    EntityName(String) = ENTITY_NAME,
+    UnicastLocator(Locator) = UNICAST_LOCATOR,
+    MulticastLocator(Locator) = MULTICAST_LOCATOR,
+    DefaultUnicastLocator(Locator) = DEFAULT_UNICAST_LOCATOR,
+    DefaultMulticastLocator(Locator) = DEFAULT_MULTICAST_LOCATOR,
+    MetatrafficUnicastLocator(Locator) = METATRAFFIC_UNICAST_LOCATOR,
+    MetatrafficMulticastLocator(Locator) = METATRAFFIC_MULTICAST_LOCATOR,
+    ExpectsInlineQos(bool) = EXPECTS_INLINE_QOS,
+    ParticipantLeaseDuration(Duration) = PARTICIPANT_LEASE_DURATION,
+    ParticipantManualLivelinessCount(i32) = PARTICIPANT_MANUAL_LIVELINESS_COUNT,
+    /// The bit set of the built-in endpoints of a participant.
+    BuiltinEndpointSet(u32) = BUILTIN_ENDPOINT_SET,
+    Durability(DurabilityKind) = DURABILITY,
+    Reliability(Reliability) = RELIABILITY,
+    History(History) = HISTORY,
+    Liveliness(Liveliness) = LIVELINESS,
+    Deadline(Duration) = DEADLINE,
+    LatencyBudget(Duration) = LATENCY_BUDGET,
+    Lifespan(Duration) = LIFESPAN,
+    TimeBasedFilter(Duration) = TIME_BASED_FILTER,
+    Ownership(OwnershipKind) = OWNERSHIP,
+    OwnershipStrength(i32) = OWNERSHIP_STRENGTH,
+    TransportPriority(i32) = TRANSPORT_PRIORITY,
+    Partition(Vec<String>) = PARTITION,
+    UserData(Vec<u8>) = USER_DATA,
+    TopicData(Vec<u8>) = TOPIC_DATA,
+    GroupData(Vec<u8>) = GROUP_DATA,
+    TypeMaxSizeSerialized(u32) = TYPE_MAX_SIZE_SERIALIZED,
+    KeyHash([u8; 16]) = KEY_HASH,
+    StatusInfo([u8; 4]) = STATUS_INFO,
+}
+
+/// Deserializes the parameters of a list of discovery data in order.
+///
+/// Parameters that are not known are returned as `DiscoveryParameter::Other`
+/// unless they must be understood, in which case the list is rejected.
+pub fn parse(list: &ParameterList) -> Result<Vec<DiscoveryParameter>> {
+    list.parameters()
+        .iter()
+        .map(
+            |parameter| match DiscoveryParameter::decode(list, parameter)? {
+                DiscoveryParameter::Other(ref p)
+                    if p.id.must_understand() && !p.id.is_vendor_specific() =>
+                {
+                    Err(Error::Custom(format!(
+                        "unknown parameter {} must be understood",
+                        p.id
+                    )))
+                }
+                parameter => Ok(parameter),
+            },
+        )
+        .collect()
+}
+
+/// Serializes discovery data into a parameter list with the given byte order.
+pub fn to_parameter_list(
+    parameters: &[DiscoveryParameter],
+    little_endian: bool,
+) -> Result<ParameterList> {
+    let mut list = ParameterList::new(little_endian);
+    for parameter in parameters {
+        parameter.encode(&mut list)?;
+    }
+    Ok(list)
+}
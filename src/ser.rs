@@ -5,6 +5,7 @@ use std::{self, io::Write, marker::PhantomData};
 use byteorder::{ByteOrder, WriteBytesExt};
 use serde::ser;
 
+use crate::bulk;
 use crate::error::{Error, Result};
 use crate::size::{
     calc_serialized_data_size, calc_serialized_data_size_bounded, Infinite, SizeLimit,
@@ -14,6 +15,8 @@ use crate::size::{
 pub struct Serializer<W, E> {
     writer: W,
     pos: u64,
+    max_alignment: usize,
+    bulk_element_size: Option<usize>,
     phantom: PhantomData<E>,
 }
 
@@ -23,9 +26,35 @@ where
     E: ByteOrder,
 {
     pub fn new(writer: W) -> Self {
+        Self::with_alignment(writer, 0, 8)
+    }
+
+    /// Creates a serializer that writes "packed" CDR, i.e. without any
+    /// alignment padding.
+    pub fn new_packed(writer: W) -> Self {
+        Self::with_alignment(writer, 0, 1)
+    }
+
+    /// Creates a serializer whose first write is `offset` bytes past the
+    /// alignment origin and whose primitives are aligned to at most
+    /// `max_alignment` bytes.
+    ///
+    /// Classic CDR aligns every primitive to its own size, which corresponds
+    /// to a `max_alignment` of 8. XCDR2 caps the alignment at 4.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_alignment` is not one of 1, 2, 4 or 8.
+    pub fn with_alignment(writer: W, offset: u64, max_alignment: usize) -> Self {
+        assert!(
+            max_alignment.is_power_of_two() && max_alignment <= 8,
+            "alignment must be 1, 2, 4 or 8"
+        );
         Self {
             writer,
-            pos: 0,
+            pos: offset,
+            max_alignment,
+            bulk_element_size: None,
             phantom: PhantomData,
         }
     }
@@ -34,10 +63,49 @@ where
         self.pos += size;
     }
 
-    pub(crate) fn reset_pos(&mut self) {
+    /// Returns the number of bytes written since the alignment origin.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Makes the current position the alignment origin of subsequent writes.
+    pub fn reset_pos(&mut self) {
         self.pos = 0;
     }
 
+    /// Serializes a value as an encapsulation, a sequence of octets whose
+    /// first octet gives the byte order of the value that follows.
+    ///
+    /// The value is written in the byte order of this serializer and aligned
+    /// relative to the start of the encapsulation, as in IOR profiles and the
+    /// parameters of CORBA TypeCodes.
+    pub fn serialize_encapsulated<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let mut buf = Vec::new();
+        write_encapsulated::<_, E>(&mut buf, value, self.max_alignment)?;
+        ser::Serializer::serialize_bytes(self, &buf)
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    ///
+    /// Writing to it directly does not advance the position of the
+    /// serializer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Unwraps this `Serializer`, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
     fn set_pos_of<T>(&mut self) -> Result<()> {
         self.write_padding_of::<T>()?;
         self.add_pos(std::mem::size_of::<T>() as u64);
@@ -45,10 +113,14 @@ where
     }
 
     fn write_padding_of<T>(&mut self) -> Result<()> {
+        self.write_padding(std::mem::size_of::<T>())
+    }
+
+    fn write_padding(&mut self, size: usize) -> Result<()> {
         // Calculate the required padding to align with 1-byte, 2-byte, 4-byte, 8-byte boundaries
         // Instead of using the slow modulo operation '%', the faster bit-masking is used
         const PADDING: [u8; 8] = [0; 8];
-        let alignment = std::mem::size_of::<T>();
+        let alignment = std::cmp::min(size, self.max_alignment);
         let rem_mask = alignment - 1; // mask like 0x0, 0x1, 0x3, 0x7
         match (self.pos as usize) & rem_mask {
             0 => Ok(()),
@@ -61,8 +133,24 @@ where
         }
     }
 
+    pub(crate) fn write_bulk(&mut self, v: &[u8], size: usize) -> Result<()> {
+        let len = v.len() / size;
+        self.write_usize_as_u32(len)?;
+        if len > 0 {
+            self.write_padding(size)?;
+        }
+        self.add_pos(v.len() as u64);
+        if bulk::is_native::<E>() {
+            self.writer.write_all(v).map_err(Into::into)
+        } else {
+            let mut buf = v.to_vec();
+            bulk::swap_bytes(&mut buf, size);
+            self.writer.write_all(&buf).map_err(Into::into)
+        }
+    }
+
     fn write_usize_as_u32(&mut self, v: usize) -> Result<()> {
-        if v > std::u32::MAX as usize {
+        if v > u32::MAX as usize {
             return Err(Error::NumberOutOfRange);
         }
 
@@ -164,6 +252,9 @@ where
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        if let Some(size) = self.bulk_element_size.take() {
+            return self.write_bulk(v, size);
+        }
         let l = v.len();
         self.write_usize_as_u32(l)?;
         self.add_pos(l as u64);
@@ -171,14 +262,14 @@ where
     }
 
     fn serialize_none(self) -> Result<Self::Ok> {
-        Ok(())
+        Err(Error::TypeNotSupported)
     }
 
-    fn serialize_some<T: ?Sized>(self, v: &T) -> Result<Self::Ok>
+    fn serialize_some<T>(self, _v: &T) -> Result<Self::Ok>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
-        v.serialize(self)
+        Err(Error::TypeNotSupported)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok> {
@@ -198,23 +289,25 @@ where
         self.serialize_u32(variant_index)
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(self, _name: &'static str, value: &T) -> Result<Self::Ok>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
+        self.bulk_element_size = bulk::element_size(name);
         value.serialize(self)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         _variant: &'static str,
         value: &T,
     ) -> Result<Self::Ok>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
+        self.serialize_u32(variant_index)?;
         value.serialize(self)
     }
 
@@ -285,9 +378,9 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -307,9 +400,9 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -329,9 +422,9 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -351,9 +444,9 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -373,17 +466,17 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<()>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         key.serialize(&mut *self.ser)
     }
 
     #[inline]
-    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -403,9 +496,9 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_field<T: ?Sized>(&mut self, _key: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -425,9 +518,9 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_field<T: ?Sized>(&mut self, _key: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -439,9 +532,9 @@ where
 }
 
 /// Serializes a serializable object into a `Vec` of bytes.
-pub fn serialize_data<T: ?Sized, S, E>(value: &T, size_limit: S) -> Result<Vec<u8>>
+pub fn serialize_data<T, S, E>(value: &T, size_limit: S) -> Result<Vec<u8>>
 where
-    T: ser::Serialize,
+    T: ?Sized + ser::Serialize,
     S: SizeLimit,
     E: ByteOrder,
 {
@@ -461,10 +554,10 @@ where
 }
 
 /// Serializes an object directly into a `Write`.
-pub fn serialize_data_into<W, T: ?Sized, S, E>(writer: W, value: &T, size_limit: S) -> Result<()>
+pub fn serialize_data_into<W, T, S, E>(writer: W, value: &T, size_limit: S) -> Result<()>
 where
     W: Write,
-    T: ser::Serialize,
+    T: ?Sized + ser::Serialize,
     S: SizeLimit,
     E: ByteOrder,
 {
@@ -476,6 +569,96 @@ where
     ser::Serialize::serialize(value, &mut serializer)
 }
 
+/// Serializes an object into a `Vec`, appending to its contents, in a single
+/// pass.
+///
+/// Unlike `serialize_data`, the size of the object is not computed beforehand;
+/// `buf` grows as needed, and reusing it across calls avoids reallocations.
+/// If an error occurs, `buf` is truncated to its original length.
+pub fn serialize_data_into_vec<T, S, E>(buf: &mut Vec<u8>, value: &T, size_limit: S) -> Result<()>
+where
+    T: ?Sized + ser::Serialize,
+    S: SizeLimit,
+    E: ByteOrder,
+{
+    write_limited(buf, size_limit, |writer| {
+        let mut serializer = Serializer::<_, E>::new(writer);
+        ser::Serialize::serialize(value, &mut serializer)
+    })
+}
+
+/// Serializes an object into the octets of an encapsulation in the byte
+/// order `E`, without the length that precedes them in a sequence.
+pub fn serialize_encapsulated<T, E>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + ser::Serialize,
+    E: ByteOrder,
+{
+    let mut buf = Vec::new();
+    write_encapsulated::<_, E>(&mut buf, value, 8)?;
+    Ok(buf)
+}
+
+fn write_encapsulated<T, E>(buf: &mut Vec<u8>, value: &T, max_alignment: usize) -> Result<()>
+where
+    T: ?Sized + ser::Serialize,
+    E: ByteOrder,
+{
+    // The byte order flag is the alignment origin of the value
+    let little_endian = E::read_u16(&[1, 0]) == 1;
+    buf.push(u8::from(little_endian));
+    let mut serializer = Serializer::<_, E>::with_alignment(buf, 1, max_alignment);
+    value.serialize(&mut serializer)
+}
+
+/// Appends to `buf` whatever `f` writes to the given writer, failing with
+/// `Error::SizeLimit` as soon as more than `size_limit` bytes are written.
+pub(crate) fn write_limited<S, F>(buf: &mut Vec<u8>, size_limit: S, f: F) -> Result<()>
+where
+    S: SizeLimit,
+    F: FnOnce(&mut LimitedWriter<'_, S>) -> Result<()>,
+{
+    let len = buf.len();
+    let mut writer = LimitedWriter {
+        buf,
+        size_limit,
+        exceeded: false,
+    };
+    let result = match f(&mut writer) {
+        Err(_) if writer.exceeded => Err(Error::SizeLimit),
+        result => result,
+    };
+    if result.is_err() {
+        writer.buf.truncate(len);
+    }
+    result
+}
+
+pub(crate) struct LimitedWriter<'a, S> {
+    buf: &'a mut Vec<u8>,
+    size_limit: S,
+    exceeded: bool,
+}
+
+impl<S> Write for LimitedWriter<'_, S>
+where
+    S: SizeLimit,
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.size_limit.add(buf.len() as u64).is_err() {
+            self.exceeded = true;
+            // Reported as `Error::SizeLimit` by `write_limited`
+            return Err(std::io::ErrorKind::Other.into());
+        }
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -587,7 +770,7 @@ mod tests {
 
     #[test]
     fn serialize_float() {
-        let v = std::f32::MIN_POSITIVE;
+        let v = f32::MIN_POSITIVE;
         assert_eq!(
             serialize_data::<_, _, BigEndian>(&v, Infinite).unwrap(),
             vec![0x00, 0x80, 0x00, 0x00]
@@ -600,7 +783,7 @@ mod tests {
 
     #[test]
     fn serialize_double() {
-        let v = std::f64::MIN_POSITIVE;
+        let v = f64::MIN_POSITIVE;
         assert_eq!(
             serialize_data::<_, _, BigEndian>(&v, Infinite).unwrap(),
             vec![0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
@@ -860,7 +1043,7 @@ mod tests {
 
     #[test]
     fn serialize_float_array() {
-        let f = std::f32::MIN_POSITIVE;
+        let f = f32::MIN_POSITIVE;
 
         let v = [f, f + 1., f + 2., f + 3., f + 4.];
         assert_eq!(
@@ -887,7 +1070,7 @@ mod tests {
 
     #[test]
     fn serialize_double_array() {
-        let f = std::f64::MIN_POSITIVE;
+        let f = f64::MIN_POSITIVE;
 
         let v = [f, f + 1., f + 2., f + 3., f + 4.];
         assert_eq!(
@@ -1196,7 +1379,7 @@ mod tests {
 
     #[test]
     fn serialize_float_sequence() {
-        let f = std::f32::MIN_POSITIVE;
+        let f = f32::MIN_POSITIVE;
 
         let v = vec![f, f + 1., f + 2., f + 3., f + 4.];
         assert_eq!(
@@ -1225,7 +1408,7 @@ mod tests {
 
     #[test]
     fn serialize_double_sequence() {
-        let f = std::f64::MIN_POSITIVE;
+        let f = f64::MIN_POSITIVE;
 
         let v = vec![f, f + 1., f + 2., f + 3., f + 4.];
         assert_eq!(
@@ -1315,4 +1498,40 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn serializer_position_and_writer() {
+        let mut serializer = Serializer::<_, BigEndian>::new(Vec::new());
+        ser::Serialize::serialize(&1u8, &mut serializer).unwrap();
+        assert_eq!(serializer.position(), 1);
+        ser::Serialize::serialize(&2u32, &mut serializer).unwrap();
+        assert_eq!(serializer.position(), 8);
+        assert_eq!(serializer.get_ref().len(), 8);
+
+        serializer.reset_pos();
+        assert_eq!(serializer.position(), 0);
+        ser::Serialize::serialize(&1u8, &mut serializer).unwrap();
+        ser::Serialize::serialize(&3u16, &mut serializer).unwrap();
+        assert_eq!(serializer.position(), 4);
+        assert_eq!(
+            serializer.into_inner(),
+            vec![0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x01, 0x00, 0x00, 0x03]
+        );
+    }
+
+    #[test]
+    fn serialize_with_alignment() {
+        let mut serializer = Serializer::<_, BigEndian>::with_alignment(Vec::new(), 2, 4);
+        ser::Serialize::serialize(&(1u32, 1u8, 2u64), &mut serializer).unwrap();
+        assert_eq!(
+            serializer.into_inner(),
+            vec![
+                0x00, 0x00, //
+                0x00, 0x00, 0x00, 0x01, //
+                0x01, //
+                0x00, 0x00, 0x00, //
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,
+            ]
+        );
+    }
 }
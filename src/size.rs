@@ -4,6 +4,7 @@ use std;
 
 use serde::ser;
 
+use crate::bulk;
 use crate::error::{Error, Result};
 
 /// Limits on the number of bytes that can be read or written.
@@ -73,7 +74,9 @@ impl SizeLimit for Counter {
 
 struct SizeChecker<S> {
     counter: S,
-    pos: usize,
+    pos: u64,
+    max_alignment: usize,
+    bulk_element_size: Option<usize>,
 }
 
 impl<S> SizeChecker<S>
@@ -81,7 +84,11 @@ where
     S: SizeLimit,
 {
     fn add_padding_of<T>(&mut self) -> Result<()> {
-        let alignment = std::mem::size_of::<T>();
+        self.add_padding(std::mem::size_of::<T>())
+    }
+
+    fn add_padding(&mut self, size: usize) -> Result<()> {
+        let alignment = std::cmp::min(size, self.max_alignment);
         let rem_mask = alignment - 1; // mask like 0x0, 0x1, 0x3, 0x7
         match (self.pos as usize) & rem_mask {
             0 => Ok(()),
@@ -94,12 +101,12 @@ where
     }
 
     fn add_size(&mut self, size: u64) -> Result<()> {
-        self.pos += size as usize;
+        self.pos += size;
         self.counter.add(size)
     }
 
     fn add_usize_as_u32(&mut self, v: usize) -> Result<()> {
-        if v > std::u32::MAX as usize {
+        if v > u32::MAX as usize {
             return Err(Error::NumberOutOfRange);
         }
 
@@ -175,25 +182,29 @@ where
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok> {
-        self.add_value(0 as u32)?;
+        self.add_value(0u32)?;
         self.add_size(v.len() as u64 + 1) // adds the length 1 of a terminating character
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
-        self.add_value(0 as u32)?;
+        self.add_value(0u32)?;
+        if let Some(size) = self.bulk_element_size.take() {
+            if !v.is_empty() {
+                self.add_padding(size)?;
+            }
+        }
         self.add_size(v.len() as u64)
     }
 
     fn serialize_none(self) -> Result<Self::Ok> {
-        self.add_value(0 as u8)
+        Err(Error::TypeNotSupported)
     }
 
-    fn serialize_some<T: ?Sized>(self, v: &T) -> Result<Self::Ok>
+    fn serialize_some<T>(self, _v: &T) -> Result<Self::Ok>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
-        self.add_value(1 as u8)?;
-        v.serialize(self)
+        Err(Error::TypeNotSupported)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok> {
@@ -213,14 +224,15 @@ where
         self.serialize_u32(variant_index)
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(self, _name: &'static str, value: &T) -> Result<Self::Ok>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
+        self.bulk_element_size = bulk::element_size(name);
         value.serialize(self)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         variant_index: u32,
@@ -228,7 +240,7 @@ where
         value: &T,
     ) -> Result<Self::Ok>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         self.serialize_u32(variant_index)?;
         value.serialize(self)
@@ -300,9 +312,9 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -321,9 +333,9 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -342,9 +354,9 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -363,9 +375,9 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -384,17 +396,17 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<()>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         key.serialize(&mut *self.ser)
     }
 
     #[inline]
-    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -413,9 +425,9 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_field<T: ?Sized>(&mut self, _key: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -434,9 +446,9 @@ where
     type Error = Error;
 
     #[inline]
-    fn serialize_field<T: ?Sized>(&mut self, _key: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<()>
     where
-        T: ser::Serialize,
+        T: ?Sized + ser::Serialize,
     {
         value.serialize(&mut *self.ser)
     }
@@ -448,16 +460,56 @@ where
 }
 
 /// Returns the size that an object would be if serialized.
-pub fn calc_serialized_data_size<T: ?Sized>(value: &T) -> u64
+///
+/// This visits the whole object. For types whose values all have the same
+/// size, `calc_serialized_data_size_fixed` returns it without a value.
+pub fn calc_serialized_data_size<T>(value: &T) -> u64
 where
-    T: ser::Serialize,
+    T: ?Sized + ser::Serialize,
 {
+    calc_serialized_data_size_with_alignment(value, 0, 8)
+}
+
+/// Returns the size that every value of type `T` would be if serialized, or
+/// `None` if the size depends on the value.
+///
+/// Unlike `calc_serialized_data_size`, this does not visit a value; the size
+/// is known at compile time.
+pub fn calc_serialized_data_size_fixed<T>() -> Option<u64>
+where
+    T: ?Sized + CdrFixedSize,
+{
+    T::LAYOUT.fixed_size_at(0).map(|size| size as u64)
+}
+
+/// Returns the size that an object would be if serialized by a serializer
+/// created with `Serializer::with_alignment(writer, offset, max_alignment)`.
+///
+/// A `max_alignment` of 1 gives the size of the packed representation.
+///
+/// # Panics
+///
+/// Panics if `max_alignment` is not one of 1, 2, 4 or 8.
+pub fn calc_serialized_data_size_with_alignment<T>(
+    value: &T,
+    offset: u64,
+    max_alignment: usize,
+) -> u64
+where
+    T: ?Sized + ser::Serialize,
+{
+    assert!(
+        max_alignment.is_power_of_two() && max_alignment <= 8,
+        "alignment must be 1, 2, 4 or 8"
+    );
     let mut checker = SizeChecker {
         counter: Counter {
             total: 0,
             limit: None,
         },
-        pos: 0,
+        pos: offset,
+        max_alignment,
+        bulk_element_size: None,
     };
 
     value.serialize(&mut checker).ok();
@@ -466,13 +518,15 @@ where
 
 /// Given a maximum size limit, check how large an object would be if it were
 /// to be serialized.
-pub fn calc_serialized_data_size_bounded<T: ?Sized>(value: &T, max: u64) -> Result<u64>
+pub fn calc_serialized_data_size_bounded<T>(value: &T, max: u64) -> Result<u64>
 where
-    T: ser::Serialize,
+    T: ?Sized + ser::Serialize,
 {
     let mut checker = SizeChecker {
         counter: Bounded(max),
         pos: 0,
+        max_alignment: 8,
+        bulk_element_size: None,
     };
 
     match value.serialize(&mut checker) {
@@ -480,3 +534,437 @@ where
         Err(e) => Err(e),
     }
 }
+
+const NO_SIZE: usize = usize::MAX;
+const NO_ELEMENT: [usize; 8] = [NO_SIZE; 8];
+
+/// The serialized sizes of a type, used to implement `CdrFixedSize`.
+///
+/// For every position of the first byte relative to an 8-byte boundary, this
+/// records the largest serialized size, including alignment padding, that ends
+/// at each position relative to an 8-byte boundary.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Layout {
+    sizes: [[usize; 8]; 8],
+    bounded: bool,
+    fixed: bool,
+    // For a sequence of elements of a fixed layout, the size of an element
+    // for every position of its first byte relative to an 8-byte boundary
+    element: [usize; 8],
+}
+
+impl Layout {
+    /// The layout of a type that serializes to nothing.
+    pub const EMPTY: Layout = {
+        let mut sizes = [[NO_SIZE; 8]; 8];
+        let mut p = 0;
+        while p < 8 {
+            sizes[p][p] = 0;
+            p += 1;
+        }
+        Layout {
+            sizes,
+            bounded: true,
+            fixed: true,
+            element: NO_ELEMENT,
+        }
+    };
+
+    /// The layout of a type whose serialized size has no upper bound.
+    pub const UNBOUNDED: Layout = Layout {
+        sizes: [[NO_SIZE; 8]; 8],
+        bounded: false,
+        fixed: false,
+        element: NO_ELEMENT,
+    };
+
+    /// The layout of a primitive of 1, 2, 4 or 8 bytes.
+    pub const fn primitive(size: usize) -> Layout {
+        Layout::aligned(size, size)
+    }
+
+    /// The layout of a primitive of `size` bytes that is aligned to
+    /// `alignment` bytes, which is 1, 2, 4 or 8, as with a maximum alignment
+    /// below its size.
+    pub const fn aligned(size: usize, alignment: usize) -> Layout {
+        let mut sizes = [[NO_SIZE; 8]; 8];
+        let mut p = 0;
+        while p < 8 {
+            let padding = (alignment - p % alignment) % alignment;
+            let n = padding + size;
+            sizes[p][(p + n) % 8] = n;
+            p += 1;
+        }
+        Layout {
+            sizes,
+            bounded: true,
+            fixed: true,
+            element: NO_ELEMENT,
+        }
+    }
+
+    /// The layout of a run of between `min` and `max` unaligned bytes.
+    pub const fn bytes(min: usize, max: usize) -> Layout {
+        let mut sizes = [[NO_SIZE; 8]; 8];
+        let mut p = 0;
+        while p < 8 {
+            let mut r = 0;
+            while r < 8 {
+                // The largest n <= max with (p + n) % 8 == r
+                let excess = (p + max + 8 - r) % 8;
+                if max >= excess && max - excess >= min {
+                    sizes[p][r] = max - excess;
+                }
+                r += 1;
+            }
+            p += 1;
+        }
+        Layout {
+            sizes,
+            bounded: true,
+            fixed: min == max,
+            element: NO_ELEMENT,
+        }
+    }
+
+    /// The layout of `self` followed by `next`.
+    pub const fn then(self, next: Layout) -> Layout {
+        let mut sizes = [[NO_SIZE; 8]; 8];
+        let mut p = 0;
+        while p < 8 {
+            let mut q = 0;
+            while q < 8 {
+                let first = self.sizes[p][q];
+                if first != NO_SIZE {
+                    let mut r = 0;
+                    while r < 8 {
+                        let second = next.sizes[q][r];
+                        if second != NO_SIZE {
+                            let n = first.saturating_add(second);
+                            if sizes[p][r] == NO_SIZE || sizes[p][r] < n {
+                                sizes[p][r] = n;
+                            }
+                        }
+                        r += 1;
+                    }
+                }
+                q += 1;
+            }
+            p += 1;
+        }
+        Layout {
+            sizes,
+            bounded: self.bounded && next.bounded,
+            fixed: self.fixed && next.fixed,
+            element: NO_ELEMENT,
+        }
+    }
+
+    /// The layout of a value that is laid out as either `self` or `other`.
+    pub const fn or(self, other: Layout) -> Layout {
+        let mut sizes = self.sizes;
+        let mut p = 0;
+        while p < 8 {
+            let mut q = 0;
+            while q < 8 {
+                let n = other.sizes[p][q];
+                if n != NO_SIZE && (sizes[p][q] == NO_SIZE || sizes[p][q] < n) {
+                    sizes[p][q] = n;
+                }
+                q += 1;
+            }
+            p += 1;
+        }
+        Layout {
+            sizes,
+            bounded: self.bounded && other.bounded,
+            fixed: self.fixed && other.fixed && self.same_sizes(other),
+            element: NO_ELEMENT,
+        }
+    }
+
+    const fn same_sizes(self, other: Layout) -> bool {
+        let mut p = 0;
+        while p < 8 {
+            let mut q = 0;
+            while q < 8 {
+                if self.sizes[p][q] != other.sizes[p][q] {
+                    return false;
+                }
+                q += 1;
+            }
+            p += 1;
+        }
+        true
+    }
+
+    /// The layout of `count` consecutive copies of `self`.
+    pub const fn repeat(self, count: usize) -> Layout {
+        let mut result = Layout::EMPTY;
+        let mut base = self;
+        let mut count = count;
+        while count > 0 {
+            if count & 1 == 1 {
+                result = result.then(base);
+            }
+            base = base.then(base);
+            count >>= 1;
+        }
+        result
+    }
+
+    /// The layout of up to `count` consecutive copies of `self`.
+    pub const fn repeat_up_to(self, count: usize) -> Layout {
+        // Walks the bits of `count` from the most significant one, keeping the
+        // layouts of up to `n` and of exactly `n` copies.
+        let mut up_to = Layout::EMPTY;
+        let mut exactly = Layout::EMPTY;
+        let mut bit = usize::BITS;
+        while bit > 0 {
+            bit -= 1;
+            up_to = up_to.or(exactly.then(up_to));
+            exactly = exactly.then(exactly);
+            if (count >> bit) & 1 == 1 {
+                exactly = exactly.then(self);
+                up_to = up_to.or(exactly);
+            }
+        }
+        Layout {
+            fixed: count == 0,
+            ..up_to
+        }
+    }
+
+    /// The layout `self` of a sequence, a `u32` length followed by that many
+    /// elements laid out as `element`.
+    pub const fn with_elements(self, element: Layout) -> Layout {
+        let mut sizes = NO_ELEMENT;
+        let mut p = 0;
+        while p < 8 {
+            if let Some(size) = element.fixed_size_at(p) {
+                sizes[p] = size;
+            }
+            p += 1;
+        }
+        Layout {
+            element: sizes,
+            ..self
+        }
+    }
+
+    /// Returns whether the serialized size has an upper bound.
+    pub const fn is_bounded(self) -> bool {
+        self.bounded
+    }
+
+    /// Returns whether every value has the same layout.
+    pub const fn is_fixed(self) -> bool {
+        self.fixed
+    }
+
+    /// Returns the serialized size, including leading padding, of a value
+    /// whose first byte is `pos` bytes past the alignment origin, if every
+    /// value has the same layout.
+    pub const fn fixed_size_at(self, pos: usize) -> Option<usize> {
+        if !self.fixed {
+            return None;
+        }
+        let mut q = 0;
+        while q < 8 {
+            if self.sizes[pos % 8][q] != NO_SIZE {
+                return Some(self.sizes[pos % 8][q]);
+            }
+            q += 1;
+        }
+        None
+    }
+
+    /// Returns whether this is the layout of a sequence whose elements have
+    /// a fixed layout.
+    pub const fn has_fixed_elements(self) -> bool {
+        self.element[0] != NO_SIZE
+    }
+
+    /// Returns the serialized size, including leading padding, of `len`
+    /// elements of a sequence whose first element starts `pos` bytes past the
+    /// alignment origin, if the elements have a fixed layout.
+    pub fn elements_size_at(self, pos: usize, len: usize) -> Option<usize> {
+        if !self.has_fixed_elements() {
+            return None;
+        }
+        // The position relative to an 8-byte boundary cycles within 8
+        // elements, so whole cycles are skipped at once.
+        let mut seen: [Option<(usize, usize)>; 8] = [None; 8];
+        let mut total = 0usize;
+        let mut p = pos % 8;
+        let mut remaining = len;
+        let mut step = 0;
+        while remaining > 0 {
+            if let Some((first_step, first_total)) = seen[p] {
+                let cycle_len = step - first_step;
+                let cycle_size = total - first_total;
+                total = total.checked_add((remaining / cycle_len).checked_mul(cycle_size)?)?;
+                remaining %= cycle_len;
+                seen = [None; 8];
+                continue;
+            }
+            seen[p] = Some((step, total));
+            total = total.checked_add(self.element[p])?;
+            p = (p + self.element[p]) % 8;
+            remaining -= 1;
+            step += 1;
+        }
+        Some(total)
+    }
+
+    /// Returns the largest serialized size when the first byte is at the
+    /// alignment origin, or `usize::MAX` if it is unbounded.
+    pub const fn max_size(self) -> usize {
+        if !self.bounded {
+            return usize::MAX;
+        }
+        let mut max = 0;
+        let mut q = 0;
+        while q < 8 {
+            let n = self.sizes[0][q];
+            if n != NO_SIZE && n > max {
+                max = n;
+            }
+            q += 1;
+        }
+        max
+    }
+}
+
+/// Returns the largest size that a value of type `T` would be if serialized,
+/// or `None` if it has no upper bound.
+///
+/// Strings and sequences are bounded by using `BoundedString` and
+/// `BoundedSeq` in place of `String` and `Vec`.
+pub fn max_serialized_data_size<T>() -> Option<u64>
+where
+    T: ?Sized + CdrFixedSize,
+{
+    if T::IS_BOUNDED {
+        Some(T::MAX_SIZE as u64)
+    } else {
+        None
+    }
+}
+
+/// Types whose largest serialized size is known at compile time.
+///
+/// The sizes assume the classic CDR alignment of up to 8 bytes and a value
+/// that starts at the alignment origin, as with `calc_serialized_data_size`.
+/// For types in which every value has the same layout, such as primitives and
+/// structs, arrays and tuples of them, `IS_FIXED` is `true` and `MAX_SIZE` is
+/// the serialized size of every value.
+///
+/// This trait can be derived for structs and enums with the `derive` feature.
+/// The derived implementation assumes that all fields are serialized in
+/// declaration order.
+pub trait CdrFixedSize {
+    #[doc(hidden)]
+    const LAYOUT: Layout;
+
+    /// The layout with the XCDR2 alignment of up to 4 bytes.
+    #[doc(hidden)]
+    const LAYOUT_XCDR2: Layout;
+
+    /// The largest serialized size of a value of the type, or `usize::MAX` if
+    /// `IS_BOUNDED` is `false`.
+    const MAX_SIZE: usize = Self::LAYOUT.max_size();
+
+    /// Whether the serialized size of the type has an upper bound.
+    const IS_BOUNDED: bool = Self::LAYOUT.is_bounded();
+
+    /// Whether every value of the type has the same serialized size.
+    const IS_FIXED: bool = Self::LAYOUT.is_fixed();
+}
+
+macro_rules! impl_fixed_size {
+    ($layout:expr, $layout_xcdr2:expr => $($ty:ty),*) => {
+        $(
+            impl CdrFixedSize for $ty {
+                const LAYOUT: Layout = $layout;
+                const LAYOUT_XCDR2: Layout = $layout_xcdr2;
+            }
+        )*
+    };
+}
+
+impl_fixed_size!(Layout::EMPTY, Layout::EMPTY => ());
+impl_fixed_size!(Layout::primitive(1), Layout::primitive(1) => bool, char, u8, i8);
+impl_fixed_size!(Layout::primitive(2), Layout::primitive(2) => u16, i16);
+impl_fixed_size!(Layout::primitive(4), Layout::primitive(4) => u32, i32, f32);
+impl_fixed_size!(Layout::primitive(8), Layout::aligned(8, 4) => u64, i64, f64);
+impl_fixed_size!(
+    Layout::UNBOUNDED.with_elements(Layout::primitive(1)),
+    Layout::UNBOUNDED.with_elements(Layout::primitive(1)) => str, String
+);
+
+impl<T> CdrFixedSize for [T]
+where
+    T: CdrFixedSize,
+{
+    const LAYOUT: Layout = Layout::UNBOUNDED.with_elements(T::LAYOUT);
+    const LAYOUT_XCDR2: Layout = Layout::UNBOUNDED.with_elements(T::LAYOUT_XCDR2);
+}
+
+impl<T> CdrFixedSize for Vec<T>
+where
+    T: CdrFixedSize,
+{
+    const LAYOUT: Layout = Layout::UNBOUNDED.with_elements(T::LAYOUT);
+    const LAYOUT_XCDR2: Layout = Layout::UNBOUNDED.with_elements(T::LAYOUT_XCDR2);
+}
+
+impl<T, const N: usize> CdrFixedSize for [T; N]
+where
+    T: CdrFixedSize,
+{
+    const LAYOUT: Layout = T::LAYOUT.repeat(N);
+    const LAYOUT_XCDR2: Layout = T::LAYOUT_XCDR2.repeat(N);
+}
+
+impl<T> CdrFixedSize for &T
+where
+    T: ?Sized + CdrFixedSize,
+{
+    const LAYOUT: Layout = T::LAYOUT;
+    const LAYOUT_XCDR2: Layout = T::LAYOUT_XCDR2;
+}
+
+impl<T> CdrFixedSize for Box<T>
+where
+    T: ?Sized + CdrFixedSize,
+{
+    const LAYOUT: Layout = T::LAYOUT;
+    const LAYOUT_XCDR2: Layout = T::LAYOUT_XCDR2;
+}
+
+macro_rules! impl_fixed_size_for_tuple {
+    ($($name:ident)+) => {
+        impl<$($name),+> CdrFixedSize for ($($name,)+)
+        where
+            $($name: CdrFixedSize,)+
+        {
+            const LAYOUT: Layout = Layout::EMPTY$(.then($name::LAYOUT))+;
+            const LAYOUT_XCDR2: Layout = Layout::EMPTY$(.then($name::LAYOUT_XCDR2))+;
+        }
+    };
+}
+
+impl_fixed_size_for_tuple!(T0);
+impl_fixed_size_for_tuple!(T0 T1);
+impl_fixed_size_for_tuple!(T0 T1 T2);
+impl_fixed_size_for_tuple!(T0 T1 T2 T3);
+impl_fixed_size_for_tuple!(T0 T1 T2 T3 T4);
+impl_fixed_size_for_tuple!(T0 T1 T2 T3 T4 T5);
+impl_fixed_size_for_tuple!(T0 T1 T2 T3 T4 T5 T6);
+impl_fixed_size_for_tuple!(T0 T1 T2 T3 T4 T5 T6 T7);
+impl_fixed_size_for_tuple!(T0 T1 T2 T3 T4 T5 T6 T7 T8);
+impl_fixed_size_for_tuple!(T0 T1 T2 T3 T4 T5 T6 T7 T8 T9);
+impl_fixed_size_for_tuple!(T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10);
+impl_fixed_size_for_tuple!(T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11);
@@ -0,0 +1,755 @@
+//! Transcoding between CDR and JSON.
+//!
+//! Data of a Rust type is transcoded with `to_json` and `from_json`, which
+//! follow the representation of the type used by `serde_json`. Data of a type
+//! only known at runtime is transcoded with `dynamic_to_json` and
+//! `dynamic_from_json`, which use its `TypeDescriptor` as follows:
+//!
+//! | Type                            | JSON                                          |
+//! |---------------------------------|-----------------------------------------------|
+//! | `Unit`                          | `null`                                        |
+//! | `Bool`                          | a boolean                                     |
+//! | integers                        | a number                                      |
+//! | `F32`, `F64`                    | a number, or `"NaN"`, `"Infinity"` or `"-Infinity"` |
+//! | `Char`, `String`                | a string                                      |
+//! | sequences and arrays            | an array, including sequences of octets       |
+//! | `Struct`                        | an object with a key per member               |
+//! | `Union` with a `Unit` member    | the name of the member                        |
+//! | `Union`                         | an object with the name of the member as its only key |
+//!
+//! Enums, byte sequences and floats of Rust types are represented the same
+//! way: unit variants as strings, other variants as single-key objects,
+//! `Vec<u8>` as an array of numbers, and non-finite floats as `"NaN"`,
+//! `"Infinity"` or `"-Infinity"`.
+//!
+//! ```rust
+//! use cdr::{CdrLe, Infinite};
+//! use serde_derive::{Deserialize, Serialize};
+//! use serde_json::json;
+//!
+//! #[derive(Deserialize, Serialize)]
+//! enum Command {
+//!     Stop,
+//!     Move { x: f32, y: f32 },
+//! }
+//!
+//! let command = Command::Move { x: 1.0, y: 2.5 };
+//! let encoded = cdr::serialize::<_, _, CdrLe>(&command, Infinite).unwrap();
+//! let json = cdr::transcode::to_json::<Command>(&encoded).unwrap();
+//! assert_eq!(json, json!({ "Move": { "x": 1.0, "y": 2.5 } }));
+//!
+//! let injected =
+//!     cdr::transcode::from_json::<Command, _, CdrLe>(&json!("Stop"), Infinite).unwrap();
+//! let expected = cdr::serialize::<_, _, CdrLe>(&Command::Stop, Infinite).unwrap();
+//! assert_eq!(injected, expected);
+//! ```
+
+use std::convert::TryFrom;
+
+use serde::de::value::{MapDeserializer, SeqDeserializer, StrDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::{ser, Serialize};
+use serde_json::{Map, Number, Value as Json};
+
+use crate::encapsulation::Encapsulation;
+use crate::error::{Error, Result};
+use crate::size::SizeLimit;
+use crate::value::{self, TypeDescriptor, Value};
+
+/// Transcodes a slice of bytes with the encapsulation holding a `T` into
+/// JSON.
+pub fn to_json<T>(bytes: &[u8]) -> Result<Json>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let value: T = crate::deserialize(bytes)?;
+    serde_json::to_value(NonFinite(&value)).map_err(json_error)
+}
+
+/// Transcodes JSON representing a `T` into a `Vec` of bytes with the
+/// encapsulation.
+pub fn from_json<T, S, C>(json: &Json, size_limit: S) -> Result<Vec<u8>>
+where
+    T: DeserializeOwned + Serialize,
+    S: SizeLimit,
+    C: Encapsulation,
+{
+    let value = T::deserialize(JsonDeserializer(json)).map_err(json_error)?;
+    crate::serialize::<_, _, C>(&value, size_limit)
+}
+
+/// Transcodes a slice of bytes with the encapsulation holding a value of the
+/// described type into JSON.
+pub fn dynamic_to_json(bytes: &[u8], ty: &TypeDescriptor) -> Result<Json> {
+    value_to_json(&value::deserialize(bytes, ty)?, ty)
+}
+
+/// Transcodes JSON representing a value of the described type into a `Vec` of
+/// bytes with the encapsulation.
+pub fn dynamic_from_json<S, C>(json: &Json, ty: &TypeDescriptor, size_limit: S) -> Result<Vec<u8>>
+where
+    S: SizeLimit,
+    C: Encapsulation,
+{
+    value::serialize::<_, C>(&value_from_json(json, ty)?, ty, size_limit)
+}
+
+/// Converts a value of the described type into JSON.
+pub fn value_to_json(value: &Value, ty: &TypeDescriptor) -> Result<Json> {
+    use crate::value::TypeDescriptor as T;
+
+    let json = match (value, ty) {
+        (Value::Unit, T::Unit) => Json::Null,
+        (Value::Bool(v), T::Bool) => Json::Bool(*v),
+        (Value::I8(v), T::I8) => Json::from(*v),
+        (Value::I16(v), T::I16) => Json::from(*v),
+        (Value::I32(v), T::I32) => Json::from(*v),
+        (Value::I64(v), T::I64) => Json::from(*v),
+        (Value::U8(v), T::U8) => Json::from(*v),
+        (Value::U16(v), T::U16) => Json::from(*v),
+        (Value::U32(v), T::U32) => Json::from(*v),
+        (Value::U64(v), T::U64) => Json::from(*v),
+        (Value::F32(v), T::F32) => float_to_json(f64::from(*v)),
+        (Value::F64(v), T::F64) => float_to_json(*v),
+        (Value::Char(v), T::Char) => Json::String(v.to_string()),
+        (Value::String(v), T::String) | (Value::String(v), T::BoundedString(_)) => {
+            Json::String(v.clone())
+        }
+        (Value::Sequence(values), T::Sequence(element))
+        | (Value::Sequence(values), T::BoundedSequence(element, _))
+        | (Value::Array(values), T::Array(element, _)) => Json::Array(
+            values
+                .iter()
+                .map(|v| value_to_json(v, element))
+                .collect::<Result<_>>()?,
+        ),
+        (Value::Struct(values), T::Struct(desc)) if values.len() == desc.members.len() => {
+            let mut map = Map::new();
+            for (v, member) in values.iter().zip(&desc.members) {
+                map.insert(member.name.clone(), value_to_json(v, &member.ty)?);
+            }
+            Json::Object(map)
+        }
+        (
+            Value::Union {
+                discriminator,
+                value,
+            },
+            T::Union(desc),
+        ) => match discriminator.label().and_then(|label| desc.member(label)) {
+            Some(member) if member.ty == T::Unit => Json::String(member.name.clone()),
+            Some(member) => {
+                let mut map = Map::new();
+                map.insert(member.name.clone(), value_to_json(value, &member.ty)?);
+                Json::Object(map)
+            }
+            None => return Err(value::nonconforming()),
+        },
+        _ => return Err(value::nonconforming()),
+    };
+    Ok(json)
+}
+
+/// Converts JSON into a value of the described type.
+pub fn value_from_json(json: &Json, ty: &TypeDescriptor) -> Result<Value> {
+    from_json_at(json, ty).map_err(|e| {
+        if e.path.is_empty() {
+            Error::Custom(e.message)
+        } else {
+            Error::Custom(format!("{} at {}", e.message, e.path))
+        }
+    })
+}
+
+/// A mismatch between JSON and a type descriptor.
+struct Mismatch {
+    path: String,
+    message: String,
+}
+
+impl Mismatch {
+    fn new(expected: &str, found: &Json) -> Self {
+        let found = match *found {
+            Json::Null => "null",
+            Json::Bool(_) => "a boolean",
+            Json::Number(_) => "a number",
+            Json::String(_) => "a string",
+            Json::Array(_) => "an array",
+            Json::Object(_) => "an object",
+        };
+        Mismatch {
+            path: String::new(),
+            message: format!("expected {}, found {}", expected, found),
+        }
+    }
+
+    fn within_member(self, name: &str) -> Self {
+        self.within(name.to_string())
+    }
+
+    fn within_index(self, index: usize) -> Self {
+        self.within(format!("[{}]", index))
+    }
+
+    fn within(mut self, segment: String) -> Self {
+        let sep = if self.path.is_empty() || self.path.starts_with('[') {
+            ""
+        } else {
+            "."
+        };
+        self.path = format!("{}{}{}", segment, sep, self.path);
+        self
+    }
+}
+
+fn from_json_at(json: &Json, ty: &TypeDescriptor) -> std::result::Result<Value, Mismatch> {
+    use crate::value::TypeDescriptor as T;
+
+    macro_rules! integer {
+        ($variant:ident, $ty:ident) => {
+            json.as_i64()
+                .map(i128::from)
+                .or_else(|| json.as_u64().map(i128::from))
+                .and_then(|v| $ty::try_from(v).ok())
+                .map(Value::$variant)
+                .ok_or_else(|| Mismatch::new(stringify!($ty), json))
+        };
+    }
+
+    match *ty {
+        T::Unit => match *json {
+            Json::Null => Ok(Value::Unit),
+            _ => Err(Mismatch::new("null", json)),
+        },
+        T::Bool => json
+            .as_bool()
+            .map(Value::Bool)
+            .ok_or_else(|| Mismatch::new("a boolean", json)),
+        T::I8 => integer!(I8, i8),
+        T::I16 => integer!(I16, i16),
+        T::I32 => integer!(I32, i32),
+        T::I64 => integer!(I64, i64),
+        T::U8 => integer!(U8, u8),
+        T::U16 => integer!(U16, u16),
+        T::U32 => integer!(U32, u32),
+        T::U64 => integer!(U64, u64),
+        T::F32 => float_from_json(json).map(|v| Value::F32(v as f32)),
+        T::F64 => float_from_json(json).map(Value::F64),
+        T::Char => {
+            let mut chars = json.as_str().unwrap_or_default().chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(Value::Char(c)),
+                _ => Err(Mismatch::new("a single character", json)),
+            }
+        }
+        T::String => json
+            .as_str()
+            .map(|v| Value::String(v.to_string()))
+            .ok_or_else(|| Mismatch::new("a string", json)),
+        T::BoundedString(bound) => json
+            .as_str()
+            .filter(|v| v.len() <= bound)
+            .map(|v| Value::String(v.to_string()))
+            .ok_or_else(|| Mismatch::new(&format!("a string of at most {} bytes", bound), json)),
+        T::Sequence(ref element) => {
+            elements_from_json(json, element, "an array", |_| true).map(Value::Sequence)
+        }
+        T::BoundedSequence(ref element, bound) => elements_from_json(
+            json,
+            element,
+            &format!("an array of at most {} elements", bound),
+            |len| len <= bound,
+        )
+        .map(Value::Sequence),
+        T::Array(ref element, len) => elements_from_json(
+            json,
+            element,
+            &format!("an array of {} elements", len),
+            |n| n == len,
+        )
+        .map(Value::Array),
+        T::Struct(ref desc) => {
+            let map = json
+                .as_object()
+                .ok_or_else(|| Mismatch::new(&format!("struct {}", desc.name), json))?;
+            if let Some(key) = map
+                .keys()
+                .find(|key| desc.members.iter().all(|m| m.name != **key))
+            {
+                return Err(Mismatch {
+                    path: String::new(),
+                    message: format!("unknown member {} of struct {}", key, desc.name),
+                });
+            }
+            desc.members
+                .iter()
+                .map(|member| match map.get(&member.name) {
+                    Some(v) => {
+                        from_json_at(v, &member.ty).map_err(|e| e.within_member(&member.name))
+                    }
+                    None => Err(Mismatch {
+                        path: String::new(),
+                        message: format!("missing member {} of struct {}", member.name, desc.name),
+                    }),
+                })
+                .collect::<std::result::Result<_, _>>()
+                .map(Value::Struct)
+        }
+        T::Union(ref desc) => {
+            let expected = || Mismatch::new(&format!("union {}", desc.name), json);
+            let (name, value) = match *json {
+                Json::String(ref name) => (name, &Json::Null),
+                Json::Object(ref map) if map.len() == 1 => map.iter().next().unwrap(),
+                _ => return Err(expected()),
+            };
+            let unknown = || Mismatch {
+                path: String::new(),
+                message: format!("unknown member {} of union {}", name, desc.name),
+            };
+            let label = desc.label_of(name).ok_or_else(unknown)?;
+            let discriminator = desc.discriminator_value(label).ok_or_else(unknown)?;
+            let member = desc.member(label).ok_or_else(unknown)?;
+            let value = from_json_at(value, &member.ty).map_err(|e| e.within_member(name))?;
+            Ok(Value::Union {
+                discriminator: Box::new(discriminator),
+                value: Box::new(value),
+            })
+        }
+    }
+}
+
+fn elements_from_json<F>(
+    json: &Json,
+    element: &TypeDescriptor,
+    expected: &str,
+    valid_len: F,
+) -> std::result::Result<Vec<Value>, Mismatch>
+where
+    F: FnOnce(usize) -> bool,
+{
+    match *json {
+        Json::Array(ref values) if valid_len(values.len()) => values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| from_json_at(v, element).map_err(|e| e.within_index(i)))
+            .collect(),
+        _ => Err(Mismatch::new(expected, json)),
+    }
+}
+
+fn float_to_json(v: f64) -> Json {
+    match Number::from_f64(v) {
+        Some(n) => Json::Number(n),
+        None => Json::String(non_finite_name(v).to_string()),
+    }
+}
+
+fn non_finite_name(v: f64) -> &'static str {
+    if v.is_nan() {
+        "NaN"
+    } else if v > 0.0 {
+        "Infinity"
+    } else {
+        "-Infinity"
+    }
+}
+
+fn non_finite_from_name(name: &str) -> Option<f64> {
+    match name {
+        "NaN" => Some(f64::NAN),
+        "Infinity" => Some(f64::INFINITY),
+        "-Infinity" => Some(f64::NEG_INFINITY),
+        _ => None,
+    }
+}
+
+fn float_from_json(json: &Json) -> std::result::Result<f64, Mismatch> {
+    match *json {
+        Json::Number(ref n) => n.as_f64(),
+        Json::String(ref s) => non_finite_from_name(s),
+        _ => None,
+    }
+    .ok_or_else(|| Mismatch::new("a number", json))
+}
+
+fn json_error(e: serde_json::Error) -> Error {
+    Error::Custom(e.to_string())
+}
+
+/// Serializes a value like itself, but with non-finite floats as strings.
+struct NonFinite<'a, T: ?Sized>(&'a T);
+
+impl<'a, T> Serialize for NonFinite<'a, T>
+where
+    T: ?Sized + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        self.0.serialize(NonFiniteSerializer(serializer))
+    }
+}
+
+struct NonFiniteSerializer<S>(S);
+
+macro_rules! forward_serialize {
+    ($($method:ident($($arg:ident: $ty:ty),*),)*) => {
+        $(
+            fn $method(self, $($arg: $ty),*) -> std::result::Result<S::Ok, S::Error> {
+                self.0.$method($($arg),*)
+            }
+        )*
+    };
+}
+
+macro_rules! forward_compound {
+    ($($method:ident($($arg:ident: $ty:ty),*) -> $compound:ident,)*) => {
+        $(
+            fn $method(
+                self,
+                $($arg: $ty),*
+            ) -> std::result::Result<Self::$compound, S::Error> {
+                self.0.$method($($arg),*).map(NonFiniteSerializer)
+            }
+        )*
+    };
+}
+
+impl<S> ser::Serializer for NonFiniteSerializer<S>
+where
+    S: ser::Serializer,
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = NonFiniteSerializer<S::SerializeSeq>;
+    type SerializeTuple = NonFiniteSerializer<S::SerializeTuple>;
+    type SerializeTupleStruct = NonFiniteSerializer<S::SerializeTupleStruct>;
+    type SerializeTupleVariant = NonFiniteSerializer<S::SerializeTupleVariant>;
+    type SerializeMap = NonFiniteSerializer<S::SerializeMap>;
+    type SerializeStruct = NonFiniteSerializer<S::SerializeStruct>;
+    type SerializeStructVariant = NonFiniteSerializer<S::SerializeStructVariant>;
+
+    forward_serialize! {
+        serialize_bool(v: bool),
+        serialize_i8(v: i8),
+        serialize_i16(v: i16),
+        serialize_i32(v: i32),
+        serialize_i64(v: i64),
+        serialize_i128(v: i128),
+        serialize_u8(v: u8),
+        serialize_u16(v: u16),
+        serialize_u32(v: u32),
+        serialize_u64(v: u64),
+        serialize_u128(v: u128),
+        serialize_char(v: char),
+        serialize_str(v: &str),
+        serialize_bytes(v: &[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(name: &'static str),
+        serialize_unit_variant(name: &'static str, index: u32, variant: &'static str),
+    }
+
+    forward_compound! {
+        serialize_seq(len: Option<usize>) -> SerializeSeq,
+        serialize_tuple(len: usize) -> SerializeTuple,
+        serialize_tuple_struct(name: &'static str, len: usize) -> SerializeTupleStruct,
+        serialize_tuple_variant(
+            name: &'static str,
+            index: u32,
+            variant: &'static str,
+            len: usize
+        ) -> SerializeTupleVariant,
+        serialize_map(len: Option<usize>) -> SerializeMap,
+        serialize_struct(name: &'static str, len: usize) -> SerializeStruct,
+        serialize_struct_variant(
+            name: &'static str,
+            index: u32,
+            variant: &'static str,
+            len: usize
+        ) -> SerializeStructVariant,
+    }
+
+    fn serialize_f32(self, v: f32) -> std::result::Result<S::Ok, S::Error> {
+        if v.is_finite() {
+            self.0.serialize_f32(v)
+        } else {
+            self.0.serialize_str(non_finite_name(f64::from(v)))
+        }
+    }
+
+    fn serialize_f64(self, v: f64) -> std::result::Result<S::Ok, S::Error> {
+        if v.is_finite() {
+            self.0.serialize_f64(v)
+        } else {
+            self.0.serialize_str(non_finite_name(v))
+        }
+    }
+
+    fn serialize_some<T>(self, value: &T) -> std::result::Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.serialize_some(&NonFinite(value))
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> std::result::Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.serialize_newtype_struct(name, &NonFinite(value))
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> std::result::Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0
+            .serialize_newtype_variant(name, index, variant, &NonFinite(value))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.0.is_human_readable()
+    }
+}
+
+macro_rules! impl_compound {
+    ($($trait:ident: $method:ident($($key:ident),*),)*) => {
+        $(
+            impl<S> ser::$trait for NonFiniteSerializer<S>
+            where
+                S: ser::$trait,
+            {
+                type Ok = S::Ok;
+                type Error = S::Error;
+
+                fn $method<T>(
+                    &mut self,
+                    $($key: &'static str,)*
+                    value: &T,
+                ) -> std::result::Result<(), S::Error>
+                where
+                    T: ?Sized + Serialize,
+                {
+                    self.0.$method($($key,)* &NonFinite(value))
+                }
+
+                fn end(self) -> std::result::Result<S::Ok, S::Error> {
+                    self.0.end()
+                }
+            }
+        )*
+    };
+}
+
+impl_compound! {
+    SerializeSeq: serialize_element(),
+    SerializeTuple: serialize_element(),
+    SerializeTupleStruct: serialize_field(),
+    SerializeTupleVariant: serialize_field(),
+    SerializeStruct: serialize_field(key),
+    SerializeStructVariant: serialize_field(key),
+}
+
+impl<S> ser::SerializeMap for NonFiniteSerializer<S>
+where
+    S: ser::SerializeMap,
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> std::result::Result<(), S::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.serialize_key(key)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> std::result::Result<(), S::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.serialize_value(&NonFinite(value))
+    }
+
+    fn end(self) -> std::result::Result<S::Ok, S::Error> {
+        self.0.end()
+    }
+}
+
+/// Deserializes from JSON like `serde_json`, but also reads floats from
+/// `"NaN"`, `"Infinity"` and `"-Infinity"`.
+#[derive(Clone, Copy)]
+struct JsonDeserializer<'a>(&'a Json);
+
+impl<'de, 'a> IntoDeserializer<'de, serde_json::Error> for JsonDeserializer<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for JsonDeserializer<'a> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.0 {
+            Json::Null => visitor.visit_unit(),
+            Json::Bool(v) => visitor.visit_bool(v),
+            Json::Number(ref n) => match (n.as_u64(), n.as_i64(), n.as_f64()) {
+                (Some(v), _, _) => visitor.visit_u64(v),
+                (None, Some(v), _) => visitor.visit_i64(v),
+                (None, None, Some(v)) => visitor.visit_f64(v),
+                _ => Err(de::Error::custom("number out of range")),
+            },
+            Json::String(ref v) => visitor.visit_str(v),
+            Json::Array(ref values) => {
+                let mut seq = SeqDeserializer::new(values.iter().map(JsonDeserializer));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Json::Object(ref map) => {
+                let mut map = MapDeserializer::new(
+                    map.iter().map(|(k, v)| (k.as_str(), JsonDeserializer(v))),
+                );
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.0 {
+            Json::String(ref name) => match non_finite_from_name(name) {
+                Some(v) => visitor.visit_f64(v),
+                None => self.deserialize_any(visitor),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.0 {
+            Json::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.0 {
+            Json::String(ref variant) => visitor.visit_enum(JsonVariant(variant, &Json::Null)),
+            Json::Object(ref map) if map.len() == 1 => {
+                let (variant, value) = map.iter().next().unwrap();
+                visitor.visit_enum(JsonVariant(variant, value))
+            }
+            _ => Err(de::Error::invalid_type(
+                de::Unexpected::Other("JSON value"),
+                &"a string or an object with a single key",
+            )),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string bytes
+        byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+/// The variant of an enum and its content.
+struct JsonVariant<'a>(&'a str, &'a Json);
+
+impl<'de, 'a> de::EnumAccess<'de> for JsonVariant<'a> {
+    type Error = serde_json::Error;
+    type Variant = JsonDeserializer<'a>;
+
+    fn variant_seed<V>(self, seed: V) -> std::result::Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant: StrDeserializer<serde_json::Error> = self.0.into_deserializer();
+        Ok((seed.deserialize(variant)?, JsonDeserializer(self.1)))
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for JsonDeserializer<'a> {
+    type Error = serde_json::Error;
+
+    fn unit_variant(self) -> std::result::Result<(), Self::Error> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> std::result::Result<T::Value, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
@@ -0,0 +1,1168 @@
+//! CORBA TypeCodes and the IDL `any` type.
+//!
+//! A `TypeCode` describes an IDL type at runtime and is encoded as specified
+//! in CORBA 3.3, Part 2, 9.3.5.1: a kind, followed by the parameters of the
+//! type either inline or, for constructed types, in an encapsulation. A type
+//! that contains itself refers to its enclosing type with
+//! `TypeCode::Recursive`, which is encoded as an indirection.
+//!
+//! An `Any` is a TypeCode followed by a value of the type it describes.
+//!
+//! ```rust
+//! use cdr::typecode::{Any, StructMember, TypeCode};
+//! use cdr::{CdrBe, Infinite, Value};
+//!
+//! // struct Node { long value; sequence<Node> children; };
+//! let node = TypeCode::Struct {
+//!     id: "IDL:Node:1.0".to_string(),
+//!     name: "Node".to_string(),
+//!     members: vec![
+//!         StructMember::new("value", TypeCode::Long),
+//!         StructMember::new(
+//!             "children",
+//!             TypeCode::Sequence(Box::new(TypeCode::Recursive("IDL:Node:1.0".to_string())), 0),
+//!         ),
+//!     ],
+//! };
+//! let leaf = Value::Struct(vec![Value::I32(2), Value::Sequence(vec![])]);
+//! let tree = Value::Struct(vec![Value::I32(1), Value::Sequence(vec![leaf])]);
+//! let any = Any::new(node, tree).unwrap();
+//!
+//! let encoded = cdr::serialize::<_, _, CdrBe>(&any, Infinite).unwrap();
+//! assert_eq!(cdr::deserialize::<Any>(&encoded).unwrap(), any);
+//! ```
+
+use std::{cmp, convert::TryFrom, fmt};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use serde::de::{self, DeserializeSeed, SeqAccess, Visitor};
+use serde::ser::{self, Serialize};
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::value::{self, TypeDescriptor, Value};
+
+const INDIRECTION: u32 = 0xffff_ffff;
+
+/// The number of constructed types that can be nested in a TypeCode read
+/// from the input.
+const MAX_NESTING: usize = 128;
+
+/// The number of constructed values that can be nested in the value of an
+/// `Any`, which recursive types do not bound otherwise.
+const MAX_VALUE_NESTING: usize = 256;
+
+mod tk {
+    pub const NULL: u32 = 0;
+    pub const VOID: u32 = 1;
+    pub const SHORT: u32 = 2;
+    pub const LONG: u32 = 3;
+    pub const USHORT: u32 = 4;
+    pub const ULONG: u32 = 5;
+    pub const FLOAT: u32 = 6;
+    pub const DOUBLE: u32 = 7;
+    pub const BOOLEAN: u32 = 8;
+    pub const CHAR: u32 = 9;
+    pub const OCTET: u32 = 10;
+    pub const ANY: u32 = 11;
+    pub const TYPE_CODE: u32 = 12;
+    pub const PRINCIPAL: u32 = 13;
+    pub const OBJREF: u32 = 14;
+    pub const STRUCT: u32 = 15;
+    pub const UNION: u32 = 16;
+    pub const ENUM: u32 = 17;
+    pub const STRING: u32 = 18;
+    pub const SEQUENCE: u32 = 19;
+    pub const ARRAY: u32 = 20;
+    pub const ALIAS: u32 = 21;
+    pub const EXCEPT: u32 = 22;
+    pub const LONGLONG: u32 = 23;
+    pub const ULONGLONG: u32 = 24;
+    pub const LONGDOUBLE: u32 = 25;
+    pub const WCHAR: u32 = 26;
+    pub const WSTRING: u32 = 27;
+    pub const FIXED: u32 = 28;
+}
+
+/// The description of an IDL type.
+///
+/// Repository ids have the form `IDL:Module/Type:1.0`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TypeCode {
+    Null,
+    Void,
+    Short,
+    Long,
+    UShort,
+    ULong,
+    Float,
+    Double,
+    Boolean,
+    Char,
+    Octet,
+    Any,
+    TypeCode,
+    Principal,
+    ObjRef {
+        id: String,
+        name: String,
+    },
+    Struct {
+        id: String,
+        name: String,
+        members: Vec<StructMember>,
+    },
+    Union {
+        id: String,
+        name: String,
+        discriminator: Box<TypeCode>,
+        /// The index of the default member, or -1 if there is none.
+        default_index: i32,
+        members: Vec<UnionMember>,
+    },
+    Enum {
+        id: String,
+        name: String,
+        members: Vec<String>,
+    },
+    /// A string of at most the given number of bytes, or of any length if 0.
+    String(u32),
+    /// A sequence of at most the given number of elements, or of any length
+    /// if 0.
+    Sequence(Box<TypeCode>, u32),
+    Array(Box<TypeCode>, u32),
+    Alias {
+        id: String,
+        name: String,
+        content: Box<TypeCode>,
+    },
+    Except {
+        id: String,
+        name: String,
+        members: Vec<StructMember>,
+    },
+    LongLong,
+    ULongLong,
+    LongDouble,
+    WChar,
+    /// A wide string of at most the given number of characters, or of any
+    /// length if 0.
+    WString(u32),
+    Fixed {
+        digits: u16,
+        scale: i16,
+    },
+    /// The struct, union or exception with the repository id that encloses
+    /// this type.
+    Recursive(String),
+}
+
+/// A member of a struct or exception.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct StructMember {
+    pub name: String,
+    pub ty: TypeCode,
+}
+
+impl StructMember {
+    pub fn new<N>(name: N, ty: TypeCode) -> Self
+    where
+        N: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            ty,
+        }
+    }
+}
+
+/// A member of a union.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct UnionMember {
+    /// The value of the discriminator that selects the member, which is
+    /// ignored for the default member.
+    pub label: i64,
+    pub name: String,
+    pub ty: TypeCode,
+}
+
+impl TypeCode {
+    fn kind(&self) -> u32 {
+        match *self {
+            TypeCode::Null => tk::NULL,
+            TypeCode::Void => tk::VOID,
+            TypeCode::Short => tk::SHORT,
+            TypeCode::Long => tk::LONG,
+            TypeCode::UShort => tk::USHORT,
+            TypeCode::ULong => tk::ULONG,
+            TypeCode::Float => tk::FLOAT,
+            TypeCode::Double => tk::DOUBLE,
+            TypeCode::Boolean => tk::BOOLEAN,
+            TypeCode::Char => tk::CHAR,
+            TypeCode::Octet => tk::OCTET,
+            TypeCode::Any => tk::ANY,
+            TypeCode::TypeCode => tk::TYPE_CODE,
+            TypeCode::Principal => tk::PRINCIPAL,
+            TypeCode::ObjRef { .. } => tk::OBJREF,
+            TypeCode::Struct { .. } => tk::STRUCT,
+            TypeCode::Union { .. } => tk::UNION,
+            TypeCode::Enum { .. } => tk::ENUM,
+            TypeCode::String(_) => tk::STRING,
+            TypeCode::Sequence(..) => tk::SEQUENCE,
+            TypeCode::Array(..) => tk::ARRAY,
+            TypeCode::Alias { .. } => tk::ALIAS,
+            TypeCode::Except { .. } => tk::EXCEPT,
+            TypeCode::LongLong => tk::LONGLONG,
+            TypeCode::ULongLong => tk::ULONGLONG,
+            TypeCode::LongDouble => tk::LONGDOUBLE,
+            TypeCode::WChar => tk::WCHAR,
+            TypeCode::WString(_) => tk::WSTRING,
+            TypeCode::Fixed { .. } => tk::FIXED,
+            TypeCode::Recursive(_) => INDIRECTION,
+        }
+    }
+
+    fn from_kind(kind: u32) -> Option<Self> {
+        Some(match kind {
+            tk::NULL => TypeCode::Null,
+            tk::VOID => TypeCode::Void,
+            tk::SHORT => TypeCode::Short,
+            tk::LONG => TypeCode::Long,
+            tk::USHORT => TypeCode::UShort,
+            tk::ULONG => TypeCode::ULong,
+            tk::FLOAT => TypeCode::Float,
+            tk::DOUBLE => TypeCode::Double,
+            tk::BOOLEAN => TypeCode::Boolean,
+            tk::CHAR => TypeCode::Char,
+            tk::OCTET => TypeCode::Octet,
+            tk::ANY => TypeCode::Any,
+            tk::TYPE_CODE => TypeCode::TypeCode,
+            tk::PRINCIPAL => TypeCode::Principal,
+            tk::LONGLONG => TypeCode::LongLong,
+            tk::ULONGLONG => TypeCode::ULongLong,
+            tk::LONGDOUBLE => TypeCode::LongDouble,
+            tk::WCHAR => TypeCode::WChar,
+            _ => return None,
+        })
+    }
+
+    /// Returns whether the parameters are carried in an encapsulation.
+    fn is_complex(&self) -> bool {
+        matches!(
+            *self,
+            TypeCode::ObjRef { .. }
+                | TypeCode::Struct { .. }
+                | TypeCode::Union { .. }
+                | TypeCode::Enum { .. }
+                | TypeCode::Sequence(..)
+                | TypeCode::Array(..)
+                | TypeCode::Alias { .. }
+                | TypeCode::Except { .. }
+        )
+    }
+
+    /// Returns the repository id of a named type.
+    pub fn id(&self) -> Option<&str> {
+        match *self {
+            TypeCode::ObjRef { ref id, .. }
+            | TypeCode::Struct { ref id, .. }
+            | TypeCode::Union { ref id, .. }
+            | TypeCode::Enum { ref id, .. }
+            | TypeCode::Alias { ref id, .. }
+            | TypeCode::Except { ref id, .. } => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Returns the unscoped name of a named type.
+    pub fn name(&self) -> Option<&str> {
+        match *self {
+            TypeCode::ObjRef { ref name, .. }
+            | TypeCode::Struct { ref name, .. }
+            | TypeCode::Union { ref name, .. }
+            | TypeCode::Enum { ref name, .. }
+            | TypeCode::Alias { ref name, .. }
+            | TypeCode::Except { ref name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Returns the type that aliases and recursive references stand for,
+    /// looking up the latter in `root`.
+    fn resolve<'a>(&'a self, root: &'a TypeCode) -> Option<&'a TypeCode> {
+        match *self {
+            TypeCode::Alias { ref content, .. } => content.resolve(root),
+            TypeCode::Recursive(ref id) => root.find(id),
+            ref ty => Some(ty),
+        }
+    }
+
+    /// Finds the struct, union or exception with the repository id.
+    fn find(&self, id: &str) -> Option<&TypeCode> {
+        match *self {
+            TypeCode::Struct {
+                id: ref this,
+                ref members,
+                ..
+            }
+            | TypeCode::Except {
+                id: ref this,
+                ref members,
+                ..
+            } => {
+                if this == id {
+                    return Some(self);
+                }
+                members.iter().find_map(|m| m.ty.find(id))
+            }
+            TypeCode::Union {
+                id: ref this,
+                ref members,
+                ..
+            } => {
+                if this == id {
+                    return Some(self);
+                }
+                members.iter().find_map(|m| m.ty.find(id))
+            }
+            TypeCode::Sequence(ref element, _) | TypeCode::Array(ref element, _) => {
+                element.find(id)
+            }
+            TypeCode::Alias { ref content, .. } => content.find(id),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for TypeCode {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let kind = self.kind();
+        match *self {
+            TypeCode::String(bound) | TypeCode::WString(bound) => {
+                (kind, bound).serialize(serializer)
+            }
+            TypeCode::Fixed { digits, scale } => (kind, digits, scale).serialize(serializer),
+            TypeCode::Recursive(ref id) => Err(ser::Error::custom(unenclosed(id))),
+            ref ty if ty.is_complex() => {
+                // The kind and the length of the encapsulation come first
+                let mut enclosing = Vec::new();
+                let encapsulation =
+                    Encoder::encapsulate(ty, 0, 8, &mut enclosing).map_err(ser::Error::custom)?;
+                (kind, encapsulation).serialize(serializer)
+            }
+            _ => kind.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TypeCode {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple(3, TypeCodeVisitor)
+    }
+}
+
+struct TypeCodeVisitor;
+
+impl<'de> Visitor<'de> for TypeCodeVisitor {
+    type Value = TypeCode;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a TypeCode")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<TypeCode, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        fn next<'de, A, T>(seq: &mut A) -> std::result::Result<T, A::Error>
+        where
+            A: SeqAccess<'de>,
+            T: Deserialize<'de>,
+        {
+            seq.next_element()?
+                .ok_or_else(|| de::Error::custom("truncated TypeCode"))
+        }
+
+        let kind: u32 = next(&mut seq)?;
+        if let Some(ty) = TypeCode::from_kind(kind) {
+            return Ok(ty);
+        }
+        match kind {
+            tk::STRING => Ok(TypeCode::String(next(&mut seq)?)),
+            tk::WSTRING => Ok(TypeCode::WString(next(&mut seq)?)),
+            tk::FIXED => Ok(TypeCode::Fixed {
+                digits: next(&mut seq)?,
+                scale: next(&mut seq)?,
+            }),
+            INDIRECTION => Err(de::Error::custom(
+                "TypeCode indirections are only supported within a TypeCode",
+            )),
+            kind => {
+                let encapsulation: Vec<u8> = next(&mut seq)?;
+                let mut decoder =
+                    Decoder::new(&encapsulation, 8, Vec::new(), 0).map_err(de::Error::custom)?;
+                decoder.parameters(kind, 0).map_err(de::Error::custom)
+            }
+        }
+    }
+}
+
+fn unenclosed(id: &str) -> String {
+    format!("no enclosing type {} for a recursive TypeCode", id)
+}
+
+/// Writes the parameters of a TypeCode into a big-endian encapsulation.
+struct Encoder<'a> {
+    buf: Vec<u8>,
+    /// The position of the encapsulation relative to the outermost TypeCode.
+    origin: usize,
+    /// The repository ids and positions of the enclosing types.
+    enclosing: &'a mut Vec<(String, usize)>,
+}
+
+impl<'a> Encoder<'a> {
+    /// Returns the encapsulated parameters of the TypeCode whose kind is at
+    /// `pos`, with the encapsulation starting at `origin`.
+    fn encapsulate(
+        ty: &TypeCode,
+        pos: usize,
+        origin: usize,
+        enclosing: &mut Vec<(String, usize)>,
+    ) -> Result<Vec<u8>> {
+        let mut encoder = Encoder {
+            buf: vec![0],
+            origin,
+            enclosing,
+        };
+        encoder.parameters(ty, pos)?;
+        Ok(encoder.buf)
+    }
+
+    fn align(&mut self, alignment: usize) {
+        let padding = (alignment - self.buf.len() % alignment) % alignment;
+        self.buf.resize(self.buf.len() + padding, 0);
+    }
+
+    fn write_u16(&mut self, v: u16) {
+        self.align(2);
+        self.buf.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn write_u32(&mut self, v: u32) {
+        self.align(4);
+        self.buf.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn write_u64(&mut self, v: u64) {
+        self.align(8);
+        self.buf.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn write_len(&mut self, len: usize) -> Result<()> {
+        if len > u32::MAX as usize {
+            return Err(Error::SizeLimit);
+        }
+        self.write_u32(len as u32);
+        Ok(())
+    }
+
+    fn write_string(&mut self, v: &str) -> Result<()> {
+        self.write_len(v.len() + 1)?;
+        self.buf.extend_from_slice(v.as_bytes());
+        self.buf.push(0);
+        Ok(())
+    }
+
+    fn write_label(&mut self, discriminator: &TypeCode, label: i64) -> Result<()> {
+        match *discriminator {
+            TypeCode::Boolean | TypeCode::Char | TypeCode::Octet => self.buf.push(label as u8),
+            TypeCode::Short | TypeCode::UShort => self.write_u16(label as u16),
+            TypeCode::Long | TypeCode::ULong | TypeCode::Enum { .. } => {
+                self.write_u32(label as u32)
+            }
+            TypeCode::LongLong | TypeCode::ULongLong => self.write_u64(label as u64),
+            TypeCode::Alias { ref content, .. } => return self.write_label(content, label),
+            _ => return Err(invalid_discriminator()),
+        }
+        Ok(())
+    }
+
+    fn type_code(&mut self, ty: &TypeCode) -> Result<()> {
+        self.align(4);
+        let pos = self.origin + self.buf.len();
+        self.write_u32(ty.kind());
+        match *ty {
+            TypeCode::String(bound) | TypeCode::WString(bound) => self.write_u32(bound),
+            TypeCode::Fixed { digits, scale } => {
+                self.write_u16(digits);
+                self.write_u16(scale as u16);
+            }
+            TypeCode::Recursive(ref id) => {
+                let target = self
+                    .enclosing
+                    .iter()
+                    .rev()
+                    .find(|e| e.0 == *id)
+                    .map(|e| e.1)
+                    .ok_or_else(|| Error::Custom(unenclosed(id)))?;
+                // The offset is relative to the offset itself
+                let offset = target as i64 - (self.origin + self.buf.len()) as i64;
+                self.write_u32(offset as i32 as u32);
+            }
+            ref ty if ty.is_complex() => {
+                let origin = self.origin + self.buf.len() + 4;
+                let encapsulation = Self::encapsulate(ty, pos, origin, self.enclosing)?;
+                self.write_len(encapsulation.len())?;
+                self.buf.extend_from_slice(&encapsulation);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn parameters(&mut self, ty: &TypeCode, pos: usize) -> Result<()> {
+        match *ty {
+            TypeCode::ObjRef { ref id, ref name } => {
+                self.write_string(id)?;
+                self.write_string(name)?;
+            }
+            TypeCode::Struct {
+                ref id,
+                ref name,
+                ref members,
+            }
+            | TypeCode::Except {
+                ref id,
+                ref name,
+                ref members,
+            } => {
+                self.write_string(id)?;
+                self.write_string(name)?;
+                self.write_len(members.len())?;
+                self.enclosing.push((id.clone(), pos));
+                for member in members {
+                    self.write_string(&member.name)?;
+                    self.type_code(&member.ty)?;
+                }
+                self.enclosing.pop();
+            }
+            TypeCode::Union {
+                ref id,
+                ref name,
+                ref discriminator,
+                default_index,
+                ref members,
+            } => {
+                self.write_string(id)?;
+                self.write_string(name)?;
+                self.enclosing.push((id.clone(), pos));
+                self.type_code(discriminator)?;
+                self.write_u32(default_index as u32);
+                self.write_len(members.len())?;
+                for (i, member) in members.iter().enumerate() {
+                    if i as i64 == i64::from(default_index) {
+                        self.buf.push(0);
+                    } else {
+                        self.write_label(discriminator, member.label)?;
+                    }
+                    self.write_string(&member.name)?;
+                    self.type_code(&member.ty)?;
+                }
+                self.enclosing.pop();
+            }
+            TypeCode::Enum {
+                ref id,
+                ref name,
+                ref members,
+            } => {
+                self.write_string(id)?;
+                self.write_string(name)?;
+                self.write_len(members.len())?;
+                for member in members {
+                    self.write_string(member)?;
+                }
+            }
+            TypeCode::Sequence(ref element, bound) | TypeCode::Array(ref element, bound) => {
+                self.type_code(element)?;
+                self.write_u32(bound);
+            }
+            TypeCode::Alias {
+                ref id,
+                ref name,
+                ref content,
+            } => {
+                self.write_string(id)?;
+                self.write_string(name)?;
+                self.type_code(content)?;
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+}
+
+/// Reads the parameters of a TypeCode from an encapsulation.
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// The position of the encapsulation relative to the outermost TypeCode.
+    origin: usize,
+    little_endian: bool,
+    /// The positions and repository ids of the enclosing types.
+    enclosing: Vec<(usize, String)>,
+    nesting: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(
+        data: &'a [u8],
+        origin: usize,
+        enclosing: Vec<(usize, String)>,
+        nesting: usize,
+    ) -> Result<Self> {
+        if nesting >= MAX_NESTING {
+            return Err(Error::Custom("TypeCode is nested too deeply".to_string()));
+        }
+        let byte_order = *data.first().ok_or(Error::Eof)?;
+        Ok(Decoder {
+            data,
+            pos: 1,
+            origin,
+            little_endian: byte_order & 1 != 0,
+            enclosing,
+            nesting,
+        })
+    }
+
+    fn read(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos + len).ok_or(Error::Eof)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_aligned(&mut self, len: usize) -> Result<&'a [u8]> {
+        self.pos += (len - self.pos % len) % len;
+        self.read(len)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        let bytes = self.read_aligned(2)?;
+        Ok(if self.little_endian {
+            LittleEndian::read_u16(bytes)
+        } else {
+            BigEndian::read_u16(bytes)
+        })
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let bytes = self.read_aligned(4)?;
+        Ok(if self.little_endian {
+            LittleEndian::read_u32(bytes)
+        } else {
+            BigEndian::read_u32(bytes)
+        })
+    }
+
+    fn read_u64(&mut self) -> Result<u64> {
+        let bytes = self.read_aligned(8)?;
+        Ok(if self.little_endian {
+            LittleEndian::read_u64(bytes)
+        } else {
+            BigEndian::read_u64(bytes)
+        })
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        let len = self.read_u32()? as usize;
+        let bytes = self.read(len)?;
+        match bytes.split_last() {
+            Some((0, bytes)) => String::from_utf8(bytes.to_vec())
+                .map_err(|e| Error::InvalidUtf8Encoding(e.utf8_error())),
+            _ => Err(Error::Custom(
+                "string in a TypeCode is not null-terminated".to_string(),
+            )),
+        }
+    }
+
+    fn read_label(&mut self, discriminator: &TypeCode) -> Result<i64> {
+        Ok(match *discriminator {
+            TypeCode::Boolean | TypeCode::Char | TypeCode::Octet => i64::from(self.read_u8()?),
+            TypeCode::Short => i64::from(self.read_u16()? as i16),
+            TypeCode::UShort => i64::from(self.read_u16()?),
+            TypeCode::Long => i64::from(self.read_u32()? as i32),
+            TypeCode::ULong | TypeCode::Enum { .. } => i64::from(self.read_u32()?),
+            TypeCode::LongLong | TypeCode::ULongLong => self.read_u64()? as i64,
+            TypeCode::Alias { ref content, .. } => return self.read_label(content),
+            _ => return Err(invalid_discriminator()),
+        })
+    }
+
+    fn type_code(&mut self) -> Result<TypeCode> {
+        self.pos = (self.pos + 3) & !3;
+        let pos = self.origin + self.pos;
+        let kind = self.read_u32()?;
+        if let Some(ty) = TypeCode::from_kind(kind) {
+            return Ok(ty);
+        }
+        match kind {
+            tk::STRING => Ok(TypeCode::String(self.read_u32()?)),
+            tk::WSTRING => Ok(TypeCode::WString(self.read_u32()?)),
+            tk::FIXED => Ok(TypeCode::Fixed {
+                digits: self.read_u16()?,
+                scale: self.read_u16()? as i16,
+            }),
+            INDIRECTION => {
+                let at = self.origin + self.pos;
+                let target = at as i64 + i64::from(self.read_u32()? as i32);
+                self.enclosing
+                    .iter()
+                    .rev()
+                    .find(|e| e.0 as i64 == target)
+                    .map(|e| TypeCode::Recursive(e.1.clone()))
+                    .ok_or_else(|| {
+                        Error::Custom(
+                            "TypeCode indirections are only supported to enclosing types"
+                                .to_string(),
+                        )
+                    })
+            }
+            kind => {
+                let len = self.read_u32()? as usize;
+                let origin = self.origin + self.pos;
+                let data = self.read(len)?;
+                let enclosing = std::mem::take(&mut self.enclosing);
+                let mut decoder = Decoder::new(data, origin, enclosing, self.nesting + 1)?;
+                let ty = decoder.parameters(kind, pos);
+                self.enclosing = decoder.enclosing;
+                ty
+            }
+        }
+    }
+
+    /// Reads the parameters of the TypeCode of the kind at `pos`.
+    fn parameters(&mut self, kind: u32, pos: usize) -> Result<TypeCode> {
+        let ty = match kind {
+            tk::OBJREF => TypeCode::ObjRef {
+                id: self.read_string()?,
+                name: self.read_string()?,
+            },
+            tk::STRUCT | tk::EXCEPT => {
+                let id = self.read_string()?;
+                let name = self.read_string()?;
+                let count = self.read_u32()?;
+                self.enclosing.push((pos, id.clone()));
+                let mut members = Vec::new();
+                for _ in 0..count {
+                    let name = self.read_string()?;
+                    members.push(StructMember::new(name, self.type_code()?));
+                }
+                self.enclosing.pop();
+                if kind == tk::STRUCT {
+                    TypeCode::Struct { id, name, members }
+                } else {
+                    TypeCode::Except { id, name, members }
+                }
+            }
+            tk::UNION => {
+                let id = self.read_string()?;
+                let name = self.read_string()?;
+                self.enclosing.push((pos, id.clone()));
+                let discriminator = self.type_code()?;
+                let default_index = self.read_u32()? as i32;
+                let count = self.read_u32()?;
+                let mut members = Vec::new();
+                for i in 0..count {
+                    let label = if i64::from(i) == i64::from(default_index) {
+                        self.read_u8()?;
+                        0
+                    } else {
+                        self.read_label(&discriminator)?
+                    };
+                    members.push(UnionMember {
+                        label,
+                        name: self.read_string()?,
+                        ty: self.type_code()?,
+                    });
+                }
+                self.enclosing.pop();
+                TypeCode::Union {
+                    id,
+                    name,
+                    discriminator: Box::new(discriminator),
+                    default_index,
+                    members,
+                }
+            }
+            tk::ENUM => {
+                let id = self.read_string()?;
+                let name = self.read_string()?;
+                let count = self.read_u32()?;
+                let mut members = Vec::new();
+                for _ in 0..count {
+                    members.push(self.read_string()?);
+                }
+                TypeCode::Enum { id, name, members }
+            }
+            tk::SEQUENCE => {
+                let element = self.type_code()?;
+                TypeCode::Sequence(Box::new(element), self.read_u32()?)
+            }
+            tk::ARRAY => {
+                let element = self.type_code()?;
+                TypeCode::Array(Box::new(element), self.read_u32()?)
+            }
+            tk::ALIAS => TypeCode::Alias {
+                id: self.read_string()?,
+                name: self.read_string()?,
+                content: Box::new(self.type_code()?),
+            },
+            kind => return Err(Error::Custom(format!("unsupported TypeCode kind {}", kind))),
+        };
+        Ok(ty)
+    }
+}
+
+fn invalid_discriminator() -> Error {
+    Error::Custom("invalid union discriminator type".to_string())
+}
+
+/// A value along with the TypeCode of its type.
+///
+/// The value of a struct or exception is a `Value::Struct`, and that of an
+/// enum is a `Value::Union` whose discriminator is the `Value::U32` index of
+/// the enumerator. A union is a `Value::Union` whose discriminator is the
+/// value of the discriminator of the union, which has to be an integer, a
+/// `boolean`, a `char`, an `octet` or an enum. Aliases are transparent.
+/// Values of the other constructed types, as well as `long double`, `wchar`,
+/// `wstring` and `fixed`, are not supported.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Any {
+    pub type_code: TypeCode,
+    pub value: Value,
+}
+
+impl Any {
+    /// Pairs a value with the TypeCode of its type, checking that it is an
+    /// instance of it.
+    pub fn new(type_code: TypeCode, value: Value) -> Result<Self> {
+        if !conforms(&value, &type_code, &type_code, 0) {
+            return Err(value::nonconforming());
+        }
+        Ok(Any { type_code, value })
+    }
+}
+
+impl Serialize for Any {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        if !conforms(&self.value, &self.type_code, &self.type_code, 0) {
+            return Err(ser::Error::custom(value::nonconforming()));
+        }
+        (&self.type_code, &self.value).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Any {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple(2, AnyVisitor)
+    }
+}
+
+struct AnyVisitor;
+
+impl<'de> Visitor<'de> for AnyVisitor {
+    type Value = Any;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an any")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Any, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let type_code: TypeCode = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let value = seq
+            .next_element_seed(ValueSeed {
+                ty: &type_code,
+                root: &type_code,
+                depth: 0,
+            })?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok(Any { type_code, value })
+    }
+}
+
+/// Returns the descriptor of a type whose values are read and written like
+/// those of the described type.
+fn primitive_descriptor(ty: &TypeCode) -> Option<TypeDescriptor> {
+    Some(match *ty {
+        TypeCode::Null | TypeCode::Void => TypeDescriptor::Unit,
+        TypeCode::Short => TypeDescriptor::I16,
+        TypeCode::Long => TypeDescriptor::I32,
+        TypeCode::UShort => TypeDescriptor::U16,
+        TypeCode::ULong => TypeDescriptor::U32,
+        TypeCode::LongLong => TypeDescriptor::I64,
+        TypeCode::ULongLong => TypeDescriptor::U64,
+        TypeCode::Float => TypeDescriptor::F32,
+        TypeCode::Double => TypeDescriptor::F64,
+        TypeCode::Boolean => TypeDescriptor::Bool,
+        TypeCode::Char => TypeDescriptor::Char,
+        TypeCode::Octet => TypeDescriptor::U8,
+        TypeCode::String(0) => TypeDescriptor::String,
+        TypeCode::String(bound) => TypeDescriptor::BoundedString(bound as usize),
+        _ => return None,
+    })
+}
+
+/// Returns the member of a union selected by the label of the discriminator.
+fn union_member<'a>(
+    discriminator: &TypeCode,
+    default_index: i32,
+    members: &'a [UnionMember],
+    label: i64,
+    root: &TypeCode,
+) -> Option<&'a UnionMember> {
+    match discriminator.resolve(root)? {
+        TypeCode::Short
+        | TypeCode::Long
+        | TypeCode::UShort
+        | TypeCode::ULong
+        | TypeCode::LongLong
+        | TypeCode::ULongLong
+        | TypeCode::Boolean
+        | TypeCode::Char
+        | TypeCode::Octet
+        | TypeCode::Enum { .. } => {}
+        _ => return None,
+    }
+    members
+        .iter()
+        .enumerate()
+        .find(|&(i, m)| i as i64 != i64::from(default_index) && m.label == label)
+        .map(|(_, m)| m)
+        .or_else(|| members.get(usize::try_from(default_index).ok()?))
+}
+
+/// Returns whether a value, nested `depth` constructed values deep, is an
+/// instance of a type.
+fn conforms(value: &Value, ty: &TypeCode, root: &TypeCode, depth: usize) -> bool {
+    let ty = match ty.resolve(root) {
+        Some(ty) => ty,
+        None => return false,
+    };
+    if let Some(descriptor) = primitive_descriptor(ty) {
+        return value.conforms_to(&descriptor);
+    }
+    if depth >= MAX_VALUE_NESTING {
+        return false;
+    }
+    let depth = depth + 1;
+    match (value, ty) {
+        (Value::Struct(values), TypeCode::Struct { members, .. })
+        | (Value::Struct(values), TypeCode::Except { members, .. }) => {
+            values.len() == members.len()
+                && values
+                    .iter()
+                    .zip(members)
+                    .all(|(v, m)| conforms(v, &m.ty, root, depth))
+        }
+        (
+            Value::Union {
+                discriminator,
+                value,
+            },
+            TypeCode::Enum { members, .. },
+        ) => {
+            matches!(**discriminator, Value::U32(i) if (i as usize) < members.len())
+                && **value == Value::Unit
+        }
+        (
+            Value::Union {
+                discriminator: value_discriminator,
+                value,
+            },
+            TypeCode::Union {
+                discriminator,
+                default_index,
+                members,
+                ..
+            },
+        ) => {
+            conforms(value_discriminator, discriminator, root, depth)
+                && value_discriminator
+                    .label()
+                    .and_then(|label| {
+                        union_member(discriminator, *default_index, members, label, root)
+                    })
+                    .is_some_and(|m| conforms(value, &m.ty, root, depth))
+        }
+        (Value::Sequence(values), TypeCode::Sequence(element, bound)) => {
+            (*bound == 0 || values.len() <= *bound as usize)
+                && values.iter().all(|v| conforms(v, element, root, depth))
+        }
+        (Value::Array(values), TypeCode::Array(element, len)) => {
+            values.len() == *len as usize
+                && values.iter().all(|v| conforms(v, element, root, depth))
+        }
+        _ => false,
+    }
+}
+
+/// Deserializes a value of the type described by a TypeCode.
+#[derive(Clone, Copy)]
+struct ValueSeed<'a> {
+    ty: &'a TypeCode,
+    /// The outermost TypeCode, where recursive references are looked up.
+    root: &'a TypeCode,
+    /// The number of constructed values the value is nested in.
+    depth: usize,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ValueSeed<'a> {
+    type Value = Value;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let ty = self
+            .ty
+            .resolve(self.root)
+            .ok_or_else(|| de::Error::custom("recursive TypeCode without an enclosing type"))?;
+        if let Some(descriptor) = primitive_descriptor(ty) {
+            return (&descriptor).deserialize(deserializer);
+        }
+        if self.depth >= MAX_VALUE_NESTING {
+            return Err(de::Error::custom("value is nested too deeply"));
+        }
+        let visitor = ValueSeed { ty, ..self };
+        match *ty {
+            TypeCode::Struct { ref members, .. } | TypeCode::Except { ref members, .. } => {
+                deserializer.deserialize_tuple(members.len(), visitor)
+            }
+            TypeCode::Enum { ref members, .. } => {
+                let discriminator = u32::deserialize(deserializer)?;
+                if discriminator as usize >= members.len() {
+                    return Err(de::Error::invalid_value(
+                        de::Unexpected::Unsigned(u64::from(discriminator)),
+                        &visitor,
+                    ));
+                }
+                Ok(Value::Union {
+                    discriminator: Box::new(Value::U32(discriminator)),
+                    value: Box::new(Value::Unit),
+                })
+            }
+            TypeCode::Union { .. } => deserializer.deserialize_tuple(2, visitor),
+            TypeCode::Sequence(..) => deserializer.deserialize_seq(visitor),
+            TypeCode::Array(_, len) => deserializer.deserialize_tuple(len as usize, visitor),
+            _ => Err(de::Error::custom(format!(
+                "values of TypeCode kind {} are not supported",
+                ty.kind()
+            ))),
+        }
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ValueSeed<'a> {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self.ty.name() {
+            Some(name) => write!(formatter, "a value of type {}", name),
+            None => write!(formatter, "a value of type {:?}", self.ty),
+        }
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let seed = |ty| ValueSeed {
+            ty,
+            root: self.root,
+            depth: self.depth + 1,
+        };
+        match *self.ty {
+            TypeCode::Struct { ref members, .. } | TypeCode::Except { ref members, .. } => {
+                let mut values = Vec::with_capacity(members.len());
+                for member in members {
+                    let value = seq
+                        .next_element_seed(seed(&member.ty))?
+                        .ok_or_else(|| de::Error::invalid_length(values.len(), &self))?;
+                    values.push(value);
+                }
+                Ok(Value::Struct(values))
+            }
+            TypeCode::Union {
+                ref discriminator,
+                default_index,
+                ref members,
+                ..
+            } => {
+                let value = seq
+                    .next_element_seed(seed(discriminator))?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let member = value
+                    .label()
+                    .and_then(|label| {
+                        union_member(discriminator, default_index, members, label, self.root)
+                    })
+                    .ok_or_else(|| {
+                        de::Error::invalid_value(de::Unexpected::Other("discriminator"), &self)
+                    })?;
+                let member_value = seq
+                    .next_element_seed(seed(&member.ty))?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                Ok(Value::Union {
+                    discriminator: Box::new(value),
+                    value: Box::new(member_value),
+                })
+            }
+            TypeCode::Sequence(ref element, bound) => {
+                let hint = seq.size_hint().unwrap_or(0);
+                if bound != 0 && hint > bound as usize {
+                    return Err(de::Error::invalid_length(hint, &self));
+                }
+                // Caps the preallocation, as the length comes from the input
+                let mut values = Vec::with_capacity(cmp::min(hint, 4096));
+                while let Some(value) = seq.next_element_seed(seed(element))? {
+                    values.push(value);
+                    if bound != 0 && values.len() > bound as usize {
+                        return Err(de::Error::invalid_length(values.len(), &self));
+                    }
+                }
+                Ok(Value::Sequence(values))
+            }
+            TypeCode::Array(ref element, len) => {
+                let mut values = Vec::with_capacity(cmp::min(len as usize, 4096));
+                for _ in 0..len {
+                    let value = seq
+                        .next_element_seed(seed(element))?
+                        .ok_or_else(|| de::Error::invalid_length(values.len(), &self))?;
+                    values.push(value);
+                }
+                Ok(Value::Array(values))
+            }
+            _ => Err(de::Error::invalid_type(de::Unexpected::Seq, &self)),
+        }
+    }
+}
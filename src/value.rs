@@ -0,0 +1,730 @@
+//! Values of types that are only known at runtime.
+//!
+//! A `TypeDescriptor` describes the layout of a type, and a `Value` holds an
+//! instance of it. Values are encoded exactly like the statically typed data
+//! they stand for, so a `Value` read with the descriptor of a Rust type can be
+//! written back as that type and vice versa.
+//!
+//! ```rust
+//! use cdr::{CdrLe, Infinite, TypeDescriptor, Value};
+//! use cdr::value::{Member, StructDescriptor};
+//!
+//! let ty = TypeDescriptor::Struct(StructDescriptor {
+//!     name: "Temperature".to_string(),
+//!     members: vec![
+//!         Member::new("sensor", TypeDescriptor::String),
+//!         Member::new("celsius", TypeDescriptor::F64),
+//!     ],
+//! });
+//!
+//! let encoded = cdr::serialize::<_, _, CdrLe>(&("kitchen", 21.5), Infinite).unwrap();
+//! let value = cdr::value::deserialize(&encoded, &ty).unwrap();
+//! assert_eq!(
+//!     value,
+//!     Value::Struct(vec![Value::String("kitchen".to_string()), Value::F64(21.5)])
+//! );
+//! assert_eq!(cdr::value::serialize::<_, CdrLe>(&value, &ty, Infinite).unwrap(), encoded);
+//! ```
+
+use std::{cmp, convert::TryFrom, fmt};
+
+use serde::de::{self, DeserializeSeed, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeSeq, SerializeTuple};
+
+use crate::encapsulation::Encapsulation;
+use crate::error::{Error, Result};
+use crate::size::SizeLimit;
+
+/// The layout of a type.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TypeDescriptor {
+    Unit,
+    Bool,
+    Char,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    String,
+    /// A string of at most the given number of bytes.
+    BoundedString(usize),
+    Sequence(Box<TypeDescriptor>),
+    /// A sequence of at most the given number of elements.
+    BoundedSequence(Box<TypeDescriptor>, usize),
+    /// An array of exactly the given number of elements.
+    Array(Box<TypeDescriptor>, usize),
+    Struct(StructDescriptor),
+    Union(UnionDescriptor),
+}
+
+/// The members of a struct, in order.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct StructDescriptor {
+    pub name: String,
+    pub members: Vec<Member>,
+}
+
+/// The members of a union, selected by the value of its discriminator.
+///
+/// The discriminator is a `Bool`, a `Char`, an integer, or an enumeration,
+/// which is a union whose members are all of type `TypeDescriptor::Unit` and
+/// whose labels are the indices of its enumerators. `from_variants` describes
+/// the encoding of a Rust enum.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct UnionDescriptor {
+    pub name: String,
+    pub discriminator: Box<TypeDescriptor>,
+    pub cases: Vec<Case>,
+    /// The member selected by the values of the discriminator that no case
+    /// lists.
+    pub default: Option<Box<Member>>,
+}
+
+/// A member of a union along with the values of the discriminator that
+/// select it.
+///
+/// The labels of `Bool` discriminators are 0 and 1, those of `Char`
+/// discriminators their code points and those of enumerations the indices of
+/// their enumerators.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Case {
+    pub labels: Vec<i64>,
+    pub member: Member,
+}
+
+impl UnionDescriptor {
+    /// Describes a union with a `U32` discriminator whose value is the index
+    /// of the member, as for a Rust enum.
+    pub fn from_variants<N>(name: N, variants: Vec<Member>) -> Self
+    where
+        N: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            discriminator: Box::new(TypeDescriptor::U32),
+            cases: variants
+                .into_iter()
+                .enumerate()
+                .map(|(i, member)| Case {
+                    labels: vec![i as i64],
+                    member,
+                })
+                .collect(),
+            default: None,
+        }
+    }
+
+    /// Returns the member selected by a value of the discriminator.
+    pub fn member(&self, label: i64) -> Option<&Member> {
+        self.cases
+            .iter()
+            .find(|case| case.labels.contains(&label))
+            .map(|case| &case.member)
+            .or(self.default.as_deref())
+    }
+
+    /// Returns a value of the discriminator that selects the member with the
+    /// given name, which is the first label of its case or, for the default
+    /// member, the smallest value that no case lists.
+    pub fn label_of(&self, name: &str) -> Option<i64> {
+        if let Some(case) = self.cases.iter().find(|case| case.member.name == name) {
+            return case.labels.first().copied();
+        }
+        match self.default {
+            Some(ref member) if member.name == name => {
+                (0..).find(|label| self.cases.iter().all(|case| !case.labels.contains(label)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns whether the union is an enumeration, whose members are all of
+    /// type `TypeDescriptor::Unit`.
+    pub fn is_enumeration(&self) -> bool {
+        self.cases
+            .iter()
+            .map(|case| &case.member)
+            .chain(self.default.as_deref())
+            .all(|member| member.ty == TypeDescriptor::Unit)
+    }
+
+    /// Returns the value of the discriminator with the given label, or `None`
+    /// if the discriminator cannot have it.
+    pub fn discriminator_value(&self, label: i64) -> Option<Value> {
+        use self::TypeDescriptor as T;
+
+        Some(match *self.discriminator {
+            T::Bool => Value::Bool(match label {
+                0 => false,
+                1 => true,
+                _ => return None,
+            }),
+            T::Char => Value::Char(char::from_u32(u32::try_from(label).ok()?)?),
+            T::I8 => Value::I8(i8::try_from(label).ok()?),
+            T::I16 => Value::I16(i16::try_from(label).ok()?),
+            T::I32 => Value::I32(i32::try_from(label).ok()?),
+            T::I64 => Value::I64(label),
+            T::U8 => Value::U8(u8::try_from(label).ok()?),
+            T::U16 => Value::U16(u16::try_from(label).ok()?),
+            T::U32 => Value::U32(u32::try_from(label).ok()?),
+            T::U64 => Value::U64(label as u64),
+            T::Union(ref desc) if desc.is_enumeration() => {
+                desc.member(label)?;
+                Value::Union {
+                    discriminator: Box::new(desc.discriminator_value(label)?),
+                    value: Box::new(Value::Unit),
+                }
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// A named member of a struct or union.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub ty: TypeDescriptor,
+}
+
+impl Member {
+    pub fn new<N>(name: N, ty: TypeDescriptor) -> Self
+    where
+        N: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            ty,
+        }
+    }
+}
+
+/// Types whose layout is known at compile time, which lets their data be
+/// handled through a `TypeDescriptor` without building values of them.
+///
+/// This trait can be derived for structs and enums with the `derive` feature.
+/// The derived implementation assumes that all fields are serialized in
+/// declaration order, and recursive types cannot implement it, as a
+/// descriptor is a tree.
+pub trait Described {
+    /// Returns the descriptor of the type.
+    fn descriptor() -> TypeDescriptor;
+}
+
+macro_rules! impl_described {
+    ($($ty:ty => $descriptor:ident),*) => {
+        $(
+            impl Described for $ty {
+                fn descriptor() -> TypeDescriptor {
+                    TypeDescriptor::$descriptor
+                }
+            }
+        )*
+    };
+}
+
+impl_described!(
+    () => Unit, bool => Bool, char => Char,
+    i8 => I8, i16 => I16, i32 => I32, i64 => I64,
+    u8 => U8, u16 => U16, u32 => U32, u64 => U64,
+    f32 => F32, f64 => F64,
+    str => String, String => String
+);
+
+impl<T> Described for [T]
+where
+    T: Described,
+{
+    fn descriptor() -> TypeDescriptor {
+        TypeDescriptor::Sequence(Box::new(T::descriptor()))
+    }
+}
+
+impl<T> Described for Vec<T>
+where
+    T: Described,
+{
+    fn descriptor() -> TypeDescriptor {
+        TypeDescriptor::Sequence(Box::new(T::descriptor()))
+    }
+}
+
+impl<T, const N: usize> Described for [T; N]
+where
+    T: Described,
+{
+    fn descriptor() -> TypeDescriptor {
+        TypeDescriptor::Array(Box::new(T::descriptor()), N)
+    }
+}
+
+impl<T> Described for &T
+where
+    T: ?Sized + Described,
+{
+    fn descriptor() -> TypeDescriptor {
+        T::descriptor()
+    }
+}
+
+impl<T> Described for Box<T>
+where
+    T: ?Sized + Described,
+{
+    fn descriptor() -> TypeDescriptor {
+        T::descriptor()
+    }
+}
+
+macro_rules! impl_described_for_tuple {
+    ($($name:ident $index:tt)+) => {
+        impl<$($name),+> Described for ($($name,)+)
+        where
+            $($name: Described,)+
+        {
+            fn descriptor() -> TypeDescriptor {
+                TypeDescriptor::Struct(StructDescriptor {
+                    name: String::new(),
+                    members: vec![$(Member::new(stringify!($index), $name::descriptor())),+],
+                })
+            }
+        }
+    };
+}
+
+impl_described_for_tuple!(T0 0);
+impl_described_for_tuple!(T0 0 T1 1);
+impl_described_for_tuple!(T0 0 T1 1 T2 2);
+impl_described_for_tuple!(T0 0 T1 1 T2 2 T3 3);
+impl_described_for_tuple!(T0 0 T1 1 T2 2 T3 3 T4 4);
+impl_described_for_tuple!(T0 0 T1 1 T2 2 T3 3 T4 4 T5 5);
+impl_described_for_tuple!(T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6);
+impl_described_for_tuple!(T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7);
+impl_described_for_tuple!(T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8);
+impl_described_for_tuple!(T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9);
+impl_described_for_tuple!(T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9 T10 10);
+impl_described_for_tuple!(T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9 T10 10 T11 11);
+
+/// An instance of a type described by a `TypeDescriptor`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Unit,
+    Bool(bool),
+    Char(char),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    /// A value of a `String` or `BoundedString`.
+    String(String),
+    /// A value of a `Sequence` or `BoundedSequence`.
+    Sequence(Vec<Value>),
+    Array(Vec<Value>),
+    /// The values of the members of a struct, in order.
+    Struct(Vec<Value>),
+    /// The value of the discriminator of a union and that of the member it
+    /// selects.
+    Union {
+        discriminator: Box<Value>,
+        value: Box<Value>,
+    },
+}
+
+impl Value {
+    /// Returns the label of a value of a union discriminator: the value of a
+    /// `Bool`, `Char` or integer, or the discriminator of an enumerator.
+    pub fn label(&self) -> Option<i64> {
+        Some(match *self {
+            Value::Bool(v) => i64::from(v),
+            Value::Char(v) => i64::from(u32::from(v)),
+            Value::I8(v) => i64::from(v),
+            Value::I16(v) => i64::from(v),
+            Value::I32(v) => i64::from(v),
+            Value::I64(v) => v,
+            Value::U8(v) => i64::from(v),
+            Value::U16(v) => i64::from(v),
+            Value::U32(v) => i64::from(v),
+            Value::U64(v) => v as i64,
+            Value::Union {
+                ref discriminator,
+                ref value,
+            } if **value == Value::Unit => discriminator.label()?,
+            _ => return None,
+        })
+    }
+
+    /// Returns whether the value is an instance of the described type.
+    pub fn conforms_to(&self, ty: &TypeDescriptor) -> bool {
+        use self::TypeDescriptor as T;
+
+        match (self, ty) {
+            (Value::Unit, T::Unit)
+            | (Value::Bool(_), T::Bool)
+            | (Value::Char(_), T::Char)
+            | (Value::I8(_), T::I8)
+            | (Value::I16(_), T::I16)
+            | (Value::I32(_), T::I32)
+            | (Value::I64(_), T::I64)
+            | (Value::U8(_), T::U8)
+            | (Value::U16(_), T::U16)
+            | (Value::U32(_), T::U32)
+            | (Value::U64(_), T::U64)
+            | (Value::F32(_), T::F32)
+            | (Value::F64(_), T::F64)
+            | (Value::String(_), T::String) => true,
+            (Value::String(s), T::BoundedString(bound)) => s.len() <= *bound,
+            (Value::Sequence(values), T::Sequence(element)) => {
+                values.iter().all(|v| v.conforms_to(element))
+            }
+            (Value::Sequence(values), T::BoundedSequence(element, bound)) => {
+                values.len() <= *bound && values.iter().all(|v| v.conforms_to(element))
+            }
+            (Value::Array(values), T::Array(element, len)) => {
+                values.len() == *len && values.iter().all(|v| v.conforms_to(element))
+            }
+            (Value::Struct(values), T::Struct(desc)) => {
+                values.len() == desc.members.len()
+                    && values
+                        .iter()
+                        .zip(&desc.members)
+                        .all(|(v, m)| v.conforms_to(&m.ty))
+            }
+            (
+                Value::Union {
+                    discriminator,
+                    value,
+                },
+                T::Union(desc),
+            ) => {
+                discriminator.conforms_to(&desc.discriminator)
+                    && discriminator
+                        .label()
+                        .and_then(|label| desc.member(label))
+                        .is_some_and(|m| value.conforms_to(&m.ty))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        match *self {
+            Value::Unit => serializer.serialize_unit(),
+            Value::Bool(v) => serializer.serialize_bool(v),
+            Value::Char(v) => serializer.serialize_char(v),
+            Value::I8(v) => serializer.serialize_i8(v),
+            Value::I16(v) => serializer.serialize_i16(v),
+            Value::I32(v) => serializer.serialize_i32(v),
+            Value::I64(v) => serializer.serialize_i64(v),
+            Value::U8(v) => serializer.serialize_u8(v),
+            Value::U16(v) => serializer.serialize_u16(v),
+            Value::U32(v) => serializer.serialize_u32(v),
+            Value::U64(v) => serializer.serialize_u64(v),
+            Value::F32(v) => serializer.serialize_f32(v),
+            Value::F64(v) => serializer.serialize_f64(v),
+            Value::String(ref v) => serializer.serialize_str(v),
+            Value::Sequence(ref values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for v in values {
+                    seq.serialize_element(v)?;
+                }
+                seq.end()
+            }
+            Value::Array(ref values) | Value::Struct(ref values) => {
+                let mut tuple = serializer.serialize_tuple(values.len())?;
+                for v in values {
+                    tuple.serialize_element(v)?;
+                }
+                tuple.end()
+            }
+            Value::Union {
+                ref discriminator,
+                ref value,
+            } => {
+                let mut tuple = serializer.serialize_tuple(2)?;
+                tuple.serialize_element(discriminator)?;
+                tuple.serialize_element(value)?;
+                tuple.end()
+            }
+        }
+    }
+}
+
+/// Deserializes a value of the described type.
+impl<'de> DeserializeSeed<'de> for &TypeDescriptor {
+    type Value = Value;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        use self::TypeDescriptor as T;
+
+        let visitor = ValueVisitor(self);
+        match *self {
+            T::Unit => deserializer.deserialize_unit(visitor),
+            T::Bool => deserializer.deserialize_bool(visitor),
+            T::Char => deserializer.deserialize_char(visitor),
+            T::I8 => deserializer.deserialize_i8(visitor),
+            T::I16 => deserializer.deserialize_i16(visitor),
+            T::I32 => deserializer.deserialize_i32(visitor),
+            T::I64 => deserializer.deserialize_i64(visitor),
+            T::U8 => deserializer.deserialize_u8(visitor),
+            T::U16 => deserializer.deserialize_u16(visitor),
+            T::U32 => deserializer.deserialize_u32(visitor),
+            T::U64 => deserializer.deserialize_u64(visitor),
+            T::F32 => deserializer.deserialize_f32(visitor),
+            T::F64 => deserializer.deserialize_f64(visitor),
+            T::String | T::BoundedString(_) => deserializer.deserialize_string(visitor),
+            T::Sequence(_) | T::BoundedSequence(..) => deserializer.deserialize_seq(visitor),
+            T::Array(_, len) => deserializer.deserialize_tuple(len, visitor),
+            T::Struct(ref desc) => deserializer.deserialize_tuple(desc.members.len(), visitor),
+            T::Union(_) => deserializer.deserialize_tuple(2, visitor),
+        }
+    }
+}
+
+struct ValueVisitor<'a>(&'a TypeDescriptor);
+
+impl<'a> ValueVisitor<'a> {
+    fn integer<E>(self, v: i128) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        use self::TypeDescriptor as T;
+
+        let value = match *self.0 {
+            T::I8 => i8::try_from(v).ok().map(Value::I8),
+            T::I16 => i16::try_from(v).ok().map(Value::I16),
+            T::I32 => i32::try_from(v).ok().map(Value::I32),
+            T::I64 => i64::try_from(v).ok().map(Value::I64),
+            T::U8 => u8::try_from(v).ok().map(Value::U8),
+            T::U16 => u16::try_from(v).ok().map(Value::U16),
+            T::U32 => u32::try_from(v).ok().map(Value::U32),
+            T::U64 => u64::try_from(v).ok().map(Value::U64),
+            _ => return Err(E::invalid_type(de::Unexpected::Other("integer"), &self)),
+        };
+        value.ok_or_else(|| E::invalid_value(de::Unexpected::Other("integer"), &self))
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ValueVisitor<'a> {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        use self::TypeDescriptor as T;
+
+        match *self.0 {
+            T::BoundedString(bound) => write!(formatter, "a string of at most {} bytes", bound),
+            T::BoundedSequence(_, bound) => {
+                write!(formatter, "a sequence of at most {} elements", bound)
+            }
+            T::Array(_, len) => write!(formatter, "an array of {} elements", len),
+            T::Struct(ref desc) => write!(formatter, "struct {}", desc.name),
+            T::Union(ref desc) => write!(formatter, "union {}", desc.name),
+            ref ty => write!(formatter, "a value of type {:?}", ty),
+        }
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        match *self.0 {
+            TypeDescriptor::Bool => Ok(Value::Bool(v)),
+            _ => Err(E::invalid_type(de::Unexpected::Bool(v), &self)),
+        }
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.integer(i128::from(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.integer(i128::from(v))
+    }
+
+    fn visit_f32<E>(self, v: f32) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        match *self.0 {
+            TypeDescriptor::F32 => Ok(Value::F32(v)),
+            _ => self.visit_f64(f64::from(v)),
+        }
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        match *self.0 {
+            TypeDescriptor::F32 => Ok(Value::F32(v as f32)),
+            TypeDescriptor::F64 => Ok(Value::F64(v)),
+            _ => Err(E::invalid_type(de::Unexpected::Float(v), &self)),
+        }
+    }
+
+    fn visit_char<E>(self, v: char) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        match *self.0 {
+            TypeDescriptor::Char => Ok(Value::Char(v)),
+            _ => self.visit_str(v.encode_utf8(&mut [0; 4])),
+        }
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_string<E>(self, v: String) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        match *self.0 {
+            TypeDescriptor::String => Ok(Value::String(v)),
+            TypeDescriptor::BoundedString(bound) if v.len() <= bound => Ok(Value::String(v)),
+            TypeDescriptor::BoundedString(_) => Err(E::invalid_length(v.len(), &self)),
+            TypeDescriptor::Char => {
+                let mut chars = v.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(Value::Char(c)),
+                    _ => Err(E::invalid_value(de::Unexpected::Str(&v), &self)),
+                }
+            }
+            _ => Err(E::invalid_type(de::Unexpected::Str(&v), &self)),
+        }
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        match *self.0 {
+            TypeDescriptor::Unit => Ok(Value::Unit),
+            _ => Err(E::invalid_type(de::Unexpected::Unit, &self)),
+        }
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        use self::TypeDescriptor as T;
+
+        if let T::Union(ref desc) = *self.0 {
+            return visit_union(desc, seq, &self);
+        }
+        let (len, bound) = match *self.0 {
+            T::Sequence(_) => (None, usize::MAX),
+            T::BoundedSequence(_, bound) => (None, bound),
+            T::Array(_, len) => (Some(len), len),
+            T::Struct(ref desc) => (Some(desc.members.len()), desc.members.len()),
+            _ => return Err(de::Error::invalid_type(de::Unexpected::Seq, &self)),
+        };
+        let hint = seq.size_hint().unwrap_or(0);
+        if hint > bound {
+            return Err(de::Error::invalid_length(hint, &self));
+        }
+
+        // Caps the preallocation, as the length comes from the input
+        let mut values = Vec::with_capacity(cmp::min(hint, 4096));
+        while values.len() < bound {
+            let ty = match *self.0 {
+                T::Sequence(ref element)
+                | T::BoundedSequence(ref element, _)
+                | T::Array(ref element, _) => &**element,
+                T::Struct(ref desc) => &desc.members[values.len()].ty,
+                _ => unreachable!(),
+            };
+            match seq.next_element_seed(ty)? {
+                Some(value) => values.push(value),
+                None => break,
+            }
+        }
+        if len.is_some_and(|len| values.len() != len) {
+            return Err(de::Error::invalid_length(values.len(), &self));
+        }
+
+        Ok(match *self.0 {
+            T::Array(..) => Value::Array(values),
+            T::Struct(_) => Value::Struct(values),
+            _ => Value::Sequence(values),
+        })
+    }
+}
+
+fn visit_union<'de, A, V>(
+    desc: &UnionDescriptor,
+    mut seq: A,
+    visitor: &V,
+) -> std::result::Result<Value, A::Error>
+where
+    A: SeqAccess<'de>,
+    V: Visitor<'de>,
+{
+    let discriminator = seq
+        .next_element_seed(&*desc.discriminator)?
+        .ok_or_else(|| de::Error::invalid_length(0, visitor))?;
+    let member = discriminator
+        .label()
+        .and_then(|label| desc.member(label))
+        .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Other("discriminator"), visitor))?;
+    let value = seq
+        .next_element_seed(&member.ty)?
+        .ok_or_else(|| de::Error::invalid_length(1, visitor))?;
+    Ok(Value::Union {
+        discriminator: Box::new(discriminator),
+        value: Box::new(value),
+    })
+}
+
+/// Deserializes a slice of bytes with the encapsulation into a value of the
+/// described type.
+pub fn deserialize(bytes: &[u8], ty: &TypeDescriptor) -> Result<Value> {
+    crate::deserialize_seed(bytes, ty)
+}
+
+/// Serializes a value of the described type into a `Vec` of bytes with the
+/// encapsulation.
+pub fn serialize<S, C>(value: &Value, ty: &TypeDescriptor, size_limit: S) -> Result<Vec<u8>>
+where
+    S: SizeLimit,
+    C: Encapsulation,
+{
+    if !value.conforms_to(ty) {
+        return Err(nonconforming());
+    }
+    crate::serialize::<_, _, C>(value, size_limit)
+}
+
+pub(crate) fn nonconforming() -> Error {
+    Error::Custom("value does not conform to the type descriptor".to_string())
+}
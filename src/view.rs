@@ -0,0 +1,193 @@
+//! Reading individual members of encapsulated CDR data without decoding the
+//! whole of it.
+//!
+//! A `Cursor` walks the members of a `View` in order, computing their offsets
+//! from the same alignment rules as `Deserializer`. Members can be decoded,
+//! borrowed from the buffer in the case of strings and octet sequences, or
+//! skipped. Skipping a member whose type has a fixed layout costs nothing, and
+//! skipping a string or a sequence of such members only reads its length.
+//!
+//! ```rust
+//! use cdr::{CdrLe, Infinite};
+//! use cdr::view::View;
+//! use serde_derive::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct Scan {
+//!     frame_id: String,
+//!     stamp: (i32, u32),
+//!     ranges: Vec<f32>,
+//! }
+//!
+//! let scan = Scan {
+//!     frame_id: "laser".to_string(),
+//!     stamp: (10, 500),
+//!     ranges: vec![1.0; 1000],
+//! };
+//! let encoded = cdr::serialize::<_, _, CdrLe>(&scan, Infinite).unwrap();
+//!
+//! let view = View::new(&encoded).unwrap();
+//! let mut cursor = view.cursor();
+//! assert_eq!(cursor.read_str().unwrap(), "laser");
+//! assert_eq!(cursor.read::<(i32, u32)>().unwrap(), (10, 500));
+//! ```
+
+use std::str;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use serde::de::DeserializeOwned;
+
+use crate::de::Deserializer;
+use crate::encapsulation::{EncapsulationKind, ENCAPSULATION_HEADER_SIZE};
+use crate::error::{Error, Result};
+use crate::size::{CdrFixedSize, Infinite};
+
+/// Encapsulated CDR data whose members are read on demand.
+#[derive(Clone, Copy, Debug)]
+pub struct View<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+    max_alignment: usize,
+}
+
+impl<'a> View<'a> {
+    /// Creates a view of a slice of bytes that starts with an encapsulation
+    /// header.
+    pub fn new(bytes: &'a [u8]) -> Result<Self> {
+        let kind = EncapsulationKind::from_header(bytes)?;
+        Ok(Self {
+            data: &bytes[ENCAPSULATION_HEADER_SIZE as usize..],
+            little_endian: kind.is_little_endian(),
+            max_alignment: kind.max_alignment(),
+        })
+    }
+
+    /// Creates a view of a slice of bytes without an encapsulation header.
+    pub fn from_data(data: &'a [u8], little_endian: bool) -> Self {
+        Self {
+            data,
+            little_endian,
+            max_alignment: 8,
+        }
+    }
+
+    /// Returns the data following the encapsulation header.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns whether the data is little-endian.
+    pub fn is_little_endian(&self) -> bool {
+        self.little_endian
+    }
+
+    /// Returns a cursor at the first member.
+    pub fn cursor(&self) -> Cursor<'a> {
+        self.cursor_at(0)
+    }
+
+    /// Returns a cursor at the given offset from the start of the data.
+    pub fn cursor_at(&self, pos: usize) -> Cursor<'a> {
+        Cursor { view: *self, pos }
+    }
+}
+
+/// A position within a `View`.
+#[derive(Clone, Copy, Debug)]
+pub struct Cursor<'a> {
+    view: View<'a>,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Returns the offset of the cursor from the start of the data.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Decodes the member at the cursor and moves past it.
+    pub fn read<T>(&mut self) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        if self.view.little_endian {
+            self.read_with::<T, LittleEndian>()
+        } else {
+            self.read_with::<T, BigEndian>()
+        }
+    }
+
+    fn read_with<T, E>(&mut self) -> Result<T>
+    where
+        T: DeserializeOwned,
+        E: ByteOrder,
+    {
+        let rest = self.view.data.get(self.pos..).ok_or(Error::Eof)?;
+        let mut deserializer = Deserializer::<_, _, E>::with_alignment(
+            rest,
+            Infinite,
+            self.pos as u64,
+            self.view.max_alignment,
+        );
+        let value = T::deserialize(&mut deserializer)?;
+        self.pos = deserializer.position() as usize;
+        Ok(value)
+    }
+
+    /// Moves past the member at the cursor.
+    ///
+    /// Members whose type has a fixed layout are skipped without being read,
+    /// and strings and sequences of elements with a fixed layout by reading
+    /// only their length. Others are decoded and dropped.
+    pub fn skip<T>(&mut self) -> Result<()>
+    where
+        T: CdrFixedSize + DeserializeOwned,
+    {
+        let layout = match self.view.max_alignment {
+            8 => T::LAYOUT,
+            4 => T::LAYOUT_XCDR2,
+            _ => return self.read::<T>().map(drop),
+        };
+        if let Some(size) = layout.fixed_size_at(self.pos) {
+            return self.advance(size);
+        }
+        if layout.has_fixed_elements() {
+            let mut cursor = *self;
+            let len = cursor.read::<u32>()? as usize;
+            let size = layout.elements_size_at(cursor.pos, len).ok_or(Error::Eof)?;
+            cursor.advance(size)?;
+            *self = cursor;
+            return Ok(());
+        }
+        self.read::<T>().map(drop)
+    }
+
+    fn advance(&mut self, size: usize) -> Result<()> {
+        match self.pos.checked_add(size) {
+            Some(end) if end <= self.view.data.len() => {
+                self.pos = end;
+                Ok(())
+            }
+            _ => Err(Error::Eof),
+        }
+    }
+
+    /// Borrows the string at the cursor and moves past it.
+    pub fn read_str(&mut self) -> Result<&'a str> {
+        let bytes = self.read_bytes()?;
+        match bytes.split_last() {
+            Some((0, bytes)) => str::from_utf8(bytes).map_err(Error::InvalidUtf8Encoding),
+            _ => Err(Error::InvalidCharEncoding),
+        }
+    }
+
+    /// Borrows the octet sequence at the cursor and moves past it.
+    pub fn read_bytes(&mut self) -> Result<&'a [u8]> {
+        let mut cursor = *self;
+        let len = cursor.read::<u32>()? as usize;
+        let end = cursor.pos.checked_add(len).ok_or(Error::Eof)?;
+        let bytes = self.view.data.get(cursor.pos..end).ok_or(Error::Eof)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,1615 @@
+//! DDS-XTypes 1.3 type representations, as exchanged during discovery.
+//!
+//! A type is described by a `TypeObject`, in a minimal form holding only what
+//! type assignability needs and a complete form that also holds names. A
+//! `TypeIdentifier` either describes a simple type fully, or refers to a
+//! `TypeObject` by the MD5 hash of its XCDR2 little-endian serialization.
+//!
+//! All of these types serialize through the crate as XCDR2, with a DHEADER
+//! before appendable types as specified in DDS-XTypes 1.3, 7.4.3.5, and with
+//! the extensibility Annex B gives each of them: the `TypeObject` union and
+//! the headers, members and elements of types are appendable, whereas the
+//! types themselves and the common parts of their members are final. Members
+//! appended by newer versions of appendable types are skipped when read.
+//! Alias, structure, union, enumeration, sequence, array and map types are
+//! supported; applied annotations are not.
+//!
+//! ```rust
+//! use cdr::xtypes::{
+//!     name_hash, Minimal, StructMember, StructType, TypeDefinition, TypeIdentifier, TypeObject,
+//!     IS_APPENDABLE, TK_INT32, TK_NONE,
+//! };
+//! use cdr::{Deserializer, Infinite, LittleEndian, Serializer};
+//! use serde::{Deserialize, Serialize};
+//!
+//! let object = TypeObject::Minimal(TypeDefinition::Struct(StructType::<Minimal> {
+//!     flags: IS_APPENDABLE,
+//!     base_type: TypeIdentifier::Primitive(TK_NONE),
+//!     detail: (),
+//!     members: vec![StructMember {
+//!         member_id: 0,
+//!         flags: 0,
+//!         type_id: TypeIdentifier::Primitive(TK_INT32),
+//!         detail: name_hash("x"),
+//!     }],
+//! }));
+//!
+//! match object.type_identifier().unwrap() {
+//!     TypeIdentifier::Minimal(_) => {}
+//!     _ => unreachable!(),
+//! }
+//!
+//! let mut encoded = Vec::new();
+//! object
+//!     .serialize(&mut Serializer::<_, LittleEndian>::with_alignment(&mut encoded, 0, 4))
+//!     .unwrap();
+//! let mut deserializer = Deserializer::<_, _, LittleEndian>::with_alignment(&encoded[..], Infinite, 0, 4);
+//! assert_eq!(TypeObject::deserialize(&mut deserializer).unwrap(), object);
+//! ```
+
+use std::{convert::TryFrom, fmt, marker::PhantomData};
+
+use byteorder::LittleEndian;
+use md5::{Digest, Md5};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, SeqAccess, Visitor};
+use serde::ser::{self, Serialize};
+use serde::{Deserialize, Deserializer, Serializer};
+
+use crate::de::DELIMITED;
+use crate::error::Result;
+use crate::size;
+
+/// The kind of a type that has no type, such as the base of a structure that
+/// does not inherit.
+pub const TK_NONE: u8 = 0x00;
+pub const TK_BOOLEAN: u8 = 0x01;
+pub const TK_BYTE: u8 = 0x02;
+pub const TK_INT16: u8 = 0x03;
+pub const TK_INT32: u8 = 0x04;
+pub const TK_INT64: u8 = 0x05;
+pub const TK_UINT16: u8 = 0x06;
+pub const TK_UINT32: u8 = 0x07;
+pub const TK_UINT64: u8 = 0x08;
+pub const TK_FLOAT32: u8 = 0x09;
+pub const TK_FLOAT64: u8 = 0x0a;
+pub const TK_FLOAT128: u8 = 0x0b;
+pub const TK_INT8: u8 = 0x0c;
+pub const TK_UINT8: u8 = 0x0d;
+pub const TK_CHAR8: u8 = 0x10;
+pub const TK_CHAR16: u8 = 0x11;
+pub const TK_STRING8: u8 = 0x20;
+pub const TK_STRING16: u8 = 0x21;
+pub const TK_ALIAS: u8 = 0x30;
+pub const TK_ENUM: u8 = 0x40;
+pub const TK_BITMASK: u8 = 0x41;
+pub const TK_ANNOTATION: u8 = 0x50;
+pub const TK_STRUCTURE: u8 = 0x51;
+pub const TK_UNION: u8 = 0x52;
+pub const TK_BITSET: u8 = 0x53;
+pub const TK_SEQUENCE: u8 = 0x60;
+pub const TK_ARRAY: u8 = 0x61;
+pub const TK_MAP: u8 = 0x62;
+
+/// The equivalence kind of a hash of a minimal `TypeObject`.
+pub const EK_MINIMAL: u8 = 0xf1;
+/// The equivalence kind of a hash of a complete `TypeObject`.
+pub const EK_COMPLETE: u8 = 0xf2;
+/// The equivalence kind of a plain collection whose element type is fully
+/// described by its `TypeIdentifier`.
+pub const EK_BOTH: u8 = 0xf3;
+
+const TI_STRING8_SMALL: u8 = 0x70;
+const TI_STRING8_LARGE: u8 = 0x71;
+const TI_STRING16_SMALL: u8 = 0x72;
+const TI_STRING16_LARGE: u8 = 0x73;
+const TI_PLAIN_SEQUENCE_SMALL: u8 = 0x80;
+const TI_PLAIN_SEQUENCE_LARGE: u8 = 0x81;
+const TI_PLAIN_ARRAY_SMALL: u8 = 0x90;
+const TI_PLAIN_ARRAY_LARGE: u8 = 0x91;
+const TI_PLAIN_MAP_SMALL: u8 = 0xa0;
+const TI_PLAIN_MAP_LARGE: u8 = 0xa1;
+const TI_STRONGLY_CONNECTED_COMPONENT: u8 = 0xb0;
+
+/// The number of plain collections that can be nested in a `TypeIdentifier`
+/// read from the input.
+const MAX_NESTING: usize = 128;
+
+// Member flags.
+pub const TRY_CONSTRUCT1: u16 = 1 << 0;
+pub const TRY_CONSTRUCT2: u16 = 1 << 1;
+pub const IS_EXTERNAL: u16 = 1 << 2;
+pub const IS_OPTIONAL: u16 = 1 << 3;
+pub const IS_MUST_UNDERSTAND: u16 = 1 << 4;
+pub const IS_KEY: u16 = 1 << 5;
+pub const IS_DEFAULT: u16 = 1 << 6;
+
+// Type flags.
+pub const IS_FINAL: u16 = 1 << 0;
+pub const IS_APPENDABLE: u16 = 1 << 1;
+pub const IS_MUTABLE: u16 = 1 << 2;
+pub const IS_NESTED: u16 = 1 << 3;
+pub const IS_AUTOID_HASH: u16 = 1 << 4;
+
+/// The first 14 bytes of the MD5 hash of a serialized `TypeObject`.
+pub type EquivalenceHash = [u8; 14];
+
+/// The first 4 bytes of the MD5 hash of a member name.
+pub type NameHash = [u8; 4];
+
+/// Returns the hash by which minimal `TypeObject`s name members.
+pub fn name_hash(name: &str) -> NameHash {
+    let mut hash = [0; 4];
+    hash.copy_from_slice(&Md5::digest(name.as_bytes())[..4]);
+    hash
+}
+
+/// Returns the member ID of a member of a type annotated with
+/// `@autoid(HASH)`.
+pub fn member_id_hash(name: &str) -> u32 {
+    u32::from_le_bytes(name_hash(name)) & 0x0fff_ffff
+}
+
+/// The header of a plain collection `TypeIdentifier`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct PlainCollectionHeader {
+    /// `EK_BOTH`, or the equivalence kind of the hash identifying the
+    /// element type.
+    pub equiv_kind: u8,
+    /// The member flags of the elements.
+    pub element_flags: u16,
+}
+
+impl Serialize for PlainCollectionHeader {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (self.equiv_kind, self.element_flags).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PlainCollectionHeader {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (equiv_kind, element_flags) = Deserialize::deserialize(deserializer)?;
+        Ok(PlainCollectionHeader {
+            equiv_kind,
+            element_flags,
+        })
+    }
+}
+
+/// Identifies a type, either fully or by the hash of its `TypeObject`.
+///
+/// Strings and plain collections are encoded in their small form when all of
+/// their bounds fit in an octet, and in their large form otherwise. A bound
+/// of 0 means unbounded.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TypeIdentifier {
+    /// A primitive type such as `TK_INT32`, or `TK_NONE`.
+    Primitive(u8),
+    String8(u32),
+    String16(u32),
+    PlainSequence {
+        header: PlainCollectionHeader,
+        bound: u32,
+        element: Box<TypeIdentifier>,
+    },
+    PlainArray {
+        header: PlainCollectionHeader,
+        bounds: Vec<u32>,
+        element: Box<TypeIdentifier>,
+    },
+    PlainMap {
+        header: PlainCollectionHeader,
+        bound: u32,
+        element: Box<TypeIdentifier>,
+        key_flags: u16,
+        key: Box<TypeIdentifier>,
+    },
+    /// A type of a set of mutually recursive types.
+    StronglyConnectedComponent {
+        equiv_kind: u8,
+        hash: EquivalenceHash,
+        scc_length: i32,
+        scc_index: i32,
+    },
+    /// A type by the hash of its minimal `TypeObject`.
+    Minimal(EquivalenceHash),
+    /// A type by the hash of its complete `TypeObject`.
+    Complete(EquivalenceHash),
+}
+
+impl TypeIdentifier {
+    /// Returns whether the type is fully described by the identifier.
+    pub fn is_fully_descriptive(&self) -> bool {
+        match *self {
+            TypeIdentifier::Primitive(_)
+            | TypeIdentifier::String8(_)
+            | TypeIdentifier::String16(_) => true,
+            TypeIdentifier::PlainSequence { ref header, .. }
+            | TypeIdentifier::PlainArray { ref header, .. }
+            | TypeIdentifier::PlainMap { ref header, .. } => header.equiv_kind == EK_BOTH,
+            _ => false,
+        }
+    }
+}
+
+impl Serialize for TypeIdentifier {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        fn small(bound: u32) -> Option<u8> {
+            u8::try_from(bound).ok()
+        }
+
+        match *self {
+            TypeIdentifier::Primitive(kind) => kind.serialize(serializer),
+            TypeIdentifier::String8(bound) => match small(bound) {
+                Some(bound) => (TI_STRING8_SMALL, bound).serialize(serializer),
+                None => (TI_STRING8_LARGE, bound).serialize(serializer),
+            },
+            TypeIdentifier::String16(bound) => match small(bound) {
+                Some(bound) => (TI_STRING16_SMALL, bound).serialize(serializer),
+                None => (TI_STRING16_LARGE, bound).serialize(serializer),
+            },
+            TypeIdentifier::PlainSequence {
+                ref header,
+                bound,
+                ref element,
+            } => match small(bound) {
+                Some(bound) => {
+                    (TI_PLAIN_SEQUENCE_SMALL, header, bound, element).serialize(serializer)
+                }
+                None => (TI_PLAIN_SEQUENCE_LARGE, header, bound, element).serialize(serializer),
+            },
+            TypeIdentifier::PlainArray {
+                ref header,
+                ref bounds,
+                ref element,
+            } => match bounds
+                .iter()
+                .map(|&b| small(b))
+                .collect::<Option<Vec<u8>>>()
+            {
+                Some(bounds) => {
+                    (TI_PLAIN_ARRAY_SMALL, header, bounds, element).serialize(serializer)
+                }
+                None => (TI_PLAIN_ARRAY_LARGE, header, bounds, element).serialize(serializer),
+            },
+            TypeIdentifier::PlainMap {
+                ref header,
+                bound,
+                ref element,
+                key_flags,
+                ref key,
+            } => match small(bound) {
+                Some(bound) => (TI_PLAIN_MAP_SMALL, header, bound, element, key_flags, key)
+                    .serialize(serializer),
+                None => (TI_PLAIN_MAP_LARGE, header, bound, element, key_flags, key)
+                    .serialize(serializer),
+            },
+            TypeIdentifier::StronglyConnectedComponent {
+                equiv_kind,
+                ref hash,
+                scc_length,
+                scc_index,
+            } => (
+                TI_STRONGLY_CONNECTED_COMPONENT,
+                Delimited((equiv_kind, hash, scc_length, scc_index)),
+            )
+                .serialize(serializer),
+            TypeIdentifier::Minimal(ref hash) => (EK_MINIMAL, hash).serialize(serializer),
+            TypeIdentifier::Complete(ref hash) => (EK_COMPLETE, hash).serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TypeIdentifier {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        TypeIdentifierVisitor(0).deserialize(deserializer)
+    }
+}
+
+/// Reads a `TypeIdentifier` nested in as many plain collections.
+struct TypeIdentifierVisitor(usize);
+
+impl<'de> DeserializeSeed<'de> for TypeIdentifierVisitor {
+    type Value = TypeIdentifier;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<TypeIdentifier, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if self.0 >= MAX_NESTING {
+            return Err(de::Error::custom("TypeIdentifier is nested too deeply"));
+        }
+        deserializer.deserialize_tuple(6, self)
+    }
+}
+
+impl<'de> Visitor<'de> for TypeIdentifierVisitor {
+    type Value = TypeIdentifier;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a TypeIdentifier")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<TypeIdentifier, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let discriminator: u8 = next(&mut seq)?;
+        Ok(match discriminator {
+            TK_NONE..=TK_UINT8 | TK_CHAR8 | TK_CHAR16 => TypeIdentifier::Primitive(discriminator),
+            TI_STRING8_SMALL => TypeIdentifier::String8(u32::from(next::<_, u8>(&mut seq)?)),
+            TI_STRING8_LARGE => TypeIdentifier::String8(next(&mut seq)?),
+            TI_STRING16_SMALL => TypeIdentifier::String16(u32::from(next::<_, u8>(&mut seq)?)),
+            TI_STRING16_LARGE => TypeIdentifier::String16(next(&mut seq)?),
+            TI_PLAIN_SEQUENCE_SMALL | TI_PLAIN_SEQUENCE_LARGE => {
+                let header = next(&mut seq)?;
+                let bound = if discriminator == TI_PLAIN_SEQUENCE_SMALL {
+                    u32::from(next::<_, u8>(&mut seq)?)
+                } else {
+                    next(&mut seq)?
+                };
+                TypeIdentifier::PlainSequence {
+                    header,
+                    bound,
+                    element: self.nested(&mut seq)?,
+                }
+            }
+            TI_PLAIN_ARRAY_SMALL | TI_PLAIN_ARRAY_LARGE => {
+                let header = next(&mut seq)?;
+                let bounds = if discriminator == TI_PLAIN_ARRAY_SMALL {
+                    let bounds: Vec<u8> = next(&mut seq)?;
+                    bounds.into_iter().map(u32::from).collect()
+                } else {
+                    next(&mut seq)?
+                };
+                TypeIdentifier::PlainArray {
+                    header,
+                    bounds,
+                    element: self.nested(&mut seq)?,
+                }
+            }
+            TI_PLAIN_MAP_SMALL | TI_PLAIN_MAP_LARGE => {
+                let header = next(&mut seq)?;
+                let bound = if discriminator == TI_PLAIN_MAP_SMALL {
+                    u32::from(next::<_, u8>(&mut seq)?)
+                } else {
+                    next(&mut seq)?
+                };
+                TypeIdentifier::PlainMap {
+                    header,
+                    bound,
+                    element: self.nested(&mut seq)?,
+                    key_flags: next(&mut seq)?,
+                    key: self.nested(&mut seq)?,
+                }
+            }
+            TI_STRONGLY_CONNECTED_COMPONENT => {
+                let Delimited((equiv_kind, hash, scc_length, scc_index)) = next(&mut seq)?;
+                TypeIdentifier::StronglyConnectedComponent {
+                    equiv_kind,
+                    hash,
+                    scc_length,
+                    scc_index,
+                }
+            }
+            EK_MINIMAL => TypeIdentifier::Minimal(next(&mut seq)?),
+            EK_COMPLETE => TypeIdentifier::Complete(next(&mut seq)?),
+            _ => {
+                return Err(de::Error::custom(format!(
+                    "unsupported TypeIdentifier kind {:#04x}",
+                    discriminator
+                )))
+            }
+        })
+    }
+}
+
+impl TypeIdentifierVisitor {
+    /// Reads the `TypeIdentifier` of the elements or keys of a collection.
+    fn nested<'de, A>(&self, seq: &mut A) -> std::result::Result<Box<TypeIdentifier>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        seq.next_element_seed(TypeIdentifierVisitor(self.0 + 1))?
+            .map(Box::new)
+            .ok_or_else(|| de::Error::custom("truncated XTypes type representation"))
+    }
+}
+
+/// Whether a `TypeObject` is minimal or complete, which determines the
+/// details it holds about its type and members.
+pub trait Equivalence: private::Sealed {
+    #[doc(hidden)]
+    const KIND: u8;
+    /// The details of a type, nothing for minimal types.
+    type TypeDetail: Clone + fmt::Debug + Eq + std::hash::Hash + Serialize + DeserializeOwned;
+    /// The details of a member, its `NameHash` for minimal types.
+    type MemberDetail: Clone + fmt::Debug + Eq + std::hash::Hash + Serialize + DeserializeOwned;
+}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for super::Minimal {}
+    impl Sealed for super::Complete {}
+}
+
+/// The equivalence of minimal `TypeObject`s.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Minimal {}
+
+impl Equivalence for Minimal {
+    const KIND: u8 = EK_MINIMAL;
+    type TypeDetail = ();
+    type MemberDetail = NameHash;
+}
+
+/// The equivalence of complete `TypeObject`s.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Complete {}
+
+impl Equivalence for Complete {
+    const KIND: u8 = EK_COMPLETE;
+    type TypeDetail = CompleteTypeDetail;
+    type MemberDetail = CompleteMemberDetail;
+}
+
+/// The details of a type in a complete `TypeObject`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CompleteTypeDetail {
+    /// The fully qualified name of the type, such as `"geometry::Point"`.
+    pub type_name: String,
+}
+
+impl CompleteTypeDetail {
+    pub fn new<T: Into<String>>(type_name: T) -> Self {
+        CompleteTypeDetail {
+            type_name: type_name.into(),
+        }
+    }
+}
+
+impl Serialize for CompleteTypeDetail {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (NoAnnotations, &self.type_name).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CompleteTypeDetail {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (NoAnnotations, type_name) = Deserialize::deserialize(deserializer)?;
+        Ok(CompleteTypeDetail { type_name })
+    }
+}
+
+/// The details of a member in a complete `TypeObject`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CompleteMemberDetail {
+    pub name: String,
+}
+
+impl CompleteMemberDetail {
+    pub fn new<T: Into<String>>(name: T) -> Self {
+        CompleteMemberDetail { name: name.into() }
+    }
+}
+
+impl Serialize for CompleteMemberDetail {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (&self.name, NoAnnotations).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CompleteMemberDetail {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (name, NoAnnotations) = Deserialize::deserialize(deserializer)?;
+        Ok(CompleteMemberDetail { name })
+    }
+}
+
+/// A structure type.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct StructType<K: Equivalence> {
+    pub flags: u16,
+    /// The type the structure inherits from, or `TK_NONE`.
+    pub base_type: TypeIdentifier,
+    pub detail: K::TypeDetail,
+    pub members: Vec<StructMember<K>>,
+}
+
+/// A member of a structure type.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct StructMember<K: Equivalence> {
+    pub member_id: u32,
+    pub flags: u16,
+    pub type_id: TypeIdentifier,
+    pub detail: K::MemberDetail,
+}
+
+/// A union type.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct UnionType<K: Equivalence> {
+    pub flags: u16,
+    pub detail: K::TypeDetail,
+    pub discriminator_flags: u16,
+    pub discriminator_type: TypeIdentifier,
+    pub members: Vec<UnionMember<K>>,
+}
+
+/// A member of a union type.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct UnionMember<K: Equivalence> {
+    pub member_id: u32,
+    pub flags: u16,
+    pub type_id: TypeIdentifier,
+    /// The values of the discriminator that select the member.
+    pub labels: Vec<i32>,
+    pub detail: K::MemberDetail,
+}
+
+/// An alias type, i.e. a typedef.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct AliasType<K: Equivalence> {
+    pub flags: u16,
+    pub detail: K::TypeDetail,
+    pub related_flags: u16,
+    pub related_type: TypeIdentifier,
+}
+
+/// An enumeration type.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct EnumType<K: Equivalence> {
+    pub flags: u16,
+    /// The number of bits of the values, 32 by default.
+    pub bit_bound: u16,
+    pub detail: K::TypeDetail,
+    pub literals: Vec<EnumLiteral<K>>,
+}
+
+/// A literal of an enumeration type.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct EnumLiteral<K: Equivalence> {
+    pub value: i32,
+    pub flags: u16,
+    pub detail: K::MemberDetail,
+}
+
+/// A sequence type that a `TypeIdentifier` cannot describe as a plain
+/// collection.
+///
+/// Only complete sequence types of named types have a detail.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SequenceType<K: Equivalence> {
+    pub flags: u16,
+    pub bound: u32,
+    pub detail: Option<K::TypeDetail>,
+    pub element_flags: u16,
+    pub element_type: TypeIdentifier,
+}
+
+/// An array type that a `TypeIdentifier` cannot describe as a plain
+/// collection.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ArrayType<K: Equivalence> {
+    pub flags: u16,
+    pub bounds: Vec<u32>,
+    pub detail: K::TypeDetail,
+    pub element_flags: u16,
+    pub element_type: TypeIdentifier,
+}
+
+/// A map type that a `TypeIdentifier` cannot describe as a plain
+/// collection.
+///
+/// Only complete map types of named types have a detail.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct MapType<K: Equivalence> {
+    pub flags: u16,
+    pub bound: u32,
+    pub detail: Option<K::TypeDetail>,
+    pub key_flags: u16,
+    pub key_type: TypeIdentifier,
+    pub element_flags: u16,
+    pub element_type: TypeIdentifier,
+}
+
+/// The definition of a type in a minimal or complete `TypeObject`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TypeDefinition<K: Equivalence> {
+    Alias(AliasType<K>),
+    Struct(StructType<K>),
+    Union(UnionType<K>),
+    Enum(EnumType<K>),
+    Sequence(SequenceType<K>),
+    Array(ArrayType<K>),
+    Map(MapType<K>),
+}
+
+pub type MinimalTypeObject = TypeDefinition<Minimal>;
+pub type CompleteTypeObject = TypeDefinition<Complete>;
+
+/// The description of a type exchanged between DDS implementations.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TypeObject {
+    Minimal(MinimalTypeObject),
+    Complete(CompleteTypeObject),
+}
+
+impl TypeObject {
+    /// Returns the XCDR2 little-endian serialization from which the hash of
+    /// the object is computed.
+    fn to_hashed_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.serialize(
+            &mut crate::ser::Serializer::<_, LittleEndian>::with_alignment(&mut bytes, 0, 4),
+        )?;
+        Ok(bytes)
+    }
+
+    /// Returns the `TypeIdentifier` referring to the object by its hash.
+    pub fn type_identifier(&self) -> Result<TypeIdentifier> {
+        TypeIdentifierWithSize::new(self).map(|id| id.type_id)
+    }
+}
+
+/// A `TypeIdentifier` with the size of the `TypeObject` it refers to.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TypeIdentifierWithSize {
+    pub type_id: TypeIdentifier,
+    pub typeobject_serialized_size: u32,
+}
+
+impl TypeIdentifierWithSize {
+    /// Identifies a `TypeObject` by its hash.
+    pub fn new(object: &TypeObject) -> Result<Self> {
+        let bytes = object.to_hashed_bytes()?;
+        let mut hash = [0; 14];
+        hash.copy_from_slice(&Md5::digest(&bytes)[..14]);
+        Ok(TypeIdentifierWithSize {
+            type_id: match *object {
+                TypeObject::Minimal(_) => TypeIdentifier::Minimal(hash),
+                TypeObject::Complete(_) => TypeIdentifier::Complete(hash),
+            },
+            typeobject_serialized_size: u32::try_from(bytes.len())
+                .map_err(|_| crate::Error::NumberOutOfRange)?,
+        })
+    }
+}
+
+/// A `TypeIdentifier` with those of the types it depends on.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TypeIdentifierWithDependencies {
+    pub typeid_with_size: TypeIdentifierWithSize,
+    /// The number of dependencies, which may exceed those listed, or -1 if
+    /// unknown.
+    pub dependent_typeid_count: i32,
+    pub dependent_typeids: Vec<TypeIdentifierWithSize>,
+}
+
+/// The type information advertised by endpoints during discovery.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TypeInformation {
+    pub minimal: TypeIdentifierWithDependencies,
+    pub complete: TypeIdentifierWithDependencies,
+}
+
+const MEMBER_ID_MINIMAL: u32 = 0x1001;
+const MEMBER_ID_COMPLETE: u32 = 0x1002;
+
+/// The bit of an EMHEADER set for members that must be understood.
+const EMHEADER_MUST_UNDERSTAND: u32 = 1 << 31;
+/// The length code of members whose size follows their EMHEADER.
+const LC_NEXTINT: u32 = 4;
+
+/// A value of an appendable type, preceded by its size in XCDR2.
+struct Delimited<T>(T);
+
+impl<T> Serialize for Delimited<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let size = size::calc_serialized_data_size_with_alignment(&self.0, 0, 4);
+        let size = u32::try_from(size).map_err(|_| ser::Error::custom("value too large"))?;
+        serializer.serialize_newtype_struct(DELIMITED, &(size, &self.0))
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Delimited<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(DELIMITED, DelimitedVisitor(PhantomData))
+    }
+}
+
+struct DelimitedVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for DelimitedVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Delimited<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a delimited value")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (_, value): (u32, T) = Deserialize::deserialize(deserializer)?;
+        Ok(Delimited(value))
+    }
+}
+
+/// The absent optional builtin and custom annotations of a complete type or
+/// member.
+struct NoAnnotations;
+
+impl Serialize for NoAnnotations {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (false, false).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NoAnnotations {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Deserialize::deserialize(deserializer)? {
+            (false, false) => Ok(NoAnnotations),
+            _ => Err(de::Error::custom("applied annotations are not supported")),
+        }
+    }
+}
+
+/// A value followed by the annotations complete `TypeObject`s give it.
+struct Annotated<K, T>(T, PhantomData<K>);
+
+impl<K, T> Annotated<K, T> {
+    fn new(value: T) -> Self {
+        Annotated(value, PhantomData)
+    }
+}
+
+impl<K, T> Serialize for Annotated<K, T>
+where
+    K: Equivalence,
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if K::KIND == EK_COMPLETE {
+            (&self.0, NoAnnotations).serialize(serializer)
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de, K, T> Deserialize<'de> for Annotated<K, T>
+where
+    K: Equivalence,
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if K::KIND == EK_COMPLETE {
+            let (value, NoAnnotations) = Deserialize::deserialize(deserializer)?;
+            Ok(Annotated::new(value))
+        } else {
+            T::deserialize(deserializer).map(Annotated::new)
+        }
+    }
+}
+
+/// The optional detail of a complete collection header, which minimal
+/// collection headers lack.
+struct CollectionDetail<K: Equivalence>(Option<K::TypeDetail>);
+
+impl<K> Serialize for CollectionDetail<K>
+where
+    K: Equivalence,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match (K::KIND == EK_COMPLETE, &self.0) {
+            (true, Some(detail)) => (true, detail).serialize(serializer),
+            (true, None) => false.serialize(serializer),
+            (false, Some(_)) => Err(ser::Error::custom(
+                "minimal collection types have no detail",
+            )),
+            (false, None) => ().serialize(serializer),
+        }
+    }
+}
+
+impl<'de, K> Deserialize<'de> for CollectionDetail<K>
+where
+    K: Equivalence,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if K::KIND != EK_COMPLETE {
+            return <()>::deserialize(deserializer).map(|()| CollectionDetail(None));
+        }
+        deserializer.deserialize_tuple(2, CollectionDetailVisitor(PhantomData))
+    }
+}
+
+struct CollectionDetailVisitor<K>(PhantomData<K>);
+
+impl<'de, K> Visitor<'de> for CollectionDetailVisitor<K>
+where
+    K: Equivalence,
+{
+    type Value = CollectionDetail<K>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an optional type detail")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        Ok(CollectionDetail(if next(&mut seq)? {
+            Some(next(&mut seq)?)
+        } else {
+            None
+        }))
+    }
+}
+
+fn next<'de, A, T>(seq: &mut A) -> std::result::Result<T, A::Error>
+where
+    A: SeqAccess<'de>,
+    T: Deserialize<'de>,
+{
+    seq.next_element()?
+        .ok_or_else(|| de::Error::custom("truncated XTypes type representation"))
+}
+
+impl<K> Serialize for StructType<K>
+where
+    K: Equivalence,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (
+            self.flags,
+            Delimited((&self.base_type, &self.detail)),
+            Delimited(&self.members),
+        )
+            .serialize(serializer)
+    }
+}
+
+impl<'de, K> Deserialize<'de> for StructType<K>
+where
+    K: Equivalence,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (flags, Delimited((base_type, detail)), Delimited(members)) =
+            Deserialize::deserialize(deserializer)?;
+        Ok(StructType {
+            flags,
+            base_type,
+            detail,
+            members,
+        })
+    }
+}
+
+impl<K> Serialize for StructMember<K>
+where
+    K: Equivalence,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Delimited((self.member_id, self.flags, &self.type_id, &self.detail)).serialize(serializer)
+    }
+}
+
+impl<'de, K> Deserialize<'de> for StructMember<K>
+where
+    K: Equivalence,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Delimited((member_id, flags, type_id, detail)) =
+            Deserialize::deserialize(deserializer)?;
+        Ok(StructMember {
+            member_id,
+            flags,
+            type_id,
+            detail,
+        })
+    }
+}
+
+impl<K> Serialize for UnionType<K>
+where
+    K: Equivalence,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (
+            self.flags,
+            Delimited(&self.detail),
+            Delimited(Annotated::<K, _>::new((
+                self.discriminator_flags,
+                &self.discriminator_type,
+            ))),
+            Delimited(&self.members),
+        )
+            .serialize(serializer)
+    }
+}
+
+impl<'de, K> Deserialize<'de> for UnionType<K>
+where
+    K: Equivalence,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (flags, Delimited(detail), discriminator, Delimited(members)) =
+            Deserialize::deserialize(deserializer)?;
+        let Delimited(Annotated::<K, _>((discriminator_flags, discriminator_type), _)) =
+            discriminator;
+        Ok(UnionType {
+            flags,
+            detail,
+            discriminator_flags,
+            discriminator_type,
+            members,
+        })
+    }
+}
+
+impl<K> Serialize for UnionMember<K>
+where
+    K: Equivalence,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Delimited((
+            self.member_id,
+            self.flags,
+            &self.type_id,
+            &self.labels,
+            &self.detail,
+        ))
+        .serialize(serializer)
+    }
+}
+
+impl<'de, K> Deserialize<'de> for UnionMember<K>
+where
+    K: Equivalence,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Delimited((member_id, flags, type_id, labels, detail)) =
+            Deserialize::deserialize(deserializer)?;
+        Ok(UnionMember {
+            member_id,
+            flags,
+            type_id,
+            labels,
+            detail,
+        })
+    }
+}
+
+impl<K> Serialize for AliasType<K>
+where
+    K: Equivalence,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (
+            self.flags,
+            Delimited(&self.detail),
+            Delimited(Annotated::<K, _>::new((
+                self.related_flags,
+                &self.related_type,
+            ))),
+        )
+            .serialize(serializer)
+    }
+}
+
+impl<'de, K> Deserialize<'de> for AliasType<K>
+where
+    K: Equivalence,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (flags, Delimited(detail), body) = Deserialize::deserialize(deserializer)?;
+        let Delimited(Annotated::<K, _>((related_flags, related_type), _)) = body;
+        Ok(AliasType {
+            flags,
+            detail,
+            related_flags,
+            related_type,
+        })
+    }
+}
+
+impl<K> Serialize for EnumType<K>
+where
+    K: Equivalence,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (
+            self.flags,
+            Delimited((self.bit_bound, &self.detail)),
+            Delimited(&self.literals),
+        )
+            .serialize(serializer)
+    }
+}
+
+impl<'de, K> Deserialize<'de> for EnumType<K>
+where
+    K: Equivalence,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (flags, Delimited((bit_bound, detail)), Delimited(literals)) =
+            Deserialize::deserialize(deserializer)?;
+        Ok(EnumType {
+            flags,
+            bit_bound,
+            detail,
+            literals,
+        })
+    }
+}
+
+impl<K> Serialize for EnumLiteral<K>
+where
+    K: Equivalence,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Delimited((self.value, self.flags, &self.detail)).serialize(serializer)
+    }
+}
+
+impl<'de, K> Deserialize<'de> for EnumLiteral<K>
+where
+    K: Equivalence,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Delimited((value, flags, detail)) = Deserialize::deserialize(deserializer)?;
+        Ok(EnumLiteral {
+            value,
+            flags,
+            detail,
+        })
+    }
+}
+
+impl<K> Serialize for SequenceType<K>
+where
+    K: Equivalence,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (
+            self.flags,
+            Delimited((self.bound, CollectionDetail::<K>(self.detail.clone()))),
+            Delimited(Annotated::<K, _>::new((
+                self.element_flags,
+                &self.element_type,
+            ))),
+        )
+            .serialize(serializer)
+    }
+}
+
+impl<'de, K> Deserialize<'de> for SequenceType<K>
+where
+    K: Equivalence,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (flags, Delimited((bound, CollectionDetail::<K>(detail))), element) =
+            Deserialize::deserialize(deserializer)?;
+        let Delimited(Annotated::<K, _>((element_flags, element_type), _)) = element;
+        Ok(SequenceType {
+            flags,
+            bound,
+            detail,
+            element_flags,
+            element_type,
+        })
+    }
+}
+
+impl<K> Serialize for ArrayType<K>
+where
+    K: Equivalence,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (
+            self.flags,
+            Delimited((&self.bounds, &self.detail)),
+            Delimited(Annotated::<K, _>::new((
+                self.element_flags,
+                &self.element_type,
+            ))),
+        )
+            .serialize(serializer)
+    }
+}
+
+impl<'de, K> Deserialize<'de> for ArrayType<K>
+where
+    K: Equivalence,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (flags, Delimited((bounds, detail)), element) = Deserialize::deserialize(deserializer)?;
+        let Delimited(Annotated::<K, _>((element_flags, element_type), _)) = element;
+        Ok(ArrayType {
+            flags,
+            bounds,
+            detail,
+            element_flags,
+            element_type,
+        })
+    }
+}
+
+impl<K> Serialize for MapType<K>
+where
+    K: Equivalence,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (
+            self.flags,
+            Delimited((self.bound, CollectionDetail::<K>(self.detail.clone()))),
+            Delimited(Annotated::<K, _>::new((self.key_flags, &self.key_type))),
+            Delimited(Annotated::<K, _>::new((
+                self.element_flags,
+                &self.element_type,
+            ))),
+        )
+            .serialize(serializer)
+    }
+}
+
+impl<'de, K> Deserialize<'de> for MapType<K>
+where
+    K: Equivalence,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (flags, Delimited((bound, CollectionDetail::<K>(detail))), key, element) =
+            Deserialize::deserialize(deserializer)?;
+        let Delimited(Annotated::<K, _>((key_flags, key_type), _)) = key;
+        let Delimited(Annotated::<K, _>((element_flags, element_type), _)) = element;
+        Ok(MapType {
+            flags,
+            bound,
+            detail,
+            key_flags,
+            key_type,
+            element_flags,
+            element_type,
+        })
+    }
+}
+
+impl<K> Serialize for TypeDefinition<K>
+where
+    K: Equivalence,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            TypeDefinition::Alias(ref ty) => (TK_ALIAS, ty).serialize(serializer),
+            TypeDefinition::Struct(ref ty) => (TK_STRUCTURE, ty).serialize(serializer),
+            TypeDefinition::Union(ref ty) => (TK_UNION, ty).serialize(serializer),
+            TypeDefinition::Enum(ref ty) => (TK_ENUM, ty).serialize(serializer),
+            TypeDefinition::Sequence(ref ty) => (TK_SEQUENCE, ty).serialize(serializer),
+            TypeDefinition::Array(ref ty) => (TK_ARRAY, ty).serialize(serializer),
+            TypeDefinition::Map(ref ty) => (TK_MAP, ty).serialize(serializer),
+        }
+    }
+}
+
+impl<'de, K> Deserialize<'de> for TypeDefinition<K>
+where
+    K: Equivalence,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple(2, TypeDefinitionVisitor(PhantomData))
+    }
+}
+
+struct TypeDefinitionVisitor<K>(PhantomData<K>);
+
+impl<'de, K> Visitor<'de> for TypeDefinitionVisitor<K>
+where
+    K: Equivalence,
+{
+    type Value = TypeDefinition<K>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a type definition")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        Ok(match next(&mut seq)? {
+            TK_ALIAS => TypeDefinition::Alias(next(&mut seq)?),
+            TK_STRUCTURE => TypeDefinition::Struct(next(&mut seq)?),
+            TK_UNION => TypeDefinition::Union(next(&mut seq)?),
+            TK_ENUM => TypeDefinition::Enum(next(&mut seq)?),
+            TK_SEQUENCE => TypeDefinition::Sequence(next(&mut seq)?),
+            TK_ARRAY => TypeDefinition::Array(next(&mut seq)?),
+            TK_MAP => TypeDefinition::Map(next(&mut seq)?),
+            kind => {
+                return Err(de::Error::custom(format!(
+                    "unsupported type kind {:#04x}",
+                    kind
+                )))
+            }
+        })
+    }
+}
+
+impl Serialize for TypeObject {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            TypeObject::Minimal(ref ty) => Delimited((EK_MINIMAL, ty)).serialize(serializer),
+            TypeObject::Complete(ref ty) => Delimited((EK_COMPLETE, ty)).serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TypeObject {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(DELIMITED, TypeObjectVisitor)
+    }
+}
+
+struct TypeObjectVisitor;
+
+impl<'de> Visitor<'de> for TypeObjectVisitor {
+    type Value = TypeObject;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a TypeObject")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple(3, self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<TypeObject, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let _size: u32 = next(&mut seq)?;
+        match next(&mut seq)? {
+            EK_MINIMAL => Ok(TypeObject::Minimal(next(&mut seq)?)),
+            EK_COMPLETE => Ok(TypeObject::Complete(next(&mut seq)?)),
+            kind => Err(de::Error::custom(format!(
+                "invalid TypeObject kind {:#04x}",
+                kind
+            ))),
+        }
+    }
+}
+
+impl Serialize for TypeIdentifierWithSize {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Delimited((&self.type_id, self.typeobject_serialized_size)).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TypeIdentifierWithSize {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Delimited((type_id, typeobject_serialized_size)) =
+            Deserialize::deserialize(deserializer)?;
+        Ok(TypeIdentifierWithSize {
+            type_id,
+            typeobject_serialized_size,
+        })
+    }
+}
+
+impl Serialize for TypeIdentifierWithDependencies {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Delimited((
+            &self.typeid_with_size,
+            self.dependent_typeid_count,
+            Delimited(&self.dependent_typeids),
+        ))
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TypeIdentifierWithDependencies {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Delimited((typeid_with_size, dependent_typeid_count, Delimited(dependent_typeids))) =
+            Deserialize::deserialize(deserializer)?;
+        Ok(TypeIdentifierWithDependencies {
+            typeid_with_size,
+            dependent_typeid_count,
+            dependent_typeids,
+        })
+    }
+}
+
+/// `TypeInformation` is mutable: each member is preceded by an EMHEADER
+/// holding its member ID, and by its size.
+impl Serialize for TypeInformation {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        fn member_size<S, T>(value: &T) -> std::result::Result<u32, S::Error>
+        where
+            S: Serializer,
+            T: Serialize,
+        {
+            u32::try_from(size::calc_serialized_data_size_with_alignment(value, 0, 4))
+                .map_err(|_| ser::Error::custom("value too large"))
+        }
+
+        Delimited((
+            LC_NEXTINT << 28 | MEMBER_ID_MINIMAL,
+            member_size::<S, _>(&self.minimal)?,
+            &self.minimal,
+            LC_NEXTINT << 28 | MEMBER_ID_COMPLETE,
+            member_size::<S, _>(&self.complete)?,
+            &self.complete,
+        ))
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TypeInformation {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(DELIMITED, TypeInformationVisitor)
+    }
+}
+
+struct TypeInformationVisitor;
+
+impl<'de> Visitor<'de> for TypeInformationVisitor {
+    type Value = TypeInformation;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a TypeInformation")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple(usize::MAX, self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<TypeInformation, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let size: u32 = next(&mut seq)?;
+        let (mut minimal, mut complete) = (None, None);
+        // The number of bytes read past the DHEADER, which is 4-aligned.
+        let mut pos: u32 = 0;
+        while pos < size {
+            pos = (pos + 3) & !3;
+            let emheader: u32 = next(&mut seq)?;
+            pos += 4;
+            let member_id = emheader & 0x0fff_ffff;
+            let length_code = emheader >> 28 & 0x7;
+            let slot = match member_id {
+                MEMBER_ID_MINIMAL => Some(&mut minimal),
+                MEMBER_ID_COMPLETE => Some(&mut complete),
+                _ if emheader & EMHEADER_MUST_UNDERSTAND != 0 => {
+                    return Err(de::Error::custom(format!(
+                        "unknown must-understand member {:#x}",
+                        member_id
+                    )))
+                }
+                _ => None,
+            };
+            let len = match length_code {
+                0..=3 => 1 << length_code,
+                LC_NEXTINT => {
+                    pos += 4;
+                    next(&mut seq)?
+                }
+                // The size doubles as the first 4 bytes of the member, which
+                // for the known members is their DHEADER.
+                _ => match slot {
+                    Some(slot) => {
+                        let value: TypeIdentifierWithDependencies = next(&mut seq)?;
+                        let len = size::calc_serialized_data_size_with_alignment(&value, 0, 4);
+                        pos = pos.saturating_add(u32::try_from(len).unwrap_or(u32::MAX));
+                        *slot = Some(value);
+                        continue;
+                    }
+                    None => {
+                        let count: u32 = next(&mut seq)?;
+                        pos += 4;
+                        let unit = [1, 4, 8][(length_code - 5) as usize];
+                        count
+                            .checked_mul(unit)
+                            .ok_or_else(|| de::Error::custom("member too large"))?
+                    }
+                },
+            };
+            match slot {
+                Some(slot) => *slot = Some(next(&mut seq)?),
+                None => seq
+                    .next_element_seed(Skip(len as usize))?
+                    .ok_or_else(|| de::Error::custom("truncated TypeInformation"))?,
+            }
+            pos = pos.saturating_add(len);
+        }
+        Ok(TypeInformation {
+            minimal: minimal.ok_or_else(|| de::Error::missing_field("minimal"))?,
+            complete: complete.ok_or_else(|| de::Error::missing_field("complete"))?,
+        })
+    }
+}
+
+/// Skips a number of bytes.
+struct Skip(usize);
+
+impl<'de> DeserializeSeed<'de> for Skip {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple(self.0, self)
+    }
+}
+
+impl<'de> Visitor<'de> for Skip {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} bytes", self.0)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<(), A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        for _ in 0..self.0 {
+            next::<_, u8>(&mut seq)?;
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,9 @@
+#![allow(
+    clippy::approx_constant,
+    clippy::assertions_on_constants,
+    clippy::identity_op
+)]
+
 use std::{fmt::Debug, io::Cursor};
 
 use cdr::{
@@ -38,7 +44,7 @@ where
     T: serde::Serialize + serde::Deserialize<'de> + PartialEq + Debug,
 {
     let size = match maybe_size {
-        Some(v) => v as u64,
+        Some(v) => v,
         None => cdr::calc_serialized_size(&element),
     };
     {
@@ -80,6 +86,23 @@ where
         let encoded = cdr::serialize::<_, _, PlCdrLe>(element, Infinite).unwrap();
         let decoded = cdr::deserialize(&encoded).unwrap();
 
+        assert_eq!(*element, decoded);
+        assert_eq!(size + ENCAPSULATION_HEADER_SIZE, encoded.len() as u64);
+    }
+    {
+        let mut encoded = vec![0xff];
+        cdr::ser::serialize_data_into_vec::<_, _, BigEndian>(&mut encoded, element, Infinite)
+            .unwrap();
+        let decoded = cdr::de::deserialize_data::<T, BigEndian>(&encoded[1..]).unwrap();
+
+        assert_eq!(*element, decoded);
+        assert_eq!(size + 1, encoded.len() as u64);
+    }
+    {
+        let mut encoded = Vec::new();
+        cdr::serialize_into_vec::<_, _, CdrLe>(&mut encoded, element, Infinite).unwrap();
+        let decoded = cdr::deserialize(&encoded).unwrap();
+
         assert_eq!(*element, decoded);
         assert_eq!(size + ENCAPSULATION_HEADER_SIZE, encoded.len() as u64);
     }
@@ -160,23 +183,36 @@ where
     T: serde::Serialize + serde::Deserialize<'de> + PartialEq + Debug,
 {
     if let Some(bound) = calc_invalid_size(element, maybe_size) {
-        assert!(
-            cdr::ser::serialize_data::<_, _, BigEndian>(&element, Bounded(bound as u64)).is_err()
-        );
-        assert!(
-            cdr::ser::serialize_data::<_, _, LittleEndian>(&element, Bounded(bound as u64))
-                .is_err()
-        );
-        assert!(cdr::serialize::<_, _, CdrBe>(&element, Bounded(bound as u64)).is_err());
-        assert!(cdr::serialize::<_, _, CdrLe>(&element, Bounded(bound as u64)).is_err());
-        assert!(cdr::serialize::<_, _, PlCdrBe>(&element, Bounded(bound as u64)).is_err());
-        assert!(cdr::serialize::<_, _, PlCdrLe>(&element, Bounded(bound as u64)).is_err());
+        assert!(cdr::ser::serialize_data::<_, _, BigEndian>(&element, Bounded(bound)).is_err());
+        assert!(cdr::ser::serialize_data::<_, _, LittleEndian>(&element, Bounded(bound)).is_err());
+        assert!(cdr::serialize::<_, _, CdrBe>(&element, Bounded(bound)).is_err());
+        assert!(cdr::serialize::<_, _, CdrLe>(&element, Bounded(bound)).is_err());
+        assert!(cdr::serialize::<_, _, PlCdrBe>(&element, Bounded(bound)).is_err());
+        assert!(cdr::serialize::<_, _, PlCdrLe>(&element, Bounded(bound)).is_err());
+        {
+            let mut buf = vec![0xff];
+            assert!(cdr::ser::serialize_data_into_vec::<_, _, BigEndian>(
+                &mut buf,
+                &element,
+                Bounded(bound)
+            )
+            .is_err());
+            assert_eq!(buf, vec![0xff]);
+        }
+        {
+            let mut buf = Vec::new();
+            match cdr::serialize_into_vec::<_, _, CdrBe>(&mut buf, &element, Bounded(bound)) {
+                Err(Error::SizeLimit) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            assert!(buf.is_empty());
+        }
         {
             let encoded = cdr::ser::serialize_data::<_, _, BigEndian>(&element, Infinite).unwrap();
             let mut encoded = encoded.as_slice();
             assert!(cdr::de::deserialize_data_from::<_, T, _, BigEndian>(
                 &mut encoded,
-                Bounded(bound as u64)
+                Bounded(bound)
             )
             .is_err());
         }
@@ -186,29 +222,29 @@ where
             let mut encoded = encoded.as_slice();
             assert!(cdr::de::deserialize_data_from::<_, T, _, LittleEndian>(
                 &mut encoded,
-                Bounded(bound as u64)
+                Bounded(bound)
             )
             .is_err());
         }
         {
             let encoded = cdr::serialize::<_, _, CdrBe>(&element, Infinite).unwrap();
             let mut encoded = encoded.as_slice();
-            assert!(cdr::deserialize_from::<_, T, _>(&mut encoded, Bounded(bound as u64)).is_err());
+            assert!(cdr::deserialize_from::<_, T, _>(&mut encoded, Bounded(bound)).is_err());
         }
         {
             let encoded = cdr::serialize::<_, _, CdrLe>(&element, Infinite).unwrap();
             let mut encoded = encoded.as_slice();
-            assert!(cdr::deserialize_from::<_, T, _>(&mut encoded, Bounded(bound as u64)).is_err());
+            assert!(cdr::deserialize_from::<_, T, _>(&mut encoded, Bounded(bound)).is_err());
         }
         {
             let encoded = cdr::serialize::<_, _, PlCdrBe>(&element, Infinite).unwrap();
             let mut encoded = encoded.as_slice();
-            assert!(cdr::deserialize_from::<_, T, _>(&mut encoded, Bounded(bound as u64)).is_err());
+            assert!(cdr::deserialize_from::<_, T, _>(&mut encoded, Bounded(bound)).is_err());
         }
         {
             let encoded = cdr::serialize::<_, _, PlCdrLe>(&element, Infinite).unwrap();
             let mut encoded = encoded.as_slice();
-            assert!(cdr::deserialize_from::<_, T, _>(&mut encoded, Bounded(bound as u64)).is_err());
+            assert!(cdr::deserialize_from::<_, T, _>(&mut encoded, Bounded(bound)).is_err());
         }
     } else {
         {
@@ -298,8 +334,8 @@ where
 
 #[test]
 fn test_octet() {
-    check(std::u8::MIN, Some(1));
-    check(std::u8::MAX, Some(1));
+    check(u8::MIN, Some(1));
+    check(u8::MAX, Some(1));
 }
 
 #[test]
@@ -310,50 +346,50 @@ fn test_char() {
 
 #[test]
 fn test_unsigned_short() {
-    check(std::u16::MIN, Some(2));
-    check(std::u16::MAX, Some(2));
+    check(u16::MIN, Some(2));
+    check(u16::MAX, Some(2));
 }
 
 #[test]
 fn test_short() {
-    check(std::i16::MIN, Some(2));
-    check(std::i16::MAX, Some(2));
+    check(i16::MIN, Some(2));
+    check(i16::MAX, Some(2));
 }
 
 #[test]
 fn test_unsigned_long() {
-    check(std::u32::MIN, Some(4));
-    check(std::u32::MAX, Some(4));
+    check(u32::MIN, Some(4));
+    check(u32::MAX, Some(4));
 }
 
 #[test]
 fn test_long() {
-    check(std::i32::MIN, Some(4));
-    check(std::i32::MAX, Some(4));
+    check(i32::MIN, Some(4));
+    check(i32::MAX, Some(4));
 }
 
 #[test]
 fn test_unsigned_long_long() {
-    check(std::u64::MIN, Some(8));
-    check(std::u64::MAX, Some(8));
+    check(u64::MIN, Some(8));
+    check(u64::MAX, Some(8));
 }
 
 #[test]
 fn test_long_long() {
-    check(std::i64::MIN, Some(8));
-    check(std::i64::MAX, Some(8));
+    check(i64::MIN, Some(8));
+    check(i64::MAX, Some(8));
 }
 
 #[test]
 fn test_float() {
-    check(std::f32::MIN, Some(4));
-    check(std::f32::MAX, Some(4));
+    check(f32::MIN, Some(4));
+    check(f32::MAX, Some(4));
 }
 
 #[test]
 fn test_double() {
-    check(std::f64::MIN, Some(8));
-    check(std::f64::MAX, Some(8));
+    check(f64::MIN, Some(8));
+    check(f64::MAX, Some(8));
 }
 
 #[test]
@@ -570,84 +606,72 @@ fn test_seq_in_seq() {
 }
 
 #[test]
-#[allow(const_err)]
 fn test_array_octet() {
     check([] as [u8; 0], Some(0));
     check([0u8, 1, 2], Some(3));
 }
 
 #[test]
-#[allow(const_err)]
 fn test_array_char() {
     check([] as [char; 0], Some(0));
     check(['a', 'b', 'c'], Some(3));
 }
 
 #[test]
-#[allow(const_err)]
 fn test_array_unsigned_short() {
     check([] as [u16; 0], Some(0));
     check([0u16, 1, 2], Some(6));
 }
 
 #[test]
-#[allow(const_err)]
 fn test_array_short() {
     check([] as [i16; 0], Some(0));
     check([0i16, 1, 2], Some(6));
 }
 
 #[test]
-#[allow(const_err)]
 fn test_array_unsigned_long() {
     check([] as [u32; 0], Some(0));
     check([0u32, 1, 2], Some(12));
 }
 
 #[test]
-#[allow(const_err)]
 fn test_array_long() {
     check([] as [i32; 0], Some(0));
     check([0i32, 1, 2], Some(12));
 }
 
 #[test]
-#[allow(const_err)]
 fn test_array_unsigned_long_long() {
     check([] as [u64; 0], Some(0));
     check([0u64, 1, 2], Some(24));
 }
 
 #[test]
-#[allow(const_err)]
 fn test_array_long_long() {
     check([] as [i64; 0], Some(0));
     check([0i64, 1, 2], Some(24));
 }
 
 #[test]
-#[allow(const_err)]
 fn test_array_float() {
     check([] as [f32; 0], Some(0));
     check([0f32, 1., 2.], Some(12));
 }
 
 #[test]
-#[allow(const_err)]
 fn test_array_double() {
     check([] as [f64; 0], Some(0));
     check([0f64, 1., 2.], Some(24));
 }
 
 #[test]
-#[allow(const_err)]
 fn test_array_bool() {
     check([] as [bool; 0], Some(0));
     check([false, true, false], Some(3));
 }
 
 #[test]
-#[allow(const_err)]
 fn test_array_string() {
     check([] as [String; 0], Some(0));
     check(
@@ -657,7 +681,6 @@ fn test_array_string() {
 }
 
 #[test]
-#[allow(const_err)]
 fn test_array_in_array() {
     check([[]] as [[usize; 0]; 1], Some(0));
     check([[3.14f64, 2.71, 1.41], [1.73, 2.23, 2.44]], Some(48));
@@ -786,7 +809,7 @@ fn test_unsupported() {
 
     fn check_error_kind<T: Debug>(res: Result<T>) {
         match res {
-            Err(e) => match e {
+            Err(e) => match e.root() {
                 Error::TypeNotSupported => (),
                 e => panic!("unexpected error kind: {}", e),
             },
@@ -812,12 +835,2937 @@ fn test_unsupported() {
     ));
 
     check_error_kind(cdr::de::deserialize_data::<Option<usize>, BigEndian>(
-        &Vec::new().as_slice(),
+        Vec::new().as_slice(),
     ));
     check_error_kind(
-        cdr::de::deserialize_data::<HashMap<usize, usize>, BigEndian>(&Vec::new().as_slice()),
+        cdr::de::deserialize_data::<HashMap<usize, usize>, BigEndian>(Vec::new().as_slice()),
     );
     check_error_kind(
-        cdr::de::deserialize_data::<BTreeMap<usize, usize>, BigEndian>(&Vec::new().as_slice()),
+        cdr::de::deserialize_data::<BTreeMap<usize, usize>, BigEndian>(Vec::new().as_slice()),
+    );
+}
+
+#[test]
+fn test_deserializer_accessors() {
+    use cdr::Deserializer;
+    use serde::Deserialize;
+
+    let encoded = cdr::ser::serialize_data::<_, _, BigEndian>(&(1u8, 2u32, 3u8), Infinite).unwrap();
+    let mut deserializer = Deserializer::<_, _, BigEndian>::new(encoded.as_slice(), Bounded(10));
+    assert_eq!(deserializer.position(), 0);
+    assert_eq!(deserializer.remaining_limit(), Some(10));
+
+    assert_eq!(u8::deserialize(&mut deserializer).unwrap(), 1);
+    assert_eq!(u32::deserialize(&mut deserializer).unwrap(), 2);
+    assert_eq!(deserializer.position(), 8);
+    assert_eq!(deserializer.remaining_limit(), Some(2));
+    assert_eq!(deserializer.get_ref().len(), 1);
+
+    deserializer.reset_pos();
+    assert_eq!(deserializer.position(), 0);
+    assert_eq!(u8::deserialize(&mut deserializer).unwrap(), 3);
+    assert!(deserializer.into_inner().is_empty());
+}
+
+#[test]
+fn test_alignment_origin() {
+    use cdr::{Deserializer, Serializer};
+    use serde::{Deserialize, Serialize};
+
+    let value = (1u8, 2u16, 3u64);
+    for &(offset, max_alignment, size) in &[(0, 8, 16), (4, 8, 12), (0, 4, 12), (2, 4, 14)] {
+        let mut serializer =
+            Serializer::<_, LittleEndian>::with_alignment(Vec::new(), offset, max_alignment);
+        value.serialize(&mut serializer).unwrap();
+        assert_eq!(serializer.position(), offset + size);
+        let encoded = serializer.into_inner();
+        assert_eq!(encoded.len() as u64, size);
+
+        let mut deserializer = Deserializer::<_, _, LittleEndian>::with_alignment(
+            encoded.as_slice(),
+            Infinite,
+            offset,
+            max_alignment,
+        );
+        assert_eq!(
+            <(u8, u16, u64)>::deserialize(&mut deserializer).unwrap(),
+            value
+        );
+        assert!(deserializer.into_inner().is_empty());
+    }
+}
+
+#[test]
+fn test_packed() {
+    use cdr::{Deserializer, Serializer};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct S {
+        a: u8,
+        b: f64,
+        c: u16,
+        d: Vec<i32>,
+        e: String,
+    }
+
+    let value = S {
+        a: 1,
+        b: 2.5,
+        c: 3,
+        d: vec![-1, -2],
+        e: "hi".to_string(),
+    };
+    let size = 1 + 8 + 2 + 4 + 4 * 2 + 4 + 3;
+    assert_eq!(
+        cdr::size::calc_serialized_data_size_with_alignment(&value, 0, 1),
+        size
+    );
+
+    let mut serializer = Serializer::<_, BigEndian>::new_packed(Vec::new());
+    value.serialize(&mut serializer).unwrap();
+    let encoded = serializer.into_inner();
+    assert_eq!(encoded.len() as u64, size);
+    assert_eq!(&encoded[..11], &[1, 0x40, 4, 0, 0, 0, 0, 0, 0, 0, 3]);
+
+    let mut deserializer =
+        Deserializer::<_, _, BigEndian>::new_packed(encoded.as_slice(), Infinite);
+    assert_eq!(S::deserialize(&mut deserializer).unwrap(), value);
+}
+
+#[test]
+fn test_error_location() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Point {
+        x: u16,
+        valid: bool,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Msg {
+        id: u32,
+        points: Vec<Point>,
+    }
+
+    let msg = Msg {
+        id: 1,
+        points: vec![Point { x: 1, valid: true }, Point { x: 2, valid: false }],
+    };
+    let mut encoded = cdr::ser::serialize_data::<_, _, BigEndian>(&msg, Infinite).unwrap();
+    encoded[14] = 2;
+
+    let err = cdr::de::deserialize_data::<Msg, BigEndian>(&encoded).unwrap_err();
+    assert_eq!(err.path(), Some("points[1].valid"));
+    assert_eq!(err.offset(), Some(14));
+    match err {
+        Error::At { ref error, .. } => match **error {
+            Error::InvalidBoolEncoding(2) => (),
+            ref e => panic!("unexpected error kind: {}", e),
+        },
+        ref e => panic!("unexpected error kind: {}", e),
+    }
+    assert_eq!(
+        err.to_string(),
+        "expected 0 or 1, found 2 at points[1].valid (offset 14)"
+    );
+
+    let err = cdr::de::deserialize_data::<Msg, BigEndian>(&encoded[..13]).unwrap_err();
+    assert_eq!(err.path(), Some("points[1].x"));
+    assert_eq!(err.offset(), Some(12));
+}
+
+#[test]
+fn test_error_category() {
+    use cdr::Category;
+    use serde::Deserialize;
+
+    let encoded = cdr::ser::serialize_data::<_, _, BigEndian>(&(1u32, 2u32), Infinite).unwrap();
+
+    let err = cdr::de::deserialize_data::<(u32, u32), BigEndian>(&encoded[..6]).unwrap_err();
+    assert_eq!(err.category(), Category::Eof);
+    assert_eq!(*err.root(), Error::Eof);
+    assert_eq!(err.offset(), Some(4));
+    assert_eq!(err.clone(), err);
+
+    let err = cdr::de::deserialize_data_from::<_, (u32, u32), _, BigEndian>(
+        encoded.as_slice(),
+        Bounded(6),
+    )
+    .unwrap_err();
+    assert_eq!(err.category(), Category::LimitExceeded);
+    assert_eq!(*err.root(), Error::SizeLimit);
+
+    let err = cdr::de::deserialize_data::<bool, BigEndian>(&[2]).unwrap_err();
+    assert_eq!(err.category(), Category::InvalidData);
+    assert_eq!(*err.root(), Error::InvalidBoolEncoding(2));
+    assert_ne!(*err.root(), Error::InvalidBoolEncoding(3));
+    assert_eq!(err.offset(), Some(0));
+    assert_eq!(err.path(), None);
+    assert_eq!(err.to_string(), "expected 0 or 1, found 2 (offset 0)");
+
+    let err = cdr::de::deserialize_data::<u32, BigEndian>(&[0, 0]).unwrap_err();
+    assert_eq!(*err.root(), Error::Eof);
+    assert_eq!(err.offset(), Some(0));
+    let mut deserializer = cdr::Deserializer::<_, _, BigEndian>::new(&[1, 0, 0][..], Infinite);
+    u8::deserialize(&mut deserializer).unwrap();
+    let err = u16::deserialize(&mut deserializer).unwrap_err();
+    assert_eq!(*err.root(), Error::Eof);
+    assert_eq!(err.offset(), Some(2));
+
+    let err = cdr::ser::serialize_data::<_, _, BigEndian>(&Some(1u8), Infinite).unwrap_err();
+    assert_eq!(err.category(), Category::Unsupported);
+
+    let err = cdr::ser::serialize_data::<_, _, BigEndian>(&'\u{3b1}', Infinite).unwrap_err();
+    assert_eq!(err.category(), Category::InvalidData);
+
+    let mut buf = [0u8; 2];
+    let err = cdr::ser::serialize_data_into::<_, _, _, BigEndian>(
+        std::io::Cursor::new(&mut buf[..]),
+        &1u32,
+        Infinite,
+    )
+    .unwrap_err();
+    assert_eq!(err.category(), Category::Io);
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn test_bulk() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Bulk {
+        a: u8,
+        #[serde(with = "cdr::bulk")]
+        b: Vec<f64>,
+        #[serde(with = "cdr::bulk")]
+        c: Vec<u16>,
+        #[serde(with = "cdr::bulk")]
+        d: Vec<i32>,
+        #[serde(with = "cdr::bulk")]
+        e: Vec<u64>,
+        #[serde(with = "cdr::bulk")]
+        f: Vec<i8>,
+        g: u8,
+        #[serde(with = "cdr::bulk")]
+        h: Vec<f32>,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct PerElement {
+        a: u8,
+        b: Vec<f64>,
+        c: Vec<u16>,
+        d: Vec<i32>,
+        e: Vec<u64>,
+        f: Vec<i8>,
+        g: u8,
+        h: Vec<f32>,
+    }
+
+    let bulk = Bulk {
+        a: 1,
+        b: vec![1.5, -2.25],
+        c: vec![3, 4, 5],
+        d: vec![-6],
+        e: vec![],
+        f: vec![-7, 8],
+        g: 9,
+        h: vec![10.5; 3],
+    };
+    let per_element = PerElement {
+        a: 1,
+        b: vec![1.5, -2.25],
+        c: vec![3, 4, 5],
+        d: vec![-6],
+        e: vec![],
+        f: vec![-7, 8],
+        g: 9,
+        h: vec![10.5; 3],
+    };
+
+    let size = cdr::size::calc_serialized_data_size(&per_element);
+    assert_eq!(cdr::size::calc_serialized_data_size(&bulk), size);
+    check(bulk, Some(size));
+
+    let bulk = Bulk {
+        a: 1,
+        b: vec![1.5, -2.25],
+        c: vec![3, 4, 5],
+        d: vec![-6],
+        e: vec![],
+        f: vec![-7, 8],
+        g: 9,
+        h: vec![10.5; 3],
+    };
+    assert_eq!(
+        cdr::serialize::<_, _, CdrBe>(&bulk, Infinite).unwrap(),
+        cdr::serialize::<_, _, CdrBe>(&per_element, Infinite).unwrap()
+    );
+    assert_eq!(
+        cdr::serialize::<_, _, CdrLe>(&bulk, Infinite).unwrap(),
+        cdr::serialize::<_, _, CdrLe>(&per_element, Infinite).unwrap()
+    );
+    let encoded = cdr::serialize::<_, _, CdrBe>(&per_element, Infinite).unwrap();
+    assert_eq!(cdr::deserialize::<Bulk>(&encoded).unwrap(), bulk);
+
+    // A length beyond the data is not allocated before it is read.
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Doubles(#[serde(with = "cdr::bulk")] Vec<f64>);
+    let huge = [0, 1, 0, 0, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0];
+    assert_eq!(
+        cdr::deserialize::<Doubles>(&huge).unwrap_err().root(),
+        &Error::Eof
+    );
+    assert_eq!(
+        cdr::deserialize::<Vec<u8>>(&huge).unwrap_err().root(),
+        &Error::Eof
+    );
+    assert_eq!(
+        cdr::deserialize::<String>(&huge).unwrap_err().root(),
+        &Error::Eof
+    );
+}
+
+#[test]
+fn test_fixed_size() {
+    use cdr::CdrFixedSize;
+
+    fn check_fixed<T>(value: T)
+    where
+        T: CdrFixedSize + serde::Serialize,
+    {
+        assert!(T::IS_BOUNDED);
+        let size = cdr::size::calc_serialized_data_size(&value);
+        assert_eq!(T::MAX_SIZE as u64, size);
+        assert_eq!(
+            cdr::size::calc_serialized_data_size_fixed::<T>(),
+            Some(size)
+        );
+        assert_eq!(cdr::calc_serialized_size_fixed::<T>(), Some(size + 4));
+    }
+
+    check_fixed(());
+    check_fixed(true);
+    check_fixed('a');
+    check_fixed(1u8);
+    check_fixed(1i16);
+    check_fixed(1u32);
+    check_fixed(1f32);
+    check_fixed(1i64);
+    check_fixed(1f64);
+    check_fixed([1u8, 2, 3]);
+    check_fixed([[1u16, 2, 3], [4, 5, 6]]);
+    check_fixed([(1u8, 2u64); 5]);
+    check_fixed([0u64; 0]);
+    check_fixed((true, 1u64, 'z', 2.71f32));
+    check_fixed((1u8, (2u16, 3u8), [4u32; 3], 5u64));
+    check_fixed((1u64, 2u8));
+    assert_eq!(<(u64, u8)>::MAX_SIZE, 9);
+    assert_eq!(<(u8, (u64, u8))>::MAX_SIZE, 17);
+    assert_eq!(<((u64, u8), u64)>::MAX_SIZE, 24);
+
+    assert!(!String::IS_BOUNDED);
+    assert!(!<Vec<u8>>::IS_BOUNDED);
+    assert!(!<(u8, String)>::IS_BOUNDED);
+    assert!(<[String; 0]>::IS_BOUNDED);
+    assert_eq!(<[Vec<u8>; 2]>::MAX_SIZE, usize::MAX);
+    assert_eq!(cdr::size::calc_serialized_data_size_fixed::<String>(), None);
+    assert_eq!(
+        cdr::size::calc_serialized_data_size_fixed::<cdr::BoundedString<4>>(),
+        None
+    );
+}
+
+#[test]
+fn test_max_serialized_size() {
+    use cdr::{BoundedSeq, BoundedString};
+    use std::convert::TryFrom;
+
+    type T = (
+        u8,
+        BoundedString<5>,
+        u64,
+        BoundedSeq<(u8, u16), 3>,
+        u32,
+        BoundedSeq<BoundedString<2>, 2>,
+        u16,
+    );
+
+    let mut max = 0;
+    for s in 0..=5 {
+        for n in 0..=3 {
+            for m in 0..=2 {
+                for k in 0..=2 {
+                    let value: T = (
+                        1,
+                        BoundedString::try_from("x".repeat(s)).unwrap(),
+                        2,
+                        BoundedSeq::try_from(vec![(3, 4); n]).unwrap(),
+                        5,
+                        BoundedSeq::try_from(vec![
+                            BoundedString::try_from("y".repeat(k)).unwrap();
+                            m
+                        ])
+                        .unwrap(),
+                        6,
+                    );
+                    let size = cdr::size::calc_serialized_data_size(&value);
+                    check(value, Some(size));
+                    max = std::cmp::max(max, size);
+                }
+            }
+        }
+    }
+    assert_eq!(cdr::size::max_serialized_data_size::<T>(), Some(max));
+    assert_eq!(
+        cdr::max_serialized_size::<T>(),
+        Some(max + ENCAPSULATION_HEADER_SIZE)
+    );
+
+    assert_eq!(cdr::size::max_serialized_data_size::<[u16; 3]>(), Some(6));
+    assert_eq!(
+        cdr::size::max_serialized_data_size::<BoundedSeq<u64, 0>>(),
+        Some(4)
+    );
+    assert_eq!(
+        cdr::size::max_serialized_data_size::<BoundedSeq<u64, 1000>>(),
+        Some(8 + 8 * 1000)
+    );
+    assert_eq!(cdr::size::max_serialized_data_size::<String>(), None);
+    assert_eq!(
+        cdr::size::max_serialized_data_size::<BoundedSeq<String, 3>>(),
+        None
+    );
+
+    assert!(BoundedString::<2>::try_from("abc").is_err());
+    assert!(BoundedSeq::<u8, 2>::try_from(vec![1, 2, 3]).is_err());
+    let encoded =
+        cdr::ser::serialize_data::<_, _, BigEndian>(&"abc".to_string(), Infinite).unwrap();
+    assert_eq!(
+        cdr::de::deserialize_data::<BoundedString<2>, BigEndian>(&encoded)
+            .unwrap_err()
+            .root()
+            .to_string(),
+        "invalid length 3, expected at most 2 bytes"
+    );
+    let elements = cdr::ser::serialize_data::<_, _, BigEndian>(&vec![1u8, 2, 3], Infinite).unwrap();
+    assert_eq!(
+        cdr::de::deserialize_data::<BoundedSeq<u8, 2>, BigEndian>(&elements)
+            .unwrap_err()
+            .root()
+            .to_string(),
+        "invalid length 3, expected at most 2 elements"
+    );
+    assert_eq!(
+        &*cdr::de::deserialize_data::<BoundedString<3>, BigEndian>(&encoded).unwrap(),
+        "abc"
+    );
+}
+
+#[test]
+fn test_view() {
+    use cdr::{view::View, BoundedString, CdrFixedSize};
+
+    #[derive(Serialize)]
+    struct Scan {
+        seq: u8,
+        stamp: (i32, u32),
+        frame_id: String,
+        data: Vec<u8>,
+        ranges: Vec<f64>,
+        intensity: u16,
+    }
+
+    let scan = Scan {
+        seq: 7,
+        stamp: (-1, 2),
+        frame_id: "base_link".to_string(),
+        data: vec![1, 2, 3],
+        ranges: vec![0.5, 1.5],
+        intensity: 9,
+    };
+
+    for encoded in [
+        cdr::serialize::<_, _, CdrBe>(&scan, Infinite).unwrap(),
+        cdr::serialize::<_, _, CdrLe>(&scan, Infinite).unwrap(),
+    ] {
+        let view = View::new(&encoded).unwrap();
+        assert_eq!(view.is_little_endian(), encoded[1] == 1);
+
+        let mut cursor = view.cursor();
+        assert_eq!(cursor.read::<u8>().unwrap(), 7);
+        assert_eq!(cursor.read::<(i32, u32)>().unwrap(), (-1, 2));
+        assert_eq!(cursor.position(), 12);
+        assert_eq!(cursor.read_str().unwrap(), "base_link");
+        assert_eq!(cursor.read_bytes().unwrap(), &[1, 2, 3]);
+        assert_eq!(cursor.read::<Vec<f64>>().unwrap(), vec![0.5, 1.5]);
+        assert_eq!(cursor.read::<u16>().unwrap(), 9);
+        assert_eq!(cursor.position(), view.data().len());
+        assert_eq!(*cursor.read::<u8>().unwrap_err().root(), Error::Eof);
+
+        let mut cursor = view.cursor();
+        cursor.skip::<u8>().unwrap();
+        cursor.skip::<(i32, u32)>().unwrap();
+        cursor.skip::<String>().unwrap();
+        cursor.skip::<Vec<u8>>().unwrap();
+        cursor.skip::<Vec<f64>>().unwrap();
+        assert_eq!(cursor.read::<u16>().unwrap(), 9);
+
+        let mut cursor = view.cursor_at(12);
+        assert_eq!(cursor.read_str().unwrap(), "base_link");
+    }
+
+    assert_eq!(View::new(&[0, 0, 0]).unwrap_err(), Error::Eof);
+    assert_eq!(
+        View::new(&[0, 4, 0, 0]).unwrap_err(),
+        Error::InvalidEncapsulation
+    );
+    let view = View::from_data(&[0, 0, 0, 8, b'a', 0], false);
+    assert_eq!(*view.cursor().read_str().unwrap_err().root(), Error::Eof);
+    let view = View::from_data(&[0, 0, 0, 2, b'a', b'b'], false);
+    assert_eq!(
+        *view.cursor().read_str().unwrap_err().root(),
+        Error::InvalidCharEncoding
+    );
+    let view = View::from_data(&[1, 2, 3], false);
+    assert_eq!(*view.cursor().skip::<u64>().unwrap_err().root(), Error::Eof);
+    let view = View::from_data(&[0, 0, 0, 9, b'a', 0], false);
+    assert_eq!(
+        *view.cursor().skip::<String>().unwrap_err().root(),
+        Error::Eof
+    );
+    let view = View::from_data(&[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0], false);
+    assert_eq!(
+        *view.cursor().skip::<Vec<u64>>().unwrap_err().root(),
+        Error::Eof
+    );
+
+    // XCDR2 aligns 8-byte members to 4 bytes.
+    let mut encoded = vec![0, 7, 0, 0, 1, 0, 0, 0];
+    encoded.extend_from_slice(&2u64.to_le_bytes());
+    encoded.extend_from_slice(&[2, 0, 0, 0]);
+    encoded.extend_from_slice(&3u64.to_le_bytes());
+    encoded.extend_from_slice(&4u64.to_le_bytes());
+    encoded.extend_from_slice(&[5, 0]);
+    let view = View::new(&encoded).unwrap();
+    let mut cursor = view.cursor();
+    cursor.skip::<(u8, u64)>().unwrap();
+    assert_eq!(cursor.position(), 12);
+    cursor.skip::<Vec<u64>>().unwrap();
+    assert_eq!(cursor.position(), 32);
+    assert_eq!(cursor.read::<u16>().unwrap(), 5);
+
+    let layout = <Vec<(u8, u64)>>::LAYOUT;
+    assert!(layout.has_fixed_elements());
+    assert_eq!(layout.elements_size_at(4, 2), Some(28));
+    assert_eq!(layout.elements_size_at(0, 0), Some(0));
+    assert_eq!(
+        <Vec<(u8, u64)>>::LAYOUT_XCDR2.elements_size_at(4, 2),
+        Some(24)
+    );
+    assert!(!<Vec<String>>::LAYOUT.has_fixed_elements());
+
+    assert!(<(u8, u32, [u16; 2])>::IS_FIXED);
+    assert!(!<BoundedString<4>>::IS_FIXED);
+    assert!(!<Vec<u8>>::IS_FIXED);
+}
+
+#[test]
+fn test_value() {
+    use cdr::value::{Case, Member, StructDescriptor, UnionDescriptor};
+    use cdr::{BoundedSeq, BoundedString, TypeDescriptor as T, Value};
+    use std::convert::TryFrom;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Shape {
+        Empty,
+        Circle(f32),
+        Label(String),
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Inner {
+        flag: bool,
+        c: char,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Outer {
+        a: u8,
+        b: i64,
+        name: BoundedString<8>,
+        inner: Inner,
+        samples: Vec<u16>,
+        matrix: [[i32; 2]; 2],
+        shapes: BoundedSeq<Shape, 4>,
+        unit: (),
+    }
+
+    let shape_ty = T::Union(UnionDescriptor::from_variants(
+        "Shape",
+        vec![
+            Member::new("Empty", T::Unit),
+            Member::new("Circle", T::F32),
+            Member::new("Label", T::String),
+        ],
+    ));
+    let ty = T::Struct(StructDescriptor {
+        name: "Outer".to_string(),
+        members: vec![
+            Member::new("a", T::U8),
+            Member::new("b", T::I64),
+            Member::new("name", T::BoundedString(8)),
+            Member::new(
+                "inner",
+                T::Struct(StructDescriptor {
+                    name: "Inner".to_string(),
+                    members: vec![Member::new("flag", T::Bool), Member::new("c", T::Char)],
+                }),
+            ),
+            Member::new("samples", T::Sequence(Box::new(T::U16))),
+            Member::new(
+                "matrix",
+                T::Array(Box::new(T::Array(Box::new(T::I32), 2)), 2),
+            ),
+            Member::new("shapes", T::BoundedSequence(Box::new(shape_ty), 4)),
+            Member::new("unit", T::Unit),
+        ],
+    });
+
+    let outer = Outer {
+        a: 1,
+        b: -2,
+        name: BoundedString::try_from("abc").unwrap(),
+        inner: Inner { flag: true, c: 'x' },
+        samples: vec![3, 4, 5],
+        matrix: [[6, 7], [8, 9]],
+        shapes: BoundedSeq::try_from(vec![
+            Shape::Circle(1.5),
+            Shape::Empty,
+            Shape::Label("l".to_string()),
+        ])
+        .unwrap(),
+        unit: (),
+    };
+    let value = Value::Struct(vec![
+        Value::U8(1),
+        Value::I64(-2),
+        Value::String("abc".to_string()),
+        Value::Struct(vec![Value::Bool(true), Value::Char('x')]),
+        Value::Sequence(vec![Value::U16(3), Value::U16(4), Value::U16(5)]),
+        Value::Array(vec![
+            Value::Array(vec![Value::I32(6), Value::I32(7)]),
+            Value::Array(vec![Value::I32(8), Value::I32(9)]),
+        ]),
+        Value::Sequence(vec![
+            Value::Union {
+                discriminator: Box::new(Value::U32(1)),
+                value: Box::new(Value::F32(1.5)),
+            },
+            Value::Union {
+                discriminator: Box::new(Value::U32(0)),
+                value: Box::new(Value::Unit),
+            },
+            Value::Union {
+                discriminator: Box::new(Value::U32(2)),
+                value: Box::new(Value::String("l".to_string())),
+            },
+        ]),
+        Value::Unit,
+    ]);
+    assert!(value.conforms_to(&ty));
+
+    let encoded = cdr::serialize::<_, _, CdrBe>(&outer, Infinite).unwrap();
+    assert_eq!(cdr::value::deserialize(&encoded, &ty).unwrap(), value);
+    assert_eq!(
+        cdr::value::serialize::<_, CdrBe>(&value, &ty, Infinite).unwrap(),
+        encoded
+    );
+    let encoded = cdr::serialize::<_, _, CdrLe>(&outer, Infinite).unwrap();
+    assert_eq!(cdr::value::deserialize(&encoded, &ty).unwrap(), value);
+    let encoded = cdr::value::serialize::<_, CdrLe>(&value, &ty, Infinite).unwrap();
+    assert_eq!(cdr::deserialize::<Outer>(&encoded).unwrap(), outer);
+    assert_eq!(
+        cdr::calc_serialized_size(&value),
+        cdr::calc_serialized_size(&outer)
+    );
+
+    assert!(!Value::String("too long!".to_string()).conforms_to(&T::BoundedString(8)));
+    assert!(!Value::Array(vec![Value::I32(1)]).conforms_to(&T::Array(Box::new(T::I32), 2)));
+    assert!(!Value::I32(1).conforms_to(&T::U32));
+    assert!(cdr::value::serialize::<_, CdrBe>(&Value::I32(1), &T::U32, Infinite).is_err());
+
+    let encoded = cdr::serialize::<_, _, CdrBe>(&"too long!", Infinite).unwrap();
+    assert!(cdr::value::deserialize(&encoded, &T::BoundedString(8)).is_err());
+    let encoded = cdr::serialize::<_, _, CdrBe>(&vec![1u8, 2, 3], Infinite).unwrap();
+    assert!(cdr::value::deserialize(&encoded, &T::BoundedSequence(Box::new(T::U8), 2)).is_err());
+    let encoded = cdr::serialize::<_, _, CdrBe>(&Shape::Label("l".to_string()), Infinite).unwrap();
+    let ty = T::Union(UnionDescriptor::from_variants(
+        "Shape",
+        vec![Member::new("Empty", T::Unit)],
+    ));
+    assert!(cdr::value::deserialize(&encoded, &ty).is_err());
+
+    // union U switch (short) { case -1: case 3: long a; case 7: octet b; default: double c; };
+    let desc = UnionDescriptor {
+        name: "U".to_string(),
+        discriminator: Box::new(T::I16),
+        cases: vec![
+            Case {
+                labels: vec![-1, 3],
+                member: Member::new("a", T::I32),
+            },
+            Case {
+                labels: vec![7],
+                member: Member::new("b", T::U8),
+            },
+        ],
+        default: Some(Box::new(Member::new("c", T::F64))),
+    };
+    assert_eq!(desc.member(-1).unwrap().name, "a");
+    assert_eq!(desc.member(100).unwrap().name, "c");
+    assert_eq!(desc.label_of("b"), Some(7));
+    assert_eq!(desc.label_of("c"), Some(0));
+    assert_eq!(desc.discriminator_value(-1), Some(Value::I16(-1)));
+    assert_eq!(desc.discriminator_value(1 << 20), None);
+    let ty = T::Union(desc);
+    let union = |discriminator, value| Value::Union {
+        discriminator: Box::new(discriminator),
+        value: Box::new(value),
+    };
+    for (value, data) in [
+        (
+            union(Value::I16(3), Value::I32(5)),
+            vec![0, 3, 0, 0, 0, 0, 0, 5],
+        ),
+        (union(Value::I16(7), Value::U8(9)), vec![0, 7, 9]),
+        (
+            union(Value::I16(100), Value::F64(1.5)),
+            vec![0, 100, 0, 0, 0, 0, 0, 0, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0],
+        ),
+    ] {
+        let encoded = cdr::value::serialize::<_, CdrBe>(&value, &ty, Infinite).unwrap();
+        assert_eq!(&encoded[4..], &data[..]);
+        assert_eq!(cdr::value::deserialize(&encoded, &ty).unwrap(), value);
+    }
+    assert!(!union(Value::I16(3), Value::U8(5)).conforms_to(&ty));
+    assert!(!union(Value::I32(3), Value::I32(5)).conforms_to(&ty));
+
+    // union B switch (boolean) { case TRUE: long x; };
+    let ty = T::Union(UnionDescriptor {
+        name: "B".to_string(),
+        discriminator: Box::new(T::Bool),
+        cases: vec![Case {
+            labels: vec![1],
+            member: Member::new("x", T::I32),
+        }],
+        default: None,
+    });
+    let value = union(Value::Bool(true), Value::I32(2));
+    let encoded = cdr::value::serialize::<_, CdrLe>(&value, &ty, Infinite).unwrap();
+    assert_eq!(&encoded[4..], &[1, 0, 0, 0, 2, 0, 0, 0]);
+    assert_eq!(cdr::value::deserialize(&encoded, &ty).unwrap(), value);
+    assert!(!union(Value::Bool(false), Value::Unit).conforms_to(&ty));
+    assert!(cdr::value::deserialize(&[0, 1, 0, 0, 0], &ty).is_err());
+
+    // union C switch (char) { case 'a': octet v; };
+    let ty = T::Union(UnionDescriptor {
+        name: "C".to_string(),
+        discriminator: Box::new(T::Char),
+        cases: vec![Case {
+            labels: vec![i64::from(b'a')],
+            member: Member::new("v", T::U8),
+        }],
+        default: None,
+    });
+    let value = union(Value::Char('a'), Value::U8(7));
+    let encoded = cdr::value::serialize::<_, CdrLe>(&value, &ty, Infinite).unwrap();
+    assert_eq!(&encoded[4..], b"a\x07");
+    assert_eq!(cdr::value::deserialize(&encoded, &ty).unwrap(), value);
+
+    // union E switch (Color) { case GREEN: float f; };
+    let color = T::Union(UnionDescriptor::from_variants(
+        "Color",
+        vec![Member::new("RED", T::Unit), Member::new("GREEN", T::Unit)],
+    ));
+    let desc = UnionDescriptor {
+        name: "E".to_string(),
+        discriminator: Box::new(color),
+        cases: vec![Case {
+            labels: vec![1],
+            member: Member::new("f", T::F32),
+        }],
+        default: None,
+    };
+    let green = union(Value::U32(1), Value::Unit);
+    assert_eq!(desc.discriminator_value(1), Some(green.clone()));
+    assert_eq!(green.label(), Some(1));
+    let ty = T::Union(desc);
+    let value = union(green, Value::F32(0.5));
+    let encoded = cdr::value::serialize::<_, CdrLe>(&value, &ty, Infinite).unwrap();
+    assert_eq!(
+        encoded,
+        cdr::serialize::<_, _, CdrLe>(&(1u32, 0.5f32), Infinite).unwrap()
+    );
+    assert_eq!(cdr::value::deserialize(&encoded, &ty).unwrap(), value);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json() {
+    use cdr::transcode;
+    use cdr::value::{Member, StructDescriptor, UnionDescriptor};
+    use cdr::TypeDescriptor as T;
+    use serde_json::json;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Mode {
+        Idle,
+        Gain(f64),
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Reading {
+        id: u32,
+        label: String,
+        data: Vec<u8>,
+        modes: Vec<Mode>,
+        value: f32,
+    }
+
+    let reading = Reading {
+        id: 7,
+        label: "a".to_string(),
+        data: vec![1, 2],
+        modes: vec![Mode::Idle, Mode::Gain(0.5)],
+        value: f32::NAN,
+    };
+    let expected = json!({
+        "id": 7,
+        "label": "a",
+        "data": [1, 2],
+        "modes": ["Idle", { "Gain": 0.5 }],
+        "value": "NaN",
+    });
+
+    let ty = T::Struct(StructDescriptor {
+        name: "Reading".to_string(),
+        members: vec![
+            Member::new("id", T::U32),
+            Member::new("label", T::BoundedString(4)),
+            Member::new("data", T::Sequence(Box::new(T::U8))),
+            Member::new(
+                "modes",
+                T::Sequence(Box::new(T::Union(UnionDescriptor::from_variants(
+                    "Mode",
+                    vec![Member::new("Idle", T::Unit), Member::new("Gain", T::F64)],
+                )))),
+            ),
+            Member::new("value", T::F32),
+        ],
+    });
+
+    let encoded = cdr::serialize::<_, _, CdrLe>(&reading, Infinite).unwrap();
+    let json = transcode::dynamic_to_json(&encoded, &ty).unwrap();
+    assert_eq!(json, expected);
+    assert_eq!(
+        transcode::dynamic_from_json::<_, CdrLe>(&json, &ty, Infinite).unwrap(),
+        encoded
+    );
+
+    assert_eq!(transcode::to_json::<Reading>(&encoded).unwrap(), expected);
+    let injected = transcode::from_json::<Reading, _, CdrLe>(&expected, Infinite).unwrap();
+    assert!(cdr::deserialize::<Reading>(&injected)
+        .unwrap()
+        .value
+        .is_nan());
+    let mut typed = expected.clone();
+    typed["modes"][1] = json!({ "Gain": "-Infinity" });
+    let encoded = transcode::from_json::<Reading, _, CdrLe>(&typed, Infinite).unwrap();
+    assert_eq!(
+        cdr::deserialize::<Reading>(&encoded).unwrap().modes[1],
+        Mode::Gain(f64::NEG_INFINITY)
+    );
+    assert_eq!(transcode::to_json::<Reading>(&encoded).unwrap(), typed);
+    typed["value"] = json!(null);
+    assert!(transcode::from_json::<Reading, _, CdrLe>(&typed, Infinite).is_err());
+    typed["value"] = json!("Inf");
+    assert!(transcode::from_json::<Reading, _, CdrLe>(&typed, Infinite).is_err());
+    typed["modes"][1] = json!({ "Gain": 0.5 });
+    typed["value"] = json!(1.5);
+    let encoded = transcode::from_json::<Reading, _, CdrBe>(&typed, Infinite).unwrap();
+    assert_eq!(cdr::deserialize::<Reading>(&encoded).unwrap().value, 1.5);
+    assert_eq!(transcode::to_json::<Reading>(&encoded).unwrap(), typed);
+
+    for (v, json) in [
+        (f64::INFINITY, json!("Infinity")),
+        (f64::NEG_INFINITY, json!("-Infinity")),
+        (-0.25, json!(-0.25)),
+    ] {
+        let value = cdr::Value::F64(v);
+        assert_eq!(transcode::value_to_json(&value, &T::F64).unwrap(), json);
+        assert_eq!(transcode::value_from_json(&json, &T::F64).unwrap(), value);
+    }
+
+    let mut invalid = expected.clone();
+    invalid["modes"][1] = json!({ "Gain": "high" });
+    assert_eq!(
+        transcode::value_from_json(&invalid, &ty).unwrap_err(),
+        Error::Custom("expected a number, found a string at modes[1].Gain".to_string())
+    );
+    invalid["modes"][1] = json!("Turbo");
+    assert!(transcode::value_from_json(&invalid, &ty).is_err());
+    let mut invalid = expected.clone();
+    invalid["label"] = json!("too long");
+    assert!(transcode::value_from_json(&invalid, &ty).is_err());
+    invalid["label"] = json!("a");
+    invalid["extra"] = json!(1);
+    assert!(transcode::value_from_json(&invalid, &ty).is_err());
+    invalid.as_object_mut().unwrap().remove("extra");
+    invalid.as_object_mut().unwrap().remove("id");
+    assert!(transcode::value_from_json(&invalid, &ty).is_err());
+    assert!(transcode::value_from_json(&json!(256), &T::U8).is_err());
+    assert!(transcode::value_from_json(&json!(-1), &T::U64).is_err());
+    assert_eq!(
+        transcode::value_from_json(&json!(u64::MAX), &T::U64).unwrap(),
+        cdr::Value::U64(u64::MAX)
+    );
+}
+
+#[test]
+fn test_reencode() {
+    use cdr::value::{Member, StructDescriptor, UnionDescriptor};
+    use cdr::{EncapsulationKind, TypeDescriptor as T};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Frame {
+        id: u8,
+        stamp: f64,
+        name: String,
+        samples: Vec<u16>,
+        shape: Shape,
+        #[serde(with = "cdr::bulk")]
+        ranges: Vec<f64>,
+        tail: i32,
+    }
+
+    let frame = Frame {
+        id: 1,
+        stamp: 2.5,
+        name: "abc".to_string(),
+        samples: vec![3, 4, 5],
+        shape: Shape::Circle(-1.0),
+        ranges: vec![0.5, 1.5],
+        tail: -6,
+    };
+    let ty = T::Struct(StructDescriptor {
+        name: "Frame".to_string(),
+        members: vec![
+            Member::new("id", T::U8),
+            Member::new("stamp", T::F64),
+            Member::new("name", T::String),
+            Member::new("samples", T::Sequence(Box::new(T::U16))),
+            Member::new(
+                "shape",
+                T::Union(UnionDescriptor::from_variants(
+                    "Shape",
+                    vec![Member::new("Empty", T::Unit), Member::new("Circle", T::F64)],
+                )),
+            ),
+            Member::new("ranges", T::Sequence(Box::new(T::F64))),
+            Member::new("tail", T::I32),
+        ],
+    });
+
+    let kinds = [
+        EncapsulationKind::CdrBe,
+        EncapsulationKind::CdrLe,
+        EncapsulationKind::Cdr2Be,
+        EncapsulationKind::Cdr2Le,
+    ];
+    let be = cdr::serialize::<_, _, CdrBe>(&frame, Infinite).unwrap();
+    let le = cdr::serialize::<_, _, CdrLe>(&frame, Infinite).unwrap();
+    assert_eq!(
+        cdr::reencode_dynamic(&be, &ty, EncapsulationKind::CdrLe).unwrap(),
+        le
+    );
+    assert_eq!(
+        cdr::reencode_dynamic(&le, &ty, EncapsulationKind::CdrBe).unwrap(),
+        be
+    );
+    for &source in &kinds {
+        let encoded = cdr::reencode_dynamic(&be, &ty, source).unwrap();
+        assert_eq!(&encoded[..2], &source.id());
+        assert_eq!(cdr::deserialize::<Frame>(&encoded).unwrap(), frame);
+        for &target in &kinds {
+            let reencoded = cdr::reencode_dynamic(&encoded, &ty, target).unwrap();
+            assert_eq!(cdr::deserialize::<Frame>(&reencoded).unwrap(), frame);
+        }
+    }
+
+    // XCDR2 aligns 8-byte primitives to 4 bytes
+    let xcdr2 = cdr::reencode_dynamic(&be, &ty, EncapsulationKind::Cdr2Be).unwrap();
+    assert_eq!(&xcdr2[4..16], &[1, 0, 0, 0, 64, 4, 0, 0, 0, 0, 0, 0]);
+    assert!(xcdr2.len() < be.len());
+
+    // The options are carried over, but not the padding bits.
+    let mut options = be.clone();
+    options[2..4].copy_from_slice(&[0x12, 0x37]);
+    let reencoded = cdr::reencode_dynamic(&options, &ty, EncapsulationKind::CdrLe).unwrap();
+    assert_eq!(&reencoded[..4], &[0, 1, 0x12, 0x34]);
+    assert_eq!(&reencoded[4..], &le[4..]);
+
+    for kind in [EncapsulationKind::PlCdrBe, EncapsulationKind::PlCdrLe] {
+        assert!(cdr::reencode_dynamic(&be, &ty, kind).is_err());
+        let mut pl = be.clone();
+        pl[..2].copy_from_slice(&kind.id());
+        assert!(cdr::reencode_dynamic(&pl, &ty, EncapsulationKind::CdrLe).is_err());
+    }
+
+    assert_eq!(
+        cdr::reencode_dynamic(&[0, 9, 0, 0], &ty, EncapsulationKind::CdrLe),
+        Err(Error::InvalidEncapsulation)
+    );
+    assert_eq!(
+        *cdr::reencode_dynamic(&be[..20], &ty, EncapsulationKind::CdrLe)
+            .unwrap_err()
+            .root(),
+        Error::Eof
+    );
+    assert!(cdr::reencode_dynamic(
+        &be,
+        &T::Array(Box::new(T::U64), 100),
+        EncapsulationKind::CdrLe
+    )
+    .is_err());
+
+    // The typed form converts through the descriptor of the type.
+    let pair = cdr::serialize::<_, _, CdrBe>(&(1u8, vec![2u16, 3]), Infinite).unwrap();
+    assert_eq!(
+        cdr::reencode::<(u8, Vec<u16>)>(&pair, EncapsulationKind::CdrLe).unwrap(),
+        cdr::serialize::<_, _, CdrLe>(&(1u8, vec![2u16, 3]), Infinite).unwrap()
+    );
+
+    // XCDR2 delimits sequences and arrays of non-primitive elements, which is
+    // not converted.
+    let names = cdr::serialize::<_, _, CdrBe>(&vec!["a", "b"], Infinite).unwrap();
+    assert!(cdr::reencode::<Vec<String>>(&names, EncapsulationKind::CdrLe).is_ok());
+    assert!(cdr::reencode::<Vec<String>>(&names, EncapsulationKind::Cdr2Le).is_err());
+    let nested = T::Struct(StructDescriptor {
+        name: "Nested".to_string(),
+        members: vec![Member::new(
+            "points",
+            T::Array(
+                Box::new(T::Struct(StructDescriptor {
+                    name: "Point".to_string(),
+                    members: vec![Member::new("x", T::I32)],
+                })),
+                1,
+            ),
+        )],
+    });
+    let point = cdr::serialize::<_, _, CdrBe>(&[7i32], Infinite).unwrap();
+    assert!(cdr::reencode_dynamic(&point, &nested, EncapsulationKind::CdrLe).is_ok());
+    assert!(cdr::reencode_dynamic(&point, &nested, EncapsulationKind::Cdr2Be).is_err());
+}
+
+const DEMO_IDL: &str = r#"
+// Types shared by the IDL tests
+#include <builtin.idl>
+module demo {
+    const long N = 2 * (1 + 1) - 2;
+    const double SCALE = 1.5e3;
+    enum Mode { IDLE, RUN };
+    union Shape switch (Mode) {
+        case IDLE: octet none;
+        case RUN: double speed;
+    };
+    struct Base {
+        @key unsigned long id;
+    };
+    module nested {
+        struct Frame : Base {
+            double stamp;
+            string<8> name;
+            sequence<short, N> samples;
+            ::demo::Shape shape;
+            long grid[2][N], extra;
+        };
+    };
+    typedef sequence<nested::Frame> Frames;
+};
+"#;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+enum DemoShape {
+    Idle(u8),
+    Run(f64),
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct DemoFrame {
+    id: u32,
+    stamp: f64,
+    name: String,
+    samples: Vec<i16>,
+    shape: DemoShape,
+    grid: [[i32; 2]; 2],
+    extra: i32,
+}
+
+fn demo_frame() -> DemoFrame {
+    DemoFrame {
+        id: 7,
+        stamp: 2.5,
+        name: "cam".to_string(),
+        samples: vec![-1, 2],
+        shape: DemoShape::Run(0.5),
+        grid: [[1, 2], [3, 4]],
+        extra: 5,
+    }
+}
+
+#[test]
+fn test_idl() {
+    use cdr::value::{Case, Member, StructDescriptor, UnionDescriptor};
+    use cdr::TypeDescriptor as T;
+
+    let definitions = cdr::idl::parse(DEMO_IDL).unwrap();
+    assert_eq!(
+        definitions.names().collect::<Vec<_>>(),
+        [
+            "demo::Base",
+            "demo::Frames",
+            "demo::Mode",
+            "demo::Shape",
+            "demo::nested::Frame"
+        ]
+    );
+    let frame = definitions.get("::demo::nested::Frame").unwrap();
+    assert_eq!(
+        *frame,
+        T::Struct(StructDescriptor {
+            name: "demo::nested::Frame".to_string(),
+            members: vec![
+                Member::new("id", T::U32),
+                Member::new("stamp", T::F64),
+                Member::new("name", T::BoundedString(8)),
+                Member::new("samples", T::BoundedSequence(Box::new(T::I16), 2)),
+                Member::new(
+                    "shape",
+                    T::Union(UnionDescriptor {
+                        discriminator: Box::new(definitions.get("demo::Mode").unwrap().clone()),
+                        ..UnionDescriptor::from_variants(
+                            "demo::Shape",
+                            vec![Member::new("none", T::U8), Member::new("speed", T::F64)],
+                        )
+                    }),
+                ),
+                Member::new("grid", T::Array(Box::new(T::Array(Box::new(T::I32), 2)), 2)),
+                Member::new("extra", T::I32),
+            ],
+        })
+    );
+    assert_eq!(
+        *definitions.get("demo::Frames").unwrap(),
+        T::Sequence(Box::new(frame.clone()))
+    );
+
+    let encoded = cdr::serialize::<_, _, CdrLe>(&demo_frame(), Infinite).unwrap();
+    let value = cdr::value::deserialize(&encoded, frame).unwrap();
+    assert_eq!(
+        cdr::value::serialize::<_, CdrLe>(&value, frame, Infinite).unwrap(),
+        encoded
     );
+
+    let definitions = cdr::idl::parse(
+        r#"
+        module m {
+            const short NEG = -(1 + 1);
+            enum Color { RED, GREEN, BLUE };
+            union S switch (short) {
+                case NEG: case 3: long a;
+                case -8 / 2: octet b;
+                default: double c;
+            };
+            union B switch (boolean) { case TRUE: long x; };
+            union C switch (char) { case 'a': case '\n': octet v; };
+            @final union E switch (@key Color) {
+                case GREEN: case BLUE: float f;
+                default: case RED: octet o;
+            };
+        };
+        "#,
+    )
+    .unwrap();
+    let case = |labels: Vec<i64>, member| Case { labels, member };
+    let color = definitions.get("m::Color").unwrap().clone();
+    assert_eq!(
+        *definitions.get("m::S").unwrap(),
+        T::Union(UnionDescriptor {
+            name: "m::S".to_string(),
+            discriminator: Box::new(T::I16),
+            cases: vec![
+                case(vec![-2, 3], Member::new("a", T::I32)),
+                case(vec![-4], Member::new("b", T::U8)),
+            ],
+            default: Some(Box::new(Member::new("c", T::F64))),
+        })
+    );
+    assert_eq!(
+        *definitions.get("m::B").unwrap(),
+        T::Union(UnionDescriptor {
+            name: "m::B".to_string(),
+            discriminator: Box::new(T::Bool),
+            cases: vec![case(vec![1], Member::new("x", T::I32))],
+            default: None,
+        })
+    );
+    assert_eq!(
+        *definitions.get("m::C").unwrap(),
+        T::Union(UnionDescriptor {
+            name: "m::C".to_string(),
+            discriminator: Box::new(T::Char),
+            cases: vec![case(vec![97, 10], Member::new("v", T::U8))],
+            default: None,
+        })
+    );
+    assert_eq!(
+        *definitions.get("m::E").unwrap(),
+        T::Union(UnionDescriptor {
+            name: "m::E".to_string(),
+            discriminator: Box::new(color),
+            cases: vec![
+                case(vec![1, 2], Member::new("f", T::F32)),
+                case(vec![0], Member::new("o", T::U8)),
+            ],
+            default: Some(Box::new(Member::new("o", T::U8))),
+        })
+    );
+
+    for (source, line, column) in [
+        ("struct A { foo x; };", 1, 12),
+        ("module m { struct A { long x; }; ", 1, 34),
+        ("struct A { wstring x; };", 1, 12),
+        ("union U switch (float) { case 0: long a; };", 1, 17),
+        ("union U switch (octet) { case 256: long a; };", 1, 31),
+        (
+            "union U switch (long) { case 1: long a; case 1: long b; };",
+            1,
+            46,
+        ),
+        (
+            "union U switch (long) { default: long a; default: long b; };",
+            1,
+            42,
+        ),
+        (
+            "enum E { A }; union U switch (E) { case 1: long a; };",
+            1,
+            41,
+        ),
+        ("@mutable struct A { long x; };", 1, 2),
+        ("@appendable struct A { long x; };", 1, 2),
+        ("@extensibility(MUTABLE) struct A { long x; };", 1, 2),
+        ("struct A { @optional long x; };", 1, 13),
+        ("struct A { long x[-1]; };", 1, 19),
+        ("struct A {\n  long x[M];\n};", 2, 10),
+        ("interface I {};", 1, 1),
+        ("/* unterminated", 1, 16),
+    ] {
+        let e = cdr::idl::parse(source).unwrap_err();
+        assert_eq!((e.line(), e.column()), (line, column), "{}", e);
+    }
+
+    // Deep nesting is an error rather than a stack overflow.
+    let nested = |open: &str, inner: &str, close: &str, depth| {
+        format!("{}{}{}", open.repeat(depth), inner, close.repeat(depth))
+    };
+    for depth in [100, 100_000] {
+        let sequences = nested("sequence<", "long", ">", depth);
+        let modules = nested("module m { ", "struct A { long x; };", " };", depth);
+        let parentheses = nested("(", "1", ")", depth);
+        let negations = nested("-", "1", "", depth);
+        let sources = [
+            format!("struct A {{ {} x; }};", sequences),
+            modules,
+            format!("const long N = {};", parentheses),
+            format!("const long N = {};", negations),
+        ];
+        for source in sources.iter() {
+            assert_eq!(cdr::idl::parse(source).is_ok(), depth == 100, "{}", depth);
+        }
+    }
+}
+
+#[test]
+fn test_cdr_dump() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("cdr-dump-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let idl = dir.join("demo.idl");
+    std::fs::write(&idl, DEMO_IDL).unwrap();
+    let payload = dir.join("frame.cdr");
+    let encoded = cdr::serialize::<_, _, CdrBe>(&demo_frame(), Infinite).unwrap();
+    std::fs::write(&payload, &encoded).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cdr-dump"))
+        .arg("--idl")
+        .arg(&idl)
+        .arg("--type")
+        .arg("demo::nested::Frame")
+        .arg(&payload)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        r#"encapsulation: CdrBe (id 0x0000, options 0x0000)
+data: 68 bytes
+0x0000  00 00 00 07               id: unsigned long = 7
+0x0004  00 00 00 00               (padding)
+0x0008  40 04 00 00 00 00 00 00   stamp: double = 2.5
+0x0010  00 00 00 04 63 61 6d 00   name: string = "cam"
+0x0018  00 00 00 02               samples: sequence length = 2
+0x001c  ff ff                     samples[0]: short = -1
+0x001e  00 02                     samples[1]: short = 2
+0x0020  00 00 00 01               shape: demo::Shape = speed (1)
+0x0024  00 00 00 00               (padding)
+0x0028  3f e0 00 00 00 00 00 00   shape.speed: double = 0.5
+0x0030  00 00 00 01               grid[0][0]: long = 1
+0x0034  00 00 00 02               grid[0][1]: long = 2
+0x0038  00 00 00 03               grid[1][0]: long = 3
+0x003c  00 00 00 04               grid[1][1]: long = 4
+0x0040  00 00 00 05               extra: long = 5
+"#
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cdr-dump"))
+        .arg(&payload)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout.lines().nth(2),
+        Some("0x0000  00 00 00 07 00 00 00 00 40 04 00 00 00 00 00 00")
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cdr-dump"))
+        .arg("--idl")
+        .arg(&idl)
+        .arg("--type")
+        .arg("demo::Missing")
+        .arg(&payload)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cdr-dump"))
+        .arg("--bogus")
+        .arg(&payload)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("cdr-dump: unknown option --bogus"));
+
+    let mut list = cdr::parameter_list::ParameterList::new(true);
+    list.push(cdr::parameter_list::ParameterId(0x0005), &"Square")
+        .unwrap();
+    list.push(cdr::parameter_list::ParameterId(0x8001), &7u16)
+        .unwrap();
+    let parameters = dir.join("parameters.cdr");
+    std::fs::write(&parameters, list.to_bytes()).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_cdr-dump"))
+        .arg(&parameters)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        r#"encapsulation: PlCdrLe (id 0x0003, options 0x0000)
+data: 28 bytes
+0x0000  05 00 0c 00               parameter 0x0005, length 12
+0x0004  07 00 00 00 53 71 75 61   value
+0x000c  72 65 00 00
+0x0010  01 80 04 00               parameter 0x8001, length 4
+0x0014  07 00 00 00               value
+0x0018  01 00 00 00               sentinel
+"#
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cdr-dump"))
+        .arg("--idl")
+        .arg(&idl)
+        .arg("--type")
+        .arg("demo::nested::Frame")
+        .arg(&parameters)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_explain() {
+    use cdr::EncapsulationKind;
+
+    let frame = demo_frame();
+    let layout = |explanation: &cdr::Explanation| {
+        explanation
+            .fields
+            .iter()
+            .map(|f| (f.path.clone(), f.offset, f.padding, f.len))
+            .collect::<Vec<_>>()
+    };
+    let expected = |fields: &[(&str, usize, usize, usize)]| {
+        fields
+            .iter()
+            .map(|&(path, offset, padding, len)| (path.to_string(), offset, padding, len))
+            .collect::<Vec<_>>()
+    };
+
+    let explanation = cdr::explain(&frame, EncapsulationKind::CdrBe).unwrap();
+    assert_eq!(
+        explanation.bytes,
+        cdr::serialize::<_, _, CdrBe>(&frame, Infinite).unwrap()
+    );
+    assert_eq!(
+        layout(&explanation),
+        expected(&[
+            ("id", 4, 0, 4),
+            ("stamp", 12, 4, 8),
+            ("name", 20, 0, 8),
+            ("samples", 28, 0, 4),
+            ("samples[0]", 32, 0, 2),
+            ("samples[1]", 34, 0, 2),
+            ("shape", 36, 0, 4),
+            ("shape.Run", 44, 4, 8),
+            ("grid[0][0]", 52, 0, 4),
+            ("grid[0][1]", 56, 0, 4),
+            ("grid[1][0]", 60, 0, 4),
+            ("grid[1][1]", 64, 0, 4),
+            ("extra", 68, 0, 4),
+        ])
+    );
+
+    let explanation = cdr::explain(&frame, EncapsulationKind::Cdr2Le).unwrap();
+    assert_eq!(
+        cdr::deserialize::<DemoFrame>(&explanation.bytes).unwrap(),
+        frame
+    );
+    assert_eq!(
+        layout(&explanation),
+        expected(&[
+            ("id", 4, 0, 4),
+            ("stamp", 8, 0, 8),
+            ("name", 16, 0, 8),
+            ("samples", 24, 0, 4),
+            ("samples[0]", 28, 0, 2),
+            ("samples[1]", 30, 0, 2),
+            ("shape", 32, 0, 4),
+            ("shape.Run", 36, 0, 8),
+            ("grid[0][0]", 44, 0, 4),
+            ("grid[0][1]", 48, 0, 4),
+            ("grid[1][0]", 52, 0, 4),
+            ("grid[1][1]", 56, 0, 4),
+            ("extra", 60, 0, 4),
+        ])
+    );
+
+    #[derive(Serialize)]
+    struct Scan {
+        id: u8,
+        #[serde(with = "cdr::bulk")]
+        ranges: Vec<f64>,
+    }
+
+    let scan = Scan {
+        id: 1,
+        ranges: vec![0.5, 1.5],
+    };
+    let explanation = cdr::explain(&scan, EncapsulationKind::CdrLe).unwrap();
+    assert_eq!(
+        explanation.to_string(),
+        "\
+0x0000  00 01 00 00               (header)
+0x0004  01                        id
+0x0005  00 00 00                  (padding)
+0x0008  02 00 00 00 00 00 00 00   ranges
+0x0010  00 00 e0 3f 00 00 00 00
+0x0018  00 00 f8 3f
+"
+    );
+
+    let mut map = std::collections::HashMap::new();
+    map.insert(1u8, 2u8);
+    assert!(matches!(
+        cdr::explain(&map, EncapsulationKind::CdrBe),
+        Err(Error::TypeNotSupported)
+    ));
+}
+
+#[cfg(feature = "rtps")]
+#[test]
+fn test_rtps() {
+    use cdr::rtps::{self, KeyHash, SerializedPayload};
+    use cdr::{BoundedString, EncapsulationKind};
+    use std::convert::TryFrom;
+
+    let frame = demo_frame();
+    let payload = SerializedPayload::serialize(&frame, EncapsulationKind::CdrBe).unwrap();
+    let encoded = cdr::serialize::<_, _, CdrBe>(&frame, Infinite).unwrap();
+    assert_eq!(payload.padding(), 0);
+    assert_eq!(payload.to_bytes(), encoded);
+    assert_eq!(payload.deserialize::<DemoFrame>().unwrap(), frame);
+
+    let payload = SerializedPayload::serialize(&(1u16, 2u8), EncapsulationKind::Cdr2Le).unwrap();
+    let bytes = payload.to_bytes();
+    assert_eq!(bytes, [0, 7, 0, 1, 1, 0, 2, 0]);
+    assert_eq!(payload.len(), bytes.len());
+    let payload = SerializedPayload::from_bytes(&bytes).unwrap();
+    assert_eq!(payload.kind(), EncapsulationKind::Cdr2Le);
+    assert_eq!(payload.options(), [0, 1]);
+    assert_eq!(payload.data(), [1, 0, 2]);
+    assert_eq!(payload.deserialize::<(u16, u8)>().unwrap(), (1, 2));
+
+    assert!(matches!(
+        SerializedPayload::from_bytes(&[0, 1, 0, 3, 0]),
+        Err(Error::Eof)
+    ));
+    assert!(matches!(
+        SerializedPayload::from_bytes(&[0, 0x10, 0, 0]),
+        Err(Error::InvalidEncapsulation)
+    ));
+
+    // Keys of at most 16 bytes are padded with zeros.
+    assert_eq!(
+        rtps::key_hash(&(1u64, 2u64)).unwrap(),
+        KeyHash([0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2])
+    );
+    let name = BoundedString::<3>::try_from("ab").unwrap();
+    assert_eq!(
+        rtps::key_hash(&(7u32, name)).unwrap().to_string(),
+        "00000007000000036162000000000000"
+    );
+
+    // Longer keys are hashed even if the key at hand is shorter.
+    assert_eq!(
+        rtps::key_hash(&(7u32, "cam".to_string())).unwrap(),
+        KeyHash([47, 48, 15, 96, 248, 53, 2, 173, 67, 97, 14, 116, 31, 85, 94, 0])
+    );
+    assert_eq!(
+        rtps::key_hash(&(1u64, 2u64, 3u8)).unwrap(),
+        KeyHash([12, 71, 73, 13, 238, 35, 226, 142, 10, 32, 229, 177, 109, 94, 221, 59])
+    );
+
+    // XCDR2 aligns 8-byte primitives to 4 bytes, which can keep a key short.
+    let key = (1u32, 2u64, 3u32);
+    assert_eq!(
+        rtps::key_hash(&key).unwrap(),
+        KeyHash::from_serialized_key(
+            &[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 3],
+            None
+        )
+    );
+    assert_eq!(
+        rtps::key_hash_xcdr2(&key).unwrap(),
+        KeyHash([0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 3])
+    );
+}
+
+#[test]
+fn test_parameter_list() {
+    use cdr::parameter_list::{Parameter, ParameterId, ParameterList};
+
+    let mut list = ParameterList::new(false);
+    list.push(ParameterId(0x0005), "Square").unwrap();
+    list.push(ParameterId(0x002f), &(1i32, 7411u32)).unwrap();
+    list.push(ParameterId(0x002f), &(2i32, 7412u32)).unwrap();
+    list.push_raw(Parameter {
+        id: ParameterId(0x8001),
+        value: vec![0xaa],
+    })
+    .unwrap();
+    let bytes = list.to_bytes();
+    assert_eq!(
+        bytes,
+        [
+            0, 2, 0, 0, // PL_CDR_BE
+            0, 5, 0, 12, 0, 0, 0, 7, b'S', b'q', b'u', b'a', b'r', b'e', 0, 0, //
+            0, 0x2f, 0, 8, 0, 0, 0, 1, 0, 0, 0x1c, 0xf3, //
+            0, 0x2f, 0, 8, 0, 0, 0, 2, 0, 0, 0x1c, 0xf4, //
+            0x80, 1, 0, 4, 0xaa, 0, 0, 0, //
+            0, 1, 0, 0, // sentinel
+        ][..]
+    );
+
+    let parsed = ParameterList::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed, list);
+    assert!(!parsed.is_little_endian());
+    assert_eq!(
+        parsed.read::<String>(ParameterId(0x0005)).unwrap(),
+        Some("Square".to_string())
+    );
+    assert_eq!(
+        parsed.read_all::<(i32, u32)>(ParameterId(0x002f)).unwrap(),
+        [(1, 7411), (2, 7412)]
+    );
+    assert_eq!(
+        parsed.get(ParameterId(0x8001)).unwrap().value,
+        [0xaa, 0, 0, 0]
+    );
+    assert!(ParameterId(0x8001).is_vendor_specific());
+    assert!(!ParameterId(0x8001).must_understand());
+
+    // Padding parameters are skipped, and the sentinel ends the list.
+    let data = [
+        0, 0, 4, 0, 0, 0, 0, 0, // PID_PAD
+        0x0f, 0, 4, 0, 7, 0, 0, 0, // PID_DOMAIN_ID
+        1, 0, 0, 0, // sentinel
+        0xff, 0xff,
+    ];
+    let (parsed, len) = ParameterList::from_data(&data, true).unwrap();
+    assert_eq!(len, 20);
+    assert_eq!(parsed.parameters().len(), 1);
+    assert_eq!(parsed.read::<u32>(ParameterId(0x000f)).unwrap(), Some(7));
+
+    assert!(matches!(
+        ParameterList::from_data(&data[..16], true),
+        Err(Error::Eof)
+    ));
+    assert!(matches!(
+        ParameterList::from_data(&[0x0f, 0, 2, 0, 7, 0, 1, 0, 0, 0], true),
+        Err(Error::Custom(_))
+    ));
+    assert!(matches!(
+        ParameterList::from_data(&[0x0f, 0, 8, 0, 7, 0, 0, 0], true),
+        Err(Error::Eof)
+    ));
+    assert!(matches!(
+        ParameterList::from_bytes(&[0, 1, 0, 0, 1, 0, 0, 0]),
+        Err(Error::InvalidEncapsulation)
+    ));
+}
+
+#[cfg(feature = "rtps")]
+#[test]
+fn test_discovery() {
+    use cdr::parameter_list::{Parameter, ParameterId, ParameterList};
+    use cdr::rtps::discovery::{self, DiscoveryParameter as P, *};
+
+    #[rustfmt::skip]
+    let spdp = [
+        0, 3, 0, 0, // PL_CDR_LE
+        0x15, 0, 4, 0, 2, 5, 0, 0,
+        0x16, 0, 4, 0, 1, 15, 0, 0,
+        0x50, 0, 16, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 0, 0, 1, 0xc1,
+        0x31, 0, 24, 0, 1, 0, 0, 0, 0xf3, 0x1c, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 192, 168, 0, 2,
+        0x02, 0, 8, 0, 20, 0, 0, 0, 0, 0, 0, 0,
+        0x58, 0, 4, 0, 0x3f, 0x0c, 0, 0,
+        0x05, 0, 12, 0, 7, 0, 0, 0, b'S', b'q', b'u', b'a', b'r', b'e', 0, 0,
+        0x1a, 0, 12, 0, 2, 0, 0, 0, 0xff, 0xff, 0xff, 0x7f, 0xff, 0xff, 0xff, 0xff,
+        0x29, 0, 12, 0, 1, 0, 0, 0, 2, 0, 0, 0, b'a', 0, 0, 0,
+        0x01, 0x80, 4, 0, 0xaa, 0xbb, 0xcc, 0xdd,
+        1, 0, 0, 0,
+    ];
+    let parameters = discovery::parse(&ParameterList::from_bytes(&spdp).unwrap()).unwrap();
+    let guid = Guid {
+        prefix: [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12],
+        entity_id: [0, 0, 1, 0xc1],
+    };
+    assert_eq!(
+        parameters,
+        [
+            P::ProtocolVersion(ProtocolVersion { major: 2, minor: 5 }),
+            P::VendorId(VendorId([1, 15])),
+            P::ParticipantGuid(guid),
+            P::DefaultUnicastLocator(Locator::udpv4([192, 168, 0, 2], 7411)),
+            P::ParticipantLeaseDuration(Duration {
+                seconds: 20,
+                fraction: 0
+            }),
+            P::BuiltinEndpointSet(0x0c3f),
+            P::TopicName("Square".to_string()),
+            P::Reliability(Reliability {
+                kind: ReliabilityKind::Reliable,
+                max_blocking_time: Duration::INFINITE
+            }),
+            P::Partition(vec!["a".to_string()]),
+            P::Other(Parameter {
+                id: ParameterId(0x8001),
+                value: vec![0xaa, 0xbb, 0xcc, 0xdd]
+            }),
+        ]
+    );
+    assert_eq!(parameters[2].id(), pid::PARTICIPANT_GUID);
+    assert_eq!(guid.to_string(), "01020304.05060708.090a0b0c.000001c1");
+    assert_eq!(
+        discovery::to_parameter_list(&parameters, true)
+            .unwrap()
+            .to_bytes(),
+        &spdp[..]
+    );
+
+    // Unknown parameters that must be understood are rejected.
+    let mut list = ParameterList::new(true);
+    list.push(ParameterId(0x4fff), &0u32).unwrap();
+    assert!(matches!(discovery::parse(&list), Err(Error::Custom(_))));
+
+    let mut list = ParameterList::new(true);
+    list.push(pid::RELIABILITY, &(3u32, 0i32, 0u32)).unwrap();
+    assert!(discovery::parse(&list).is_err());
+}
+
+#[test]
+fn test_giop() {
+    use cdr::giop::{
+        self, Message, MessageHeader, MessageType, ReplyHeader, ReplyStatus, RequestHeader,
+        ServiceContext, SystemException, TargetAddress, Version,
+    };
+    use cdr::ior::{Ior, TaggedProfile};
+
+    let header = RequestHeader::new(5, b"key", "add");
+    let request = giop::encode_request(Version::V1_2, false, &header, &(2i32, 3i32)).unwrap();
+    let bytes = request.to_bytes();
+    assert_eq!(
+        bytes,
+        vec![
+            b'G', b'I', b'O', b'P', 1, 2, 0, 0, 0, 0, 0, 44, // message header
+            0, 0, 0, 5, // request_id
+            3, 0, 0, 0, // response_flags, reserved
+            0, 0, 0, 0, // addressing disposition, padding
+            0, 0, 0, 3, b'k', b'e', b'y', 0, // object key, padding
+            0, 0, 0, 4, b'a', b'd', b'd', 0, // operation
+            0, 0, 0, 0, // service_context
+            0, 0, 0, 0, // padding to 8
+            0, 0, 0, 2, 0, 0, 0, 3, // arguments
+        ]
+    );
+    let message = Message::read_from(&bytes[..]).unwrap();
+    assert_eq!(message, request);
+    let (decoded, mut body) = giop::decode_request(&message).unwrap();
+    assert_eq!(decoded, header);
+    assert_eq!(body.read::<(i32, i32)>().unwrap(), (2, 3));
+    assert!(body.remaining().is_empty());
+
+    // Requests without arguments are not padded.
+    let request = giop::encode_request(Version::V1_2, true, &header, &()).unwrap();
+    assert_eq!(request.header.message_size, 32);
+    let (decoded, body) = giop::decode_request(&request).unwrap();
+    assert_eq!(decoded, header);
+    assert!(body.remaining().is_empty());
+
+    // GIOP 1.0 and 1.1 put the service context first and carry a principal.
+    let header = RequestHeader {
+        request_id: 9,
+        response_flags: 1,
+        target: TargetAddress::Key(b"obj".to_vec()),
+        operation: "ping".to_string(),
+        requesting_principal: b"me".to_vec(),
+        service_context: vec![ServiceContext {
+            context_id: 1,
+            context_data: vec![0, 0, 0, 0],
+        }],
+    };
+    let request = giop::encode_request(Version::V1_0, true, &header, &1.5f64).unwrap();
+    assert_eq!(
+        request.body,
+        vec![
+            1, 0, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, // service_context
+            9, 0, 0, 0, // request_id
+            1, 0, 0, 0, // response_expected, padding
+            3, 0, 0, 0, b'o', b'b', b'j', 0, // object_key, padding
+            5, 0, 0, 0, b'p', b'i', b'n', b'g', 0, 0, 0, 0, // operation, padding
+            2, 0, 0, 0, b'm', b'e', // requesting_principal
+            0, 0, // padding to 8
+            0, 0, 0, 0, 0, 0, 0xf8, 0x3f, // arguments
+        ]
+    );
+    for &version in &[Version::V1_0, Version::V1_1] {
+        let request = giop::encode_request(version, false, &header, &1.5f64).unwrap();
+        let (decoded, mut body) = giop::decode_request(&request).unwrap();
+        assert_eq!(decoded, header);
+        assert_eq!(body.read::<f64>().unwrap(), 1.5);
+    }
+
+    // Only GIOP 1.2 addresses targets by profile or reference.
+    let header = RequestHeader {
+        target: TargetAddress::Reference {
+            selected_profile_index: 0,
+            ior: Ior {
+                type_id: "IDL:Echo:1.0".to_string(),
+                profiles: vec![TaggedProfile {
+                    tag: TaggedProfile::TAG_INTERNET_IOP,
+                    profile_data: vec![0, 1, 2],
+                }],
+            },
+        },
+        ..header
+    };
+    assert!(giop::encode_request(Version::V1_1, false, &header, &()).is_err());
+    let request = giop::encode_request(Version::V1_2, false, &header, &()).unwrap();
+    let (decoded, _) = giop::decode_request(&request).unwrap();
+    assert_eq!(decoded.target, header.target);
+    assert!(decoded.requesting_principal.is_empty());
+
+    for &version in &[Version::V1_0, Version::V1_1, Version::V1_2] {
+        let header = ReplyHeader {
+            request_id: 5,
+            reply_status: ReplyStatus::SystemException,
+            service_context: Vec::new(),
+        };
+        let exception = SystemException {
+            exception_id: "IDL:omg.org/CORBA/OBJECT_NOT_EXIST:1.0".to_string(),
+            minor_code_value: 0x4f4d_0001,
+            completion_status: 1,
+        };
+        let reply = giop::encode_reply(version, true, &header, &exception).unwrap();
+        assert_eq!(reply.header.message_type, MessageType::Reply);
+        let reply = Message::read_from(&reply.to_bytes()[..]).unwrap();
+        let (decoded, mut body) = giop::decode_reply(&reply).unwrap();
+        assert_eq!(decoded, header);
+        assert_eq!(body.read::<SystemException>().unwrap(), exception);
+        assert!(giop::decode_request(&reply).is_err());
+    }
+
+    let header = MessageHeader::parse(b"GIOP\x01\x01\x03\x07\x10\x00\x00\x00").unwrap();
+    assert_eq!(header.message_type, MessageType::Fragment);
+    assert!(header.is_little_endian());
+    assert!(header.has_more_fragments());
+    assert_eq!(header.message_size, 16);
+    assert!(MessageHeader::parse(b"GIOP\x01\x00\x00\x07\x00\x00\x00\x00").is_err());
+    assert!(MessageHeader::parse(b"GIOP\x01\x03\x00\x00\x00\x00\x00\x00").is_err());
+    assert!(MessageHeader::parse(b"IIOP\x01\x00\x00\x00\x00\x00\x00\x00").is_err());
+    assert!(matches!(
+        Message::read_from(&b"GIOP\x01\x00\x00\x05\x00\x00\x00\x04\x00"[..]),
+        Err(Error::Eof)
+    ));
+}
+
+#[test]
+fn test_typecode() {
+    use cdr::typecode::{Any, StructMember, TypeCode, UnionMember};
+    use cdr::Value;
+
+    let node = TypeCode::Struct {
+        id: "IDL:N:1.0".to_string(),
+        name: "N".to_string(),
+        members: vec![
+            StructMember::new("v", TypeCode::Long),
+            StructMember::new(
+                "c",
+                TypeCode::Sequence(Box::new(TypeCode::Recursive("IDL:N:1.0".to_string())), 0),
+            ),
+        ],
+    };
+    let encoded = cdr::serialize::<_, _, CdrBe>(&node, Infinite).unwrap();
+    assert_eq!(
+        &encoded[4..],
+        &[
+            0, 0, 0, 15, // tk_struct
+            0, 0, 0, 76, // encapsulation length
+            0, 0, 0, 0, // big endian, padding
+            0, 0, 0, 10, b'I', b'D', b'L', b':', b'N', b':', b'1', b'.', b'0', 0, 0, 0, // id
+            0, 0, 0, 2, b'N', 0, 0, 0, // name
+            0, 0, 0, 2, // member count
+            0, 0, 0, 2, b'v', 0, 0, 0, 0, 0, 0, 3, // v: long
+            0, 0, 0, 2, b'c', 0, 0, 0, 0, 0, 0, 19, // c: sequence
+            0, 0, 0, 16, // encapsulation length
+            0, 0, 0, 0, // big endian, padding
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xb4, // indirection to offset 0
+            0, 0, 0, 0, // bound
+        ][..]
+    );
+    assert_eq!(cdr::deserialize::<TypeCode>(&encoded).unwrap(), node);
+
+    let leaf = Value::Struct(vec![Value::I32(2), Value::Sequence(vec![])]);
+    let tree = Value::Struct(vec![Value::I32(1), Value::Sequence(vec![leaf])]);
+    let any = Any::new(node.clone(), tree).unwrap();
+    let encoded = cdr::serialize::<_, _, CdrLe>(&any, Infinite).unwrap();
+    assert_eq!(
+        &encoded[88..],
+        &[1, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(cdr::deserialize::<Any>(&encoded).unwrap(), any);
+    assert!(Any::new(node.clone(), Value::I32(1)).is_err());
+
+    // union U switch (Color) { case Red: long l; default: string s; };
+    let color = TypeCode::Enum {
+        id: "IDL:Color:1.0".to_string(),
+        name: "Color".to_string(),
+        members: vec!["Red".to_string(), "Green".to_string()],
+    };
+    let union = TypeCode::Union {
+        id: "IDL:U:1.0".to_string(),
+        name: "U".to_string(),
+        discriminator: Box::new(color.clone()),
+        default_index: 1,
+        members: vec![
+            UnionMember {
+                label: 0,
+                name: "l".to_string(),
+                ty: TypeCode::Long,
+            },
+            UnionMember {
+                label: 0,
+                name: "s".to_string(),
+                ty: TypeCode::String(8),
+            },
+        ],
+    };
+    let ty = TypeCode::Alias {
+        id: "IDL:Pair:1.0".to_string(),
+        name: "Pair".to_string(),
+        content: Box::new(TypeCode::Array(Box::new(union), 2)),
+    };
+    let value = Value::Array(vec![
+        Value::Union {
+            discriminator: Box::new(Value::Union {
+                discriminator: Box::new(Value::U32(0)),
+                value: Box::new(Value::Unit),
+            }),
+            value: Box::new(Value::I32(-1)),
+        },
+        Value::Union {
+            discriminator: Box::new(Value::Union {
+                discriminator: Box::new(Value::U32(1)),
+                value: Box::new(Value::Unit),
+            }),
+            value: Box::new(Value::String("green".to_string())),
+        },
+    ]);
+    let any = Any::new(ty.clone(), value).unwrap();
+    let encoded = cdr::serialize::<_, _, CdrBe>(&any, Infinite).unwrap();
+    assert_eq!(cdr::deserialize::<Any>(&encoded).unwrap(), any);
+
+    let long_string = Value::Union {
+        discriminator: Box::new(Value::Union {
+            discriminator: Box::new(Value::U32(1)),
+            value: Box::new(Value::Unit),
+        }),
+        value: Box::new(Value::String("too long for the bound".to_string())),
+    };
+    assert!(Any::new(ty, Value::Array(vec![long_string.clone(), long_string])).is_err());
+    let any = Any::new(
+        color,
+        Value::Union {
+            discriminator: Box::new(Value::U32(1)),
+            value: Box::new(Value::Unit),
+        },
+    )
+    .unwrap();
+    let encoded = cdr::serialize::<_, _, CdrLe>(&any, Infinite).unwrap();
+    assert_eq!(&encoded[encoded.len() - 4..], &[1, 0, 0, 0]);
+    assert_eq!(cdr::deserialize::<Any>(&encoded).unwrap(), any);
+
+    // union S switch (short) { case -1: case 2: octet o; };
+    let union = TypeCode::Union {
+        id: "IDL:S:1.0".to_string(),
+        name: "S".to_string(),
+        discriminator: Box::new(TypeCode::Short),
+        default_index: -1,
+        members: vec![
+            UnionMember {
+                label: -1,
+                name: "o".to_string(),
+                ty: TypeCode::Octet,
+            },
+            UnionMember {
+                label: 2,
+                name: "o".to_string(),
+                ty: TypeCode::Octet,
+            },
+        ],
+    };
+    let value = Value::Union {
+        discriminator: Box::new(Value::I16(-1)),
+        value: Box::new(Value::U8(7)),
+    };
+    let any = Any::new(union.clone(), value).unwrap();
+    let encoded = cdr::serialize::<_, _, CdrLe>(&any, Infinite).unwrap();
+    assert_eq!(&encoded[encoded.len() - 3..], &[0xff, 0xff, 7]);
+    assert_eq!(cdr::deserialize::<Any>(&encoded).unwrap(), any);
+    let value = Value::Union {
+        discriminator: Box::new(Value::I16(1)),
+        value: Box::new(Value::U8(7)),
+    };
+    assert!(Any::new(union, value).is_err());
+
+    // Encapsulations carry their own byte order.
+    let encoded = [
+        0, 0, 0, 0, // CDR_BE
+        0, 0, 0, 21, // tk_alias
+        0, 0, 0, 24, // encapsulation length
+        1, 0, 0, 0, // little endian, padding
+        2, 0, 0, 0, b'A', 0, 0, 0, // id
+        2, 0, 0, 0, b'a', 0, 0, 0, // name
+        5, 0, 0, 0, // tk_ulong
+    ];
+    assert_eq!(
+        cdr::deserialize::<TypeCode>(&encoded).unwrap(),
+        TypeCode::Alias {
+            id: "A".to_string(),
+            name: "a".to_string(),
+            content: Box::new(TypeCode::ULong),
+        }
+    );
+
+    // Recursive TypeCodes need an enclosing type.
+    let unenclosed = TypeCode::Sequence(Box::new(TypeCode::Recursive("IDL:N:1.0".to_string())), 0);
+    assert!(cdr::serialize::<_, _, CdrBe>(&unenclosed, Infinite).is_err());
+
+    // Values of recursive types are nested to a limit.
+    let nested = |depth: usize| {
+        let mut encoded = cdr::serialize::<_, _, CdrBe>(&node, Infinite).unwrap();
+        for i in 1..=depth {
+            // v, then one child except at the deepest node
+            encoded.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, u8::from(i < depth)]);
+        }
+        encoded
+    };
+    assert!(cdr::deserialize::<Any>(&nested(100)).is_ok());
+    assert!(cdr::deserialize::<Any>(&nested(200_000)).is_err());
+    let mut deep = Value::Struct(vec![Value::I32(0), Value::Sequence(vec![])]);
+    for _ in 0..1000 {
+        deep = Value::Struct(vec![Value::I32(0), Value::Sequence(vec![deep])]);
+    }
+    assert!(Any::new(node, deep).is_err());
+}
+
+#[test]
+fn test_encapsulated() {
+    use cdr::{Deserializer, Serializer};
+    use serde::{Deserialize, Serialize};
+
+    let mut buf = Vec::new();
+    let mut serializer = Serializer::<_, BigEndian>::new(&mut buf);
+    1u8.serialize(&mut serializer).unwrap();
+    serializer.serialize_encapsulated(&(7u32, 1.0f64)).unwrap();
+    2u16.serialize(&mut serializer).unwrap();
+    assert_eq!(serializer.position(), 26);
+    assert_eq!(
+        buf,
+        vec![
+            1, 0, 0, 0, // octet, padding
+            0, 0, 0, 16, // length
+            0, 0, 0, 0, 0, 0, 0, 7, // big endian, padding, u32
+            0x3f, 0xf0, 0, 0, 0, 0, 0, 0, // f64 aligned to the encapsulation
+            0, 2, // u16
+        ]
+    );
+
+    let mut deserializer = Deserializer::<_, _, BigEndian>::new(&buf[..], Infinite);
+    assert_eq!(u8::deserialize(&mut deserializer).unwrap(), 1);
+    assert_eq!(
+        deserializer
+            .deserialize_encapsulated::<(u32, f64)>()
+            .unwrap(),
+        (7, 1.0)
+    );
+    assert_eq!(u16::deserialize(&mut deserializer).unwrap(), 2);
+
+    // Encapsulations may be in another byte order than their container.
+    let encapsulated =
+        cdr::ser::serialize_encapsulated::<_, LittleEndian>(&("ab".to_string(), 3u16)).unwrap();
+    assert_eq!(
+        encapsulated,
+        [1, 0, 0, 0, 3, 0, 0, 0, b'a', b'b', 0, 0, 3, 0]
+    );
+    let buf = cdr::ser::serialize_data::<_, _, BigEndian>(&encapsulated, Infinite).unwrap();
+    let mut deserializer = Deserializer::<_, _, BigEndian>::new(&buf[..], Infinite);
+    assert_eq!(
+        deserializer
+            .deserialize_encapsulated::<(String, u16)>()
+            .unwrap(),
+        ("ab".to_string(), 3)
+    );
+
+    assert!(matches!(
+        cdr::de::deserialize_encapsulated::<u32>(&[2, 0, 0, 0, 1, 0, 0, 0]),
+        Err(Error::InvalidEncapsulation)
+    ));
+    assert!(matches!(
+        cdr::de::deserialize_encapsulated::<u32>(&[]),
+        Err(Error::Eof)
+    ));
+}
+
+#[test]
+fn test_ior() {
+    use cdr::giop::Version;
+    use cdr::ior::{IiopProfile, Ior, TaggedComponent, TaggedProfile};
+
+    let profile = IiopProfile {
+        version: Version::V1_0,
+        host: "h".to_string(),
+        port: 2809,
+        object_key: b"k".to_vec(),
+        components: Vec::new(),
+    };
+    let ior = Ior::new("IDL:A:1.0", vec![profile.to_tagged().unwrap()]);
+    let stringified = concat!(
+        "IOR:",
+        "00000000",                         // big endian, padding
+        "0000000a49444c3a413a312e30000000", // type_id, padding
+        "00000001",                         // profile count
+        "00000000",                         // TAG_INTERNET_IOP
+        "00000011",                         // profile_data length
+        "00010000",                         // big endian, version, padding
+        "000000026800",                     // host
+        "0af9",                             // port
+        "000000016b",                       // object_key
+    );
+    assert_eq!(ior.to_string(), stringified);
+    assert_eq!(stringified.parse::<Ior>().unwrap(), ior);
+    assert_eq!(
+        stringified
+            .to_uppercase()
+            .replace("IOR:", "ior:")
+            .parse::<Ior>()
+            .unwrap(),
+        ior
+    );
+    assert_eq!(ior.iiop_profiles().unwrap(), vec![profile]);
+
+    // IIOP 1.1 and later carry components, possibly in little-endian order.
+    let profile = IiopProfile {
+        components: vec![TaggedComponent {
+            tag: TaggedComponent::TAG_ORB_TYPE,
+            component_data: vec![0, 0, 0, 0, 0x41, 0x42, 0x43, 0x44],
+        }],
+        ..IiopProfile::new("10.0.0.1", 683, b"\x00\x01")
+    };
+    let ior = Ior::new(
+        "IDL:B:1.0",
+        vec![
+            TaggedProfile {
+                tag: TaggedProfile::TAG_INTERNET_IOP,
+                profile_data: cdr::ser::serialize_encapsulated::<_, LittleEndian>(&profile)
+                    .unwrap(),
+            },
+            TaggedProfile {
+                tag: TaggedProfile::TAG_MULTIPLE_COMPONENTS,
+                profile_data: cdr::ser::serialize_encapsulated::<_, BigEndian>(&profile.components)
+                    .unwrap(),
+            },
+        ],
+    );
+    let parsed: Ior = ior.to_string().parse().unwrap();
+    assert_eq!(parsed, ior);
+    assert_eq!(parsed.iiop_profiles().unwrap(), vec![profile.clone()]);
+    assert_eq!(parsed.profiles[1].components().unwrap(), profile.components);
+    assert!(parsed.profiles[1].iiop().is_err());
+
+    let profile = IiopProfile {
+        version: Version::V1_0,
+        ..profile
+    };
+    assert!(profile.to_tagged().is_err());
+
+    assert!(Ior::default().is_nil());
+    assert!("IOR:0".parse::<Ior>().is_err());
+    assert!("IOR:zz".parse::<Ior>().is_err());
+    assert!("corbaloc::h:2809/k".parse::<Ior>().is_err());
+    assert!(matches!("IOR:".parse::<Ior>(), Err(Error::Eof)));
+}
+
+#[cfg(feature = "xtypes")]
+#[test]
+fn test_xtypes() {
+    use cdr::xtypes::*;
+    use cdr::{deserialize, LittleEndian, Serializer};
+    use serde::{Deserialize, Serialize};
+
+    fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+        let mut bytes = vec![0, 7, 0, 0]; // CDR2_LE
+        let mut serializer = Serializer::<_, LittleEndian>::with_alignment(&mut bytes, 0, 4);
+        serializer.reset_pos();
+        value.serialize(&mut serializer).unwrap();
+        bytes
+    }
+
+    let minimal = TypeObject::Minimal(TypeDefinition::Struct(StructType {
+        flags: IS_APPENDABLE,
+        base_type: TypeIdentifier::Primitive(TK_NONE),
+        detail: (),
+        members: vec![StructMember {
+            member_id: 0,
+            flags: 0,
+            type_id: TypeIdentifier::Primitive(TK_INT32),
+            detail: name_hash("x"),
+        }],
+    }));
+    #[rustfmt::skip]
+    let bytes = [
+        0, 7, 0, 0,
+        35, 0, 0, 0, // DHEADER of TypeObject
+        EK_MINIMAL, TK_STRUCTURE,
+        2, 0, // IS_APPENDABLE
+        1, 0, 0, 0, TK_NONE, 0, 0, 0, // header with its base type
+        19, 0, 0, 0, 1, 0, 0, 0, // DHEADER and length of the members
+        11, 0, 0, 0, // DHEADER of MinimalStructMember
+        0, 0, 0, 0, 0, 0, TK_INT32, // CommonStructMember
+        0x9d, 0xd4, 0xe4, 0x61, // MD5("x")
+    ];
+    assert_eq!(encode(&minimal), bytes);
+    assert_eq!(deserialize::<TypeObject>(&bytes).unwrap(), minimal);
+
+    let id = minimal.type_identifier().unwrap();
+    let with_size = TypeIdentifierWithSize::new(&minimal).unwrap();
+    assert_eq!(with_size.type_id, id);
+    assert_eq!(with_size.typeobject_serialized_size, 39);
+    // The first 14 bytes of the MD5 hash of the bytes above.
+    #[rustfmt::skip]
+    let minimal_hash = [
+        0xdf, 0x57, 0xd6, 0x2c, 0x1d, 0x3b, 0xe0, 0xd3, 0x00, 0x21, 0x55, 0xe5, 0xd2, 0x1f,
+    ];
+    assert_eq!(id, TypeIdentifier::Minimal(minimal_hash));
+    assert_eq!(member_id_hash("x"), 0x01e4_d49d);
+
+    let sequence = TypeIdentifier::PlainSequence {
+        header: PlainCollectionHeader {
+            equiv_kind: EK_BOTH,
+            element_flags: 0,
+        },
+        bound: 0,
+        element: Box::new(TypeIdentifier::String8(300)),
+    };
+    assert_eq!(
+        encode(&sequence)[4..],
+        [0x80, EK_BOTH, 0, 0, 0, 0x71, 0, 0, 44, 1, 0, 0]
+    );
+
+    // Plain collections nested in one another are read up to a limit.
+    let nested = |depth| {
+        (0..depth).fold(TypeIdentifier::Primitive(TK_INT32), |element, _| {
+            TypeIdentifier::PlainSequence {
+                header: PlainCollectionHeader {
+                    equiv_kind: EK_BOTH,
+                    element_flags: 0,
+                },
+                bound: 0,
+                element: Box::new(element),
+            }
+        })
+    };
+    assert_eq!(
+        deserialize::<TypeIdentifier>(&encode(&nested(100))).unwrap(),
+        nested(100)
+    );
+    assert!(deserialize::<TypeIdentifier>(&encode(&nested(200))).is_err());
+
+    // StronglyConnectedComponentId is appendable, so it follows a DHEADER.
+    // The bytes are laid out by hand from the IDL of Annex B; they are not a
+    // capture from another implementation.
+    let scc = TypeIdentifier::StronglyConnectedComponent {
+        equiv_kind: EK_MINIMAL,
+        hash: [0xab; 14],
+        scc_length: 2,
+        scc_index: 1,
+    };
+    let mut bytes = vec![0, 7, 0, 0, 0xb0, 0, 0, 0, 24, 0, 0, 0, EK_MINIMAL];
+    bytes.extend_from_slice(&[0xab; 14]);
+    bytes.extend_from_slice(&[0, 2, 0, 0, 0, 1, 0, 0, 0]);
+    assert_eq!(encode(&scc), bytes);
+    assert_eq!(deserialize::<TypeIdentifier>(&bytes).unwrap(), scc);
+
+    let complete = TypeObject::Complete(TypeDefinition::Union(UnionType {
+        flags: IS_FINAL,
+        detail: CompleteTypeDetail::new("Shape"),
+        discriminator_flags: 0,
+        discriminator_type: TypeIdentifier::Primitive(TK_INT32),
+        members: vec![UnionMember {
+            member_id: 1,
+            flags: 0,
+            type_id: sequence.clone(),
+            labels: vec![1, 2],
+            detail: CompleteMemberDetail::new("names"),
+        }],
+    }));
+    let others = vec![
+        complete.clone(),
+        TypeObject::Complete(TypeDefinition::Enum(EnumType {
+            flags: 0,
+            bit_bound: 32,
+            detail: CompleteTypeDetail::new("Color"),
+            literals: vec![EnumLiteral {
+                value: 0,
+                flags: IS_DEFAULT,
+                detail: CompleteMemberDetail::new("RED"),
+            }],
+        })),
+        TypeObject::Minimal(TypeDefinition::Alias(AliasType {
+            flags: 0,
+            detail: (),
+            related_flags: 0,
+            related_type: TypeIdentifier::String16(0),
+        })),
+        TypeObject::Complete(TypeDefinition::Sequence(SequenceType {
+            flags: 0,
+            bound: 4,
+            detail: None,
+            element_flags: 0,
+            element_type: id.clone(),
+        })),
+        TypeObject::Complete(TypeDefinition::Array(ArrayType {
+            flags: 0,
+            bounds: vec![2, 3],
+            detail: CompleteTypeDetail::new("Matrix"),
+            element_flags: 0,
+            element_type: id.clone(),
+        })),
+        TypeObject::Minimal(TypeDefinition::Map(MapType {
+            flags: 0,
+            bound: 0,
+            detail: None,
+            key_flags: 0,
+            key_type: TypeIdentifier::String8(0),
+            element_flags: 0,
+            element_type: id.clone(),
+        })),
+    ];
+    for object in &others {
+        assert_eq!(&deserialize::<TypeObject>(&encode(object)).unwrap(), object);
+        assert_ne!(object.type_identifier().unwrap(), id);
+    }
+    let map_detail = TypeObject::Minimal(TypeDefinition::Map(MapType {
+        flags: 0,
+        bound: 0,
+        detail: Some(()),
+        key_flags: 0,
+        key_type: TypeIdentifier::String8(0),
+        element_flags: 0,
+        element_type: id.clone(),
+    }));
+    assert!(map_detail.type_identifier().is_err());
+
+    // A member appended to TypeIdentifierWithSize by a newer peer is skipped.
+    #[rustfmt::skip]
+    let appended = [
+        0, 7, 0, 0,
+        12, 0, 0, 0, TK_INT32, 0, 0, 0, 47, 0, 0, 0, 0xff, 0xff, 0xff, 0xff,
+        TK_INT16,
+    ];
+    assert_eq!(
+        deserialize::<(TypeIdentifierWithSize, TypeIdentifier)>(&appended).unwrap(),
+        (
+            TypeIdentifierWithSize {
+                type_id: TypeIdentifier::Primitive(TK_INT32),
+                typeobject_serialized_size: 47,
+            },
+            TypeIdentifier::Primitive(TK_INT16),
+        )
+    );
+
+    let dependencies = |with_size: TypeIdentifierWithSize| TypeIdentifierWithDependencies {
+        typeid_with_size: with_size,
+        dependent_typeid_count: 0,
+        dependent_typeids: Vec::new(),
+    };
+    let information = TypeInformation {
+        minimal: dependencies(with_size.clone()),
+        complete: dependencies(TypeIdentifierWithSize::new(&complete).unwrap()),
+    };
+    let encoded = encode(&information);
+    assert_eq!(encoded[8..12], [0x01, 0x10, 0, 0x40]); // EMHEADER with LC 4
+    assert_eq!(
+        deserialize::<TypeInformation>(&encoded).unwrap(),
+        information
+    );
+
+    // Unknown members are skipped unless they must be understood.
+    let mut extended = encoded.clone();
+    extended.extend_from_slice(&[0x03, 0, 0, 0x40, 4, 0, 0, 0, 7, 0, 0, 0]);
+    let size = u32::from_le_bytes([encoded[4], encoded[5], encoded[6], encoded[7]]) + 12;
+    extended[4..8].copy_from_slice(&size.to_le_bytes());
+    assert_eq!(
+        deserialize::<TypeInformation>(&extended).unwrap(),
+        information
+    );
+    extended[11] |= 0x80;
+    assert!(deserialize::<TypeInformation>(&extended).is_ok());
+    let last = extended.len() - 9;
+    extended[last] |= 0x80;
+    assert!(deserialize::<TypeInformation>(&extended).is_err());
+
+    // The complete form of `minimal`, the structure `Point { long x; }`.
+    let point = TypeObject::Complete(TypeDefinition::Struct(StructType {
+        flags: IS_APPENDABLE,
+        base_type: TypeIdentifier::Primitive(TK_NONE),
+        detail: CompleteTypeDetail::new("Point"),
+        members: vec![StructMember {
+            member_id: 0,
+            flags: 0,
+            type_id: TypeIdentifier::Primitive(TK_INT32),
+            detail: CompleteMemberDetail::new("x"),
+        }],
+    }));
+    #[rustfmt::skip]
+    let bytes = [
+        0, 7, 0, 0,
+        52, 0, 0, 0, // DHEADER of TypeObject
+        EK_COMPLETE, TK_STRUCTURE,
+        2, 0, // IS_APPENDABLE
+        14, 0, 0, 0, TK_NONE, // DHEADER of the header and its base type
+        0, 0, 0, // absent annotations and padding
+        6, 0, 0, 0, b'P', b'o', b'i', b'n', b't', 0, 0, 0,
+        24, 0, 0, 0, 1, 0, 0, 0, // DHEADER and length of the members
+        16, 0, 0, 0, // DHEADER of CompleteStructMember
+        0, 0, 0, 0, 0, 0, TK_INT32, 0, // CommonStructMember
+        2, 0, 0, 0, b'x', 0, // name
+        0, 0, // absent annotations
+    ];
+    assert_eq!(encode(&point), bytes);
+    assert_eq!(deserialize::<TypeObject>(&bytes).unwrap(), point);
+    #[rustfmt::skip]
+    let complete_hash = [
+        0x65, 0xe5, 0x29, 0x66, 0x7b, 0x1a, 0x94, 0xf5, 0xe9, 0x17, 0xcf, 0x0f, 0x43, 0xef,
+    ];
+    assert_eq!(
+        point.type_identifier().unwrap(),
+        TypeIdentifier::Complete(complete_hash)
+    );
+
+    // PID_TYPE_INFORMATION of an endpoint of `Point`, as sent in discovery.
+    let mut blob = vec![
+        0, 3, 0, 0, // PL_CDR_LE
+        0x75, 0, 100, 0, // PID_TYPE_INFORMATION
+        96, 0, 0, 0, // DHEADER of TypeInformation
+    ];
+    for &(member_id, kind, hash, size) in &[
+        (0x01, EK_MINIMAL, &minimal_hash, 39),
+        (0x02, EK_COMPLETE, &complete_hash, 56),
+    ] {
+        blob.extend_from_slice(&[member_id, 0x10, 0, 0x40, 40, 0, 0, 0]); // EMHEADER, NEXTINT
+        blob.extend_from_slice(&[36, 0, 0, 0, 20, 0, 0, 0, kind]); // DHEADERs
+        blob.extend_from_slice(hash);
+        blob.extend_from_slice(&[0, size, 0, 0, 0]);
+        blob.extend_from_slice(&[0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0]); // no dependencies
+    }
+    blob.extend_from_slice(&[1, 0, 0, 0]); // PID_SENTINEL
+    let information = TypeInformation {
+        minimal: dependencies(TypeIdentifierWithSize::new(&minimal).unwrap()),
+        complete: dependencies(TypeIdentifierWithSize::new(&point).unwrap()),
+    };
+    let list = cdr::parameter_list::ParameterList::from_bytes(&blob).unwrap();
+    let value = &list
+        .get(cdr::parameter_list::ParameterId(0x0075))
+        .unwrap()
+        .value;
+    assert_eq!(encode(&information)[4..], value[..]);
+    let mut deserializer =
+        cdr::Deserializer::<_, _, LittleEndian>::with_alignment(&value[..], Infinite, 0, 4);
+    assert_eq!(
+        TypeInformation::deserialize(&mut deserializer).unwrap(),
+        information
+    );
+}
+
+#[cfg(feature = "ros2")]
+#[test]
+fn test_ros2() {
+    use cdr::ros2::{self, Duration, Empty, Interfaces, ServiceHeader, Time};
+    use cdr::value::{self, Member, StructDescriptor};
+    use cdr::{deserialize, serialize, CdrLe, Infinite, TypeDescriptor, Value};
+
+    // std_msgs/msg/String
+    let string = [
+        0, 1, 0, 0, 12, 0, 0, 0, b'h', b'e', b'l', b'l', b'o', b' ', b'w', b'o', b'r', b'l', b'd',
+        0,
+    ];
+    assert_eq!(
+        serialize::<_, _, CdrLe>(&"hello world", Infinite).unwrap(),
+        string
+    );
+    // std_msgs/msg/Empty
+    let empty = [0, 1, 0, 0, 0];
+    assert_eq!(serialize::<_, _, CdrLe>(&Empty, Infinite).unwrap(), empty);
+    assert_eq!(deserialize::<Empty>(&empty).unwrap(), Empty);
+    // std_msgs/msg/Header
+    #[rustfmt::skip]
+    let header = [
+        0, 1, 0, 0,
+        0, 241, 83, 101, 0xf4, 1, 0, 0,
+        10, 0, 0, 0, b'b', b'a', b's', b'e', b'_', b'l', b'i', b'n', b'k', 0,
+    ];
+    let stamp = Time::new(1_700_000_000, 500);
+    assert_eq!(
+        serialize::<_, _, CdrLe>(&(stamp, "base_link"), Infinite).unwrap(),
+        header
+    );
+    assert_eq!(
+        deserialize::<(Time, String)>(&header).unwrap(),
+        (stamp, "base_link".to_string())
+    );
+    assert_eq!(cdr::max_serialized_size::<Time>(), Some(12));
+
+    assert_eq!(
+        Time::from_nanos(1_500_000_000),
+        Some(Time::new(1, 500_000_000))
+    );
+    assert_eq!(
+        Duration::from_nanos(-1),
+        Some(Duration::new(-1, 999_999_999))
+    );
+    assert_eq!(Duration::new(-1, 999_999_999).as_nanos(), -1);
+    assert_eq!(Time::from_nanos(i64::MAX), None);
+
+    assert_eq!(ros2::topic_name("/chatter"), "rt/chatter");
+    assert_eq!(ros2::request_topic_name("/add"), "rq/addRequest");
+    assert_eq!(ros2::reply_topic_name("/add"), "rr/addReply");
+    assert_eq!(
+        ros2::dds_type_name("std_msgs/msg/String").unwrap(),
+        "std_msgs::msg::dds_::String_"
+    );
+    assert_eq!(ros2::dds_type_name("String"), None);
+
+    let mut interfaces = Interfaces::new();
+    let ty = interfaces.get("std_msgs/Header").unwrap().clone();
+    assert_eq!(
+        value::deserialize(&header, &ty).unwrap(),
+        Value::Struct(vec![
+            Value::Struct(vec![Value::I32(1_700_000_000), Value::U32(500)]),
+            Value::String("base_link".to_string()),
+        ])
+    );
+    let ty = interfaces
+        .add_msg("std_msgs", "Empty", "# Nothing.\n")
+        .unwrap();
+    assert_eq!(
+        value::deserialize(&empty, ty).unwrap(),
+        Value::Struct(vec![Value::U8(0)])
+    );
+
+    let ty = interfaces
+        .add_msg(
+            "demo",
+            "Sample",
+            r#"
+            int32 MAX=4 # constants take no space
+            string GREETING = "hi # there"
+            float64[3] position
+            int32[<=4] readings  # bounded
+            string<=10 label "a=b" # not a constant
+            uint8[] data
+            builtin_interfaces/Duration age
+            "#,
+        )
+        .unwrap()
+        .clone();
+    assert_eq!(
+        ty,
+        TypeDescriptor::Struct(StructDescriptor {
+            name: "demo::msg::dds_::Sample_".to_string(),
+            members: vec![
+                Member::new(
+                    "position",
+                    TypeDescriptor::Array(Box::new(TypeDescriptor::F64), 3)
+                ),
+                Member::new(
+                    "readings",
+                    TypeDescriptor::BoundedSequence(Box::new(TypeDescriptor::I32), 4)
+                ),
+                Member::new("label", TypeDescriptor::BoundedString(10)),
+                Member::new(
+                    "data",
+                    TypeDescriptor::Sequence(Box::new(TypeDescriptor::U8))
+                ),
+                Member::new(
+                    "age",
+                    interfaces
+                        .get("builtin_interfaces/msg/Duration")
+                        .unwrap()
+                        .clone()
+                ),
+            ],
+        })
+    );
+    let sample = (
+        [1.0, 2.0, 3.0],
+        vec![7i32],
+        "lidar",
+        vec![1u8, 2],
+        Duration::new(2, 0),
+    );
+    let encoded = serialize::<_, _, CdrLe>(&sample, Infinite).unwrap();
+    let decoded = value::deserialize(&encoded, &ty).unwrap();
+    assert_eq!(
+        value::serialize::<_, CdrLe>(&decoded, &ty, Infinite).unwrap(),
+        encoded
+    );
+
+    // example_interfaces/srv/AddTwoInts as sent by rmw_cyclonedds.
+    interfaces
+        .add_srv(
+            "example_interfaces",
+            "AddTwoInts",
+            "int64 a\nint64 b\n---\nint64 sum\n",
+        )
+        .unwrap();
+    #[rustfmt::skip]
+    let request = [
+        0, 1, 0, 0,
+        1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 0, 0, 0, 0,
+        2, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let service_header = ServiceHeader {
+        guid: 0x0807_0605_0403_0201,
+        sequence_number: 9,
+    };
+    assert_eq!(
+        serialize::<_, _, CdrLe>(&(service_header, 2i64, 3i64), Infinite).unwrap(),
+        request
+    );
+    let ty = interfaces
+        .get("example_interfaces/srv/AddTwoInts_Request")
+        .unwrap();
+    assert_eq!(
+        value::deserialize(
+            &request,
+            &TypeDescriptor::Struct(StructDescriptor {
+                name: "Request".to_string(),
+                members: vec![
+                    Member::new(
+                        "header",
+                        TypeDescriptor::Struct(StructDescriptor {
+                            name: "Header".to_string(),
+                            members: vec![
+                                Member::new("guid", TypeDescriptor::U64),
+                                Member::new("seq", TypeDescriptor::I64),
+                            ],
+                        })
+                    ),
+                    Member::new("request", ty.clone()),
+                ],
+            })
+        )
+        .unwrap(),
+        Value::Struct(vec![
+            Value::Struct(vec![Value::U64(0x0807_0605_0403_0201), Value::I64(9)]),
+            Value::Struct(vec![Value::I64(2), Value::I64(3)]),
+        ])
+    );
+    assert!(interfaces
+        .get("example_interfaces/srv/AddTwoInts_Response")
+        .is_some());
+
+    // A std_msgs/msg/Header sample padded to a multiple of 4 bytes, with the
+    // number of padding bytes in the encapsulation options (DDS-XTypes 1.3,
+    // 7.6.3.1.2).
+    #[rustfmt::skip]
+    let padded = [
+        0, 1, 0, 2,
+        0, 241, 83, 101, 0xf4, 1, 0, 0,
+        10, 0, 0, 0, b'b', b'a', b's', b'e', b'_', b'l', b'i', b'n', b'k', 0,
+        0, 0,
+    ];
+    assert_eq!(
+        deserialize::<(Time, String)>(&padded).unwrap(),
+        (stamp, "base_link".to_string())
+    );
+    let ty = interfaces.get("std_msgs/msg/Header").unwrap();
+    assert_eq!(
+        value::deserialize(&padded, ty).unwrap(),
+        value::deserialize(&header, ty).unwrap()
+    );
+
+    // A bare `Header` is the package's own before `std_msgs/msg/Header`.
+    let ty = interfaces
+        .add_msg("demo", "Stamped", "Header header\n")
+        .unwrap()
+        .clone();
+    assert_eq!(
+        ty,
+        TypeDescriptor::Struct(StructDescriptor {
+            name: "demo::msg::dds_::Stamped_".to_string(),
+            members: vec![Member::new(
+                "header",
+                interfaces.get("std_msgs/msg/Header").unwrap().clone()
+            )],
+        })
+    );
+    let local = interfaces
+        .add_msg("sensor", "Header", "uint32 seq\n")
+        .unwrap()
+        .clone();
+    let ty = interfaces
+        .add_msg("sensor", "Stamped", "Header header\n")
+        .unwrap();
+    assert_eq!(
+        *ty,
+        TypeDescriptor::Struct(StructDescriptor {
+            name: "sensor::msg::dds_::Stamped_".to_string(),
+            members: vec![Member::new("header", local)],
+        })
+    );
+
+    let error = interfaces
+        .add_msg("demo", "Bad", "int32 a\n  Unknown b\n")
+        .unwrap_err();
+    assert_eq!((error.line(), error.column()), (2, 3));
+    let error = interfaces
+        .add_msg("demo", "Bad", "wstring w\n")
+        .unwrap_err();
+    assert_eq!(error.to_string(), "1:1: wstring is not supported");
+    let error = interfaces
+        .add_msg("demo", "Bad", "int32 a\nint32 a\n")
+        .unwrap_err();
+    assert_eq!((error.line(), error.column()), (2, 7));
+    let error = interfaces
+        .add_srv("demo", "Bad", "int32 a\n---\nfloat64[x] b\n")
+        .unwrap_err();
+    assert_eq!(error.line(), 3);
+    assert!(interfaces.add_srv("demo", "Bad", "int32 a\n").is_err());
+}
+
+#[cfg(feature = "mcap")]
+#[test]
+fn test_mcap() {
+    use cdr::mcap::{Message, Reader, Schema, Writer, MAGIC};
+    use cdr::ros2::Time;
+    use cdr::Value;
+    use std::collections::BTreeMap;
+
+    let mut writer = Writer::new(Vec::new(), "ros2").unwrap();
+    writer.set_chunk_size(64);
+    // The dependencies of a type follow it, in no particular order.
+    let pose = writer
+        .add_schema(
+            "demo/msg/Pose",
+            "ros2msg",
+            concat!(
+                "std_msgs/Header header\n",
+                "Point position\n",
+                "================================================================================\n",
+                "MSG: demo/Point\n",
+                "Vector3 vector\n",
+                "================================================================================\n",
+                "MSG: demo/Vector3\n",
+                "float64 x\nfloat64 y\nfloat64 z\n",
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    let count = writer
+        .add_schema(
+            "demo/msg/Count",
+            "ros2idl",
+            concat!(
+                "================================================================================\n",
+                "IDL: demo/msg/Count\n",
+                "module demo { module msg { struct Count { demo::msg::Unit unit; uint32 value; }; }; };\n",
+                "================================================================================\n",
+                "IDL: demo/msg/Unit\n",
+                "module demo { module msg { struct Unit { string name; }; }; };\n",
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    let mut metadata = BTreeMap::new();
+    metadata.insert("offered_qos_profiles".to_string(), String::new());
+    let poses = writer
+        .add_channel(pose, "/pose", "cdr", metadata.clone())
+        .unwrap();
+    let counts = writer
+        .add_channel(count, "/count", "cdr", BTreeMap::new())
+        .unwrap();
+    for i in 0..3u32 {
+        let stamp = Time::new(i as i32, 0);
+        writer
+            .write_sample(
+                poses,
+                i,
+                u64::from(i) * 10,
+                u64::from(i) * 10,
+                &((stamp, "map"), (f64::from(i), 0.0, 1.0)),
+            )
+            .unwrap();
+        writer
+            .write_sample(counts, i, u64::from(i) * 10 + 5, 0, &(("m",), i))
+            .unwrap();
+    }
+    assert!(writer
+        .add_channel(9, "/bad", "cdr", BTreeMap::new())
+        .is_err());
+    assert!(writer.write_sample(9, 0, 0, 0, &0u8).is_err());
+    let file = writer.finish().unwrap();
+
+    #[rustfmt::skip]
+    let start = [
+        0x89, b'M', b'C', b'A', b'P', b'0', b'\r', b'\n',
+        0x01, 18, 0, 0, 0, 0, 0, 0, 0,
+        4, 0, 0, 0, b'r', b'o', b's', b'2',
+        6, 0, 0, 0, b'c', b'd', b'r', b'-', b'r', b's',
+    ];
+    assert_eq!(file[..start.len()], start);
+    assert_eq!(file[file.len() - 8..], MAGIC);
+
+    let mut reader = Reader::new(&file[..]).unwrap();
+    assert_eq!(reader.profile(), "ros2");
+    let messages: Vec<Message> = reader.by_ref().collect::<cdr::Result<_>>().unwrap();
+    assert_eq!(messages.len(), 6);
+    assert_eq!(
+        messages.iter().map(|m| m.log_time).collect::<Vec<_>>(),
+        [0, 5, 10, 15, 20, 25]
+    );
+    assert_eq!(reader.channel(poses).unwrap().topic, "/pose");
+    assert_eq!(reader.channel(poses).unwrap().metadata, metadata);
+    assert_eq!(reader.schema(count).unwrap().encoding, "ros2idl");
+    assert_eq!(
+        messages[2]
+            .deserialize::<((Time, String), (f64, f64, f64))>()
+            .unwrap(),
+        ((Time::new(1, 0), "map".to_string()), (1.0, 0.0, 1.0))
+    );
+    assert_eq!(
+        reader.decode(&messages[2]).unwrap(),
+        Value::Struct(vec![
+            Value::Struct(vec![
+                Value::Struct(vec![Value::I32(1), Value::U32(0)]),
+                Value::String("map".to_string()),
+            ]),
+            Value::Struct(vec![Value::Struct(vec![
+                Value::F64(1.0),
+                Value::F64(0.0),
+                Value::F64(1.0),
+            ])]),
+        ])
+    );
+    assert_eq!(
+        reader.decode(&messages[5]).unwrap(),
+        Value::Struct(vec![
+            Value::Struct(vec![Value::String("m".to_string())]),
+            Value::U32(2),
+        ])
+    );
+
+    // A file without chunks or a summary.
+    #[rustfmt::skip]
+    let unchunked = [
+        0x89, b'M', b'C', b'A', b'P', b'0', b'\r', b'\n',
+        0x01, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0x04, 21, 0, 0, 0, 0, 0, 0, 0,
+        3, 0, 0, 0, 2, 0, 0, 0, b'/', b'a', 3, 0, 0, 0, b'c', b'd', b'r', 0, 0, 0, 0,
+        0x05, 30, 0, 0, 0, 0, 0, 0, 0,
+        3, 0, 7, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0,
+        0, 1, 0, 0, 42, 0, 0, 0,
+        0x02, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0x89, b'M', b'C', b'A', b'P', b'0', b'\r', b'\n',
+    ];
+    let mut reader = Reader::new(&unchunked[..]).unwrap();
+    let message = reader.next().unwrap().unwrap();
+    assert_eq!(
+        (
+            message.channel_id,
+            message.sequence,
+            message.log_time,
+            message.publish_time
+        ),
+        (3, 7, 1, 2)
+    );
+    assert_eq!(message.deserialize::<u32>().unwrap(), 42);
+    assert!(reader.decode(&message).is_err());
+    assert!(reader.next().is_none());
+
+    // Chunks compressed with zstd and lz4.
+    let le_u64 = |bytes: &[u8]| {
+        let mut n = [0; 8];
+        n.copy_from_slice(&bytes[..8]);
+        u64::from_le_bytes(n) as usize
+    };
+    let recompress = |compression: &str, compress: &dyn Fn(&[u8]) -> Vec<u8>| {
+        let mut recompressed = file[..8].to_vec();
+        let mut pos = 8;
+        loop {
+            let opcode = file[pos];
+            let end = pos + 9 + le_u64(&file[pos + 1..]);
+            if opcode == 0x06 {
+                let content = &file[pos + 9..end];
+                let records = &content[28 + 4 + content[28] as usize + 8..];
+                let compressed = compress(records);
+                let mut chunk = content[..28].to_vec(); // times, size and CRC
+                chunk.extend_from_slice(&(compression.len() as u32).to_le_bytes());
+                chunk.extend_from_slice(compression.as_bytes());
+                chunk.extend_from_slice(&(compressed.len() as u64).to_le_bytes());
+                chunk.extend_from_slice(&compressed);
+                recompressed.push(opcode);
+                recompressed.extend_from_slice(&(chunk.len() as u64).to_le_bytes());
+                recompressed.extend_from_slice(&chunk);
+            } else {
+                recompressed.extend_from_slice(&file[pos..end]);
+            }
+            pos = end;
+            if opcode == 0x02 {
+                break;
+            }
+        }
+        recompressed.extend_from_slice(&file[pos..]);
+        recompressed
+    };
+    let zstd = recompress("zstd", &|records| {
+        ruzstd::encoding::compress_to_vec(records, ruzstd::encoding::CompressionLevel::Fastest)
+    });
+    let lz4 = recompress("lz4", &|records| {
+        let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+        std::io::Write::write_all(&mut encoder, records).unwrap();
+        encoder.finish().unwrap()
+    });
+    for compressed in &[&zstd, &lz4] {
+        assert_ne!(compressed[..], file[..]);
+        let read: Vec<Message> = Reader::new(&compressed[..])
+            .unwrap()
+            .collect::<cdr::Result<_>>()
+            .unwrap();
+        assert_eq!(read, messages);
+    }
+    // The first chunk claims one byte more than it decompresses to.
+    let mut truncated = zstd.clone();
+    let chunk = 8 + 9 + le_u64(&truncated[9..]); // past the magic and the header
+    assert_eq!(truncated[chunk], 0x06);
+    truncated[chunk + 9 + 16] += 1;
+    assert!(Reader::new(&truncated[..])
+        .unwrap()
+        .any(|message| message.is_err()));
+
+    // Chunks with an unknown compression, and corrupted chunks.
+    #[rustfmt::skip]
+    let compressed = [
+        0x89, b'M', b'C', b'A', b'P', b'0', b'\r', b'\n',
+        0x01, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0x06, 44, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 4, 0, 0, 0, b'l', b'z', b'm', b'a', 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let mut reader = Reader::new(&compressed[..]).unwrap();
+    assert_eq!(
+        reader.next().unwrap().unwrap_err().to_string(),
+        "unsupported chunk compression `lzma`"
+    );
+    assert!(reader.next().is_none());
+    let mut corrupted = file.clone();
+    let first = corrupted.windows(3).position(|w| w == b"map").unwrap();
+    corrupted[first] = b'n';
+    assert!(Reader::new(&corrupted[..])
+        .unwrap()
+        .any(|message| message.is_err()));
+
+    assert!(Reader::new(&b"not mcap"[..]).is_err());
+    let schema = Schema {
+        id: 1,
+        name: "demo/msg/Missing".to_string(),
+        encoding: "ros2msg".to_string(),
+        data: b"Unknown value\n".to_vec(),
+    };
+    assert!(schema.descriptor().is_err());
 }